@@ -0,0 +1,43 @@
+CREATE TABLE IF NOT EXISTS profiles (
+    id TEXT PRIMARY KEY,
+    data JSONB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS performance_records (
+    id BIGSERIAL PRIMARY KEY,
+    namespace TEXT NOT NULL,
+    data JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_performance_records_namespace
+    ON performance_records (namespace);
+
+CREATE TABLE IF NOT EXISTS reviews (
+    id BIGSERIAL PRIMARY KEY,
+    challenge_id TEXT NOT NULL,
+    rating INTEGER NOT NULL,
+    data JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_reviews_challenge_id ON reviews (challenge_id);
+
+CREATE TABLE IF NOT EXISTS coupons (
+    code TEXT PRIMARY KEY,
+    data JSONB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS chat_messages (
+    id BIGSERIAL PRIMARY KEY,
+    channel TEXT NOT NULL,
+    data JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_chat_messages_channel ON chat_messages (channel);
+
+CREATE TABLE IF NOT EXISTS presence (
+    id BIGSERIAL PRIMARY KEY,
+    namespace TEXT NOT NULL,
+    timestamp BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_presence_namespace ON presence (namespace, timestamp);
@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS claims (
+    id BIGINT PRIMARY KEY,
+    data JSONB NOT NULL
+);
@@ -0,0 +1,4 @@
+CREATE INDEX IF NOT EXISTS idx_claims_user ON claims ((data->>'user'));
+
+CREATE INDEX IF NOT EXISTS idx_coupon_redemptions_redeemed_by
+    ON coupon_redemptions ((data->>'redeemed_by'));
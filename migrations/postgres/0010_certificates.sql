@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS certificates (
+    id TEXT PRIMARY KEY,
+    data JSONB NOT NULL
+);
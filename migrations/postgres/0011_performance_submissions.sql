@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS performance_submissions (
+    id BIGSERIAL PRIMARY KEY,
+    namespace TEXT NOT NULL,
+    data JSONB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_performance_submissions_namespace
+    ON performance_submissions (namespace);
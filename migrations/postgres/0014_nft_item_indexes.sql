@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS nft_item_indexes (
+    collection TEXT PRIMARY KEY,
+    next_index BIGINT NOT NULL
+);
@@ -0,0 +1,33 @@
+CREATE TABLE IF NOT EXISTS performance_ranks (
+    namespace TEXT NOT NULL,
+    player TEXT NOT NULL,
+    score BIGINT NOT NULL,
+    data JSONB NOT NULL,
+    PRIMARY KEY (namespace, player)
+);
+
+CREATE INDEX IF NOT EXISTS idx_performance_ranks_score
+    ON performance_ranks (namespace, score);
+
+-- Best record of every player from earlier submissions, known by profile name,
+-- and the boards. Scores follow performance_score: the percentage above 45 bits
+-- of inverted total time.
+WITH records AS (
+    SELECT namespace, data->>'profile_name' AS player, data
+    FROM performance_submissions
+    UNION ALL
+    SELECT namespace, player, data FROM performance_records WHERE player IS NOT NULL
+), scored AS (
+    SELECT namespace, player, data,
+        ((data->>'performance_percentage')::BIGINT << 45) + 35184372088831
+            - LEAST(COALESCE((
+                SELECT SUM((value->>2)::BIGINT)
+                FROM jsonb_array_elements(data->'challenges_performance')
+            ), 0), 35184372088831) AS score
+    FROM records
+)
+INSERT INTO performance_ranks (namespace, player, score, data)
+SELECT DISTINCT ON (namespace, player) namespace, player, score, data
+FROM scored
+ORDER BY namespace, player, score DESC
+ON CONFLICT (namespace, player) DO NOTHING;
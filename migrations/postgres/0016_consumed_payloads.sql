@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS consumed_payloads (
+    payload_hash TEXT PRIMARY KEY,
+    expires_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_consumed_payloads_expires_at ON consumed_payloads (expires_at);
@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS coupon_campaigns (
+    name TEXT PRIMARY KEY NOT NULL,
+    data TEXT NOT NULL
+);
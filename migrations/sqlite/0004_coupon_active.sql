@@ -0,0 +1 @@
+ALTER TABLE coupons ADD COLUMN active INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS profile_credentials (
+    profile_id TEXT PRIMARY KEY NOT NULL,
+    secret_hash TEXT NOT NULL
+);
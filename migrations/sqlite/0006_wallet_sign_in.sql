@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS profile_sessions (
+    token_hash TEXT PRIMARY KEY NOT NULL,
+    profile_id TEXT NOT NULL,
+    expires_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_profile_sessions_expires_at ON profile_sessions (expires_at);
+
+CREATE TABLE IF NOT EXISTS wallet_profiles (
+    address TEXT PRIMARY KEY NOT NULL,
+    profile_id TEXT NOT NULL
+);
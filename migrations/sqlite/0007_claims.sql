@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS claims (
+    id INTEGER PRIMARY KEY NOT NULL,
+    data TEXT NOT NULL
+);
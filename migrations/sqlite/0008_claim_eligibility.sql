@@ -0,0 +1,4 @@
+CREATE INDEX IF NOT EXISTS idx_claims_user ON claims (json_extract(data, '$.user'));
+
+CREATE INDEX IF NOT EXISTS idx_coupon_redemptions_redeemed_by
+    ON coupon_redemptions (json_extract(data, '$.redeemed_by'));
@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS idx_claims_status ON claims (json_extract(data, '$.status'));
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS performance_submissions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    namespace TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_performance_submissions_namespace
+    ON performance_submissions (namespace);
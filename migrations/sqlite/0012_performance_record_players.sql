@@ -0,0 +1,6 @@
+ALTER TABLE performance_records ADD COLUMN player TEXT;
+
+UPDATE performance_records SET player = json_extract(data, '$.profile_name') WHERE player IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_performance_records_player
+    ON performance_records (namespace, player);
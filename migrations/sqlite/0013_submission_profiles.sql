@@ -0,0 +1,4 @@
+ALTER TABLE performance_submissions ADD COLUMN profile_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_performance_submissions_profile_id
+    ON performance_submissions (profile_id);
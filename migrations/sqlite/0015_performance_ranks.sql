@@ -0,0 +1,32 @@
+CREATE TABLE IF NOT EXISTS performance_ranks (
+    namespace TEXT NOT NULL,
+    player TEXT NOT NULL,
+    score INTEGER NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (namespace, player)
+);
+
+CREATE INDEX IF NOT EXISTS idx_performance_ranks_score
+    ON performance_ranks (namespace, score);
+
+-- Best record of every player from earlier submissions, known by profile name,
+-- and the boards. Scores follow performance_score: the percentage above 45 bits
+-- of inverted total time.
+WITH records AS (
+    SELECT namespace, json_extract(data, '$.profile_name') AS player, data
+    FROM performance_submissions
+    UNION ALL
+    SELECT namespace, player, data FROM performance_records WHERE player IS NOT NULL
+), scored AS (
+    SELECT namespace, player, data,
+        (json_extract(data, '$.performance_percentage') << 45) + 35184372088831
+            - MIN(COALESCE((
+                SELECT SUM(json_extract(value, '$[2]'))
+                FROM json_each(data, '$.challenges_performance')
+            ), 0), 35184372088831) AS score
+    FROM records
+)
+INSERT INTO performance_ranks (namespace, player, score, data)
+SELECT namespace, player, score, data FROM scored WHERE true
+ON CONFLICT (namespace, player) DO UPDATE SET score = excluded.score, data = excluded.data
+    WHERE excluded.score > performance_ranks.score;
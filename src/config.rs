@@ -0,0 +1,23 @@
+use thiserror::Error;
+
+/// A variable of the environment holds a value the server cannot use.
+#[derive(Debug, Error, PartialEq)]
+pub enum ConfigError {
+    #[error("Invalid value '{1}' for {0}")]
+    InvalidSetting(&'static str, String),
+}
+
+/// The trimmed value of `key` parsed, `default` if it is unset or blank.
+pub(crate) fn setting<F, T>(var: &F, key: &'static str, default: T) -> Result<T, ConfigError>
+where
+    F: Fn(&str) -> Option<String>,
+    T: std::str::FromStr,
+{
+    match var(key).filter(|value| !value.trim().is_empty()) {
+        Some(value) => value
+            .trim()
+            .parse()
+            .map_err(|_| ConfigError::InvalidSetting(key, value)),
+        None => Ok(default),
+    }
+}
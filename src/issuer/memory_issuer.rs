@@ -0,0 +1,331 @@
+use crate::issuer::{JettonInfo, TokenIssuer, TokenIssuerError};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+
+const WALLET_ADDRESS: &str = "memory-faucet";
+const JETTON_ADDRESS: &str = "memory-jetton";
+const COLLECTION_ADDRESS: &str = "memory-collection";
+
+/// A transfer signed or sent by a [`MemoryTokenIssuer`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IssuedTransfer {
+    pub receiver: String,
+    pub amount: u128,
+    pub seqno: u32,
+}
+
+/// An NFT mint signed or sent by a [`MemoryTokenIssuer`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IssuedMint {
+    pub owner: String,
+    pub item_index: u64,
+    pub content_url: String,
+    pub seqno: u32,
+}
+
+/// Messages of a [`MemoryTokenIssuer`], told apart by their fields.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IssuedMessage {
+    Transfer(IssuedTransfer),
+    Mint(IssuedMint),
+}
+
+impl IssuedMessage {
+    fn seqno(&self) -> u32 {
+        match self {
+            IssuedMessage::Transfer(transfer) => transfer.seqno,
+            IssuedMessage::Mint(mint) => mint.seqno,
+        }
+    }
+}
+
+#[derive(Default)]
+struct IssuerState {
+    seqno: u32,
+    sent: Vec<IssuedTransfer>,
+    minted: Vec<IssuedMint>,
+    /// Hashes of the messages the wallet processed
+    processed: Vec<String>,
+    /// Jettons left in the faucet, unlimited if `None`
+    balance: Option<u128>,
+    /// Nanotons left in the faucet, unlimited if `None`
+    ton_balance: Option<u128>,
+    failure: Option<TokenIssuerError>,
+}
+
+/// Keeps transfers and mints in memory instead of sending them, messages are
+/// the JSON of an [`IssuedTransfer`] or an [`IssuedMint`].
+#[derive(Default)]
+pub struct MemoryTokenIssuer {
+    state: Mutex<IssuerState>,
+}
+
+impl MemoryTokenIssuer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Transfers sent so far, oldest first.
+    pub fn sent_transfers(&self) -> Vec<IssuedTransfer> {
+        self.state().sent.clone()
+    }
+
+    /// NFTs minted so far, oldest first.
+    pub fn minted_nfts(&self) -> Vec<IssuedMint> {
+        self.state().minted.clone()
+    }
+
+    /// Limits the faucet to `balance` jettons, sent transfers are deducted from it.
+    pub fn set_balance(&self, balance: u128) {
+        self.state().balance = Some(balance);
+    }
+
+    /// Limits the TON of the faucet to `nanotons`.
+    pub fn set_ton_balance(&self, nanotons: u128) {
+        self.state().ton_balance = Some(nanotons);
+    }
+
+    /// Makes every call fail with `error` until [`Self::recover`] is called.
+    pub fn fail_with(&self, error: TokenIssuerError) {
+        self.state().failure = Some(error);
+    }
+
+    pub fn recover(&self) {
+        self.state().failure = None;
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, IssuerState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn check_failure(&self) -> Result<(), TokenIssuerError> {
+        match &self.state().failure {
+            Some(error) => Err(error.clone()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl TokenIssuer for MemoryTokenIssuer {
+    fn wallet_address(&self) -> String {
+        WALLET_ADDRESS.to_string()
+    }
+
+    fn validate_address(&self, address: &str) -> Result<(), TokenIssuerError> {
+        if address.is_empty() || address.contains(char::is_whitespace) {
+            return Err(TokenIssuerError::InvalidAddress(address.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn ton_balance(&self) -> Result<u128, TokenIssuerError> {
+        self.check_failure()?;
+        Ok(self.state().ton_balance.unwrap_or(u128::MAX))
+    }
+
+    async fn jetton_balance_of(&self, owner: &str) -> Result<u128, TokenIssuerError> {
+        self.check_failure()?;
+        self.validate_address(owner)?;
+        let state = self.state();
+        if owner == WALLET_ADDRESS {
+            return Ok(state.balance.unwrap_or(u128::MAX));
+        }
+        Ok(state
+            .sent
+            .iter()
+            .filter(|transfer| transfer.receiver == owner)
+            .map(|transfer| transfer.amount)
+            .sum())
+    }
+
+    async fn jetton_info(&self) -> Result<JettonInfo, TokenIssuerError> {
+        self.check_failure()?;
+        Ok(JettonInfo {
+            address: JETTON_ADDRESS.to_string(),
+            name: Some("Memory Jetton".to_string()),
+            symbol: Some("MEM".to_string()),
+            description: None,
+            image: None,
+            decimals: Some(9),
+            total_supply: self
+                .state()
+                .sent
+                .iter()
+                .map(|transfer| transfer.amount)
+                .sum(),
+            mintable: false,
+            admin_address: None,
+        })
+    }
+
+    async fn seqno(&self) -> Result<u32, TokenIssuerError> {
+        self.check_failure()?;
+        Ok(self.state().seqno)
+    }
+
+    async fn jetton_wallet(&self, owner: &str) -> Result<String, TokenIssuerError> {
+        self.check_failure()?;
+        self.validate_address(owner)?;
+        Ok(format!("jetton-wallet:{}", owner))
+    }
+
+    async fn sign_transfer(
+        &self,
+        receiver: &str,
+        amount: u128,
+        seqno: u32,
+    ) -> Result<Vec<u8>, TokenIssuerError> {
+        self.check_failure()?;
+        self.validate_address(receiver)?;
+        let transfer = IssuedTransfer {
+            receiver: receiver.to_string(),
+            amount,
+            seqno,
+        };
+        serde_json::to_vec(&transfer).map_err(|e| TokenIssuerError::Signing(e.to_string()))
+    }
+
+    async fn send_message(&self, message: &[u8]) -> Result<String, TokenIssuerError> {
+        self.check_failure()?;
+        let message_data: IssuedMessage = serde_json::from_slice(message)
+            .map_err(|e| TokenIssuerError::Network(format!("rejected message: {}", e)))?;
+
+        let mut state = self.state();
+        // Like a wallet contract, only accept the current seqno
+        if message_data.seqno() != state.seqno {
+            return Err(TokenIssuerError::Network(format!(
+                "seqno {} does not match wallet seqno {}",
+                message_data.seqno(),
+                state.seqno
+            )));
+        }
+        state.seqno += 1;
+        let hash = hex::encode(Sha256::digest(message));
+        state.processed.push(hash.clone());
+        match message_data {
+            IssuedMessage::Transfer(transfer) => {
+                if let Some(balance) = state.balance.as_mut() {
+                    *balance = balance.saturating_sub(transfer.amount);
+                }
+                state.sent.push(transfer);
+            }
+            IssuedMessage::Mint(mint) => {
+                // Like an NFT collection, only mint the next index
+                if mint.item_index == state.minted.len() as u64 {
+                    state.minted.push(mint);
+                }
+            }
+        }
+        Ok(hash)
+    }
+
+    async fn message_processed(&self, message: &[u8]) -> Result<bool, TokenIssuerError> {
+        self.check_failure()?;
+        let hash = hex::encode(Sha256::digest(message));
+        Ok(self.state().processed.contains(&hash))
+    }
+
+    fn nft_collection(&self) -> Option<String> {
+        Some(COLLECTION_ADDRESS.to_string())
+    }
+
+    async fn next_nft_index(&self) -> Result<u64, TokenIssuerError> {
+        self.check_failure()?;
+        Ok(self.state().minted.len() as u64)
+    }
+
+    async fn sign_nft_mint(
+        &self,
+        owner: &str,
+        item_index: u64,
+        content_url: &str,
+        seqno: u32,
+    ) -> Result<Vec<u8>, TokenIssuerError> {
+        self.check_failure()?;
+        self.validate_address(owner)?;
+        let mint = IssuedMint {
+            owner: owner.to_string(),
+            item_index,
+            content_url: content_url.to_string(),
+            seqno,
+        };
+        serde_json::to_vec(&mint).map_err(|e| TokenIssuerError::Signing(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_transfers_are_recorded() {
+        let issuer = MemoryTokenIssuer::new();
+        let hash = issuer.transfer("alice", 5).await.unwrap();
+        assert_eq!(hash.len(), 64);
+        issuer.transfer("bob", 1).await.unwrap();
+
+        assert_eq!(issuer.seqno().await.unwrap(), 2);
+        assert_eq!(issuer.jetton_balance_of("alice").await.unwrap(), 5);
+        assert_eq!(
+            issuer.sent_transfers(),
+            vec![
+                IssuedTransfer {
+                    receiver: "alice".to_string(),
+                    amount: 5,
+                    seqno: 0
+                },
+                IssuedTransfer {
+                    receiver: "bob".to_string(),
+                    amount: 1,
+                    seqno: 1
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stale_seqno_and_failures_are_rejected() {
+        let issuer = MemoryTokenIssuer::new();
+        let first = issuer.signed_transfer("alice", 5).await.unwrap();
+        let second = issuer.signed_transfer("alice", 5).await.unwrap();
+        issuer.send_message(&first).await.unwrap();
+        assert!(issuer.send_message(&second).await.is_err());
+        assert!(issuer.message_processed(&first).await.unwrap());
+        assert!(!issuer.message_processed(&second).await.unwrap());
+
+        issuer.fail_with(TokenIssuerError::Network("offline".to_string()));
+        assert!(issuer.transfer("alice", 1).await.is_err());
+        issuer.recover();
+        issuer.transfer("alice", 1).await.unwrap();
+        assert_eq!(issuer.sent_transfers().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_transfers_are_limited_by_the_balance() {
+        let issuer = MemoryTokenIssuer::new();
+        issuer.set_balance(5);
+        issuer.transfer("alice", 3).await.unwrap();
+        assert_eq!(issuer.jetton_balance().await.unwrap(), 2);
+
+        assert_eq!(
+            issuer.transfer("alice", 3).await,
+            Err(TokenIssuerError::InsufficientFunds {
+                requested: 3,
+                available: 2
+            })
+        );
+        assert_eq!(
+            issuer.transfer("not an address", 1).await,
+            Err(TokenIssuerError::InvalidAddress(
+                "not an address".to_string()
+            ))
+        );
+        assert_eq!(issuer.sent_transfers().len(), 1);
+    }
+}
@@ -0,0 +1,5 @@
+mod memory_issuer;
+mod token_issuer;
+
+pub use memory_issuer::{IssuedMint, IssuedTransfer, MemoryTokenIssuer};
+pub use token_issuer::{JettonInfo, TokenIssuer, TokenIssuerError};
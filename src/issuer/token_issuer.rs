@@ -0,0 +1,130 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum TokenIssuerError {
+    #[error("Invalid address {0}")]
+    InvalidAddress(String),
+    #[error("The faucet holds {available} tokens, {requested} requested")]
+    InsufficientFunds { requested: u128, available: u128 },
+    #[error("Signing failed: {0}")]
+    Signing(String),
+    #[error("Network error: {0}")]
+    Network(String),
+}
+
+/// Data of the jetton master contract and its metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct JettonInfo {
+    #[schema(example = "EQCP0BHV18JPMrt0JbSUulzcL4geZ_JrGgMZmbVv8gBk2iTe")]
+    pub address: String,
+    #[schema(example = "Konnektoren")]
+    pub name: Option<String>,
+    #[schema(example = "KONN")]
+    pub symbol: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    #[schema(example = 9)]
+    pub decimals: Option<u8>,
+    /// Whole jettons minted so far
+    #[schema(value_type = u64, example = 1000000)]
+    pub total_supply: u128,
+    pub mintable: bool,
+    pub admin_address: Option<String>,
+}
+
+/// Issues jettons from the faucet wallet.
+///
+/// The tonlib implementation lives in `crate::ton`, [`super::MemoryTokenIssuer`]
+/// records transfers in memory for tests.
+#[async_trait]
+pub trait TokenIssuer: Send + Sync {
+    /// Address of the wallet derived from the faucet key, it signs and pays for transfers.
+    fn wallet_address(&self) -> String;
+
+    /// Checks the format of `address` without network access.
+    fn validate_address(&self, address: &str) -> Result<(), TokenIssuerError>;
+
+    /// TON held by the faucet wallet to pay fees, in nanotons.
+    async fn ton_balance(&self) -> Result<u128, TokenIssuerError>;
+
+    /// Whole jettons held by `owner`.
+    async fn jetton_balance_of(&self, owner: &str) -> Result<u128, TokenIssuerError>;
+
+    async fn jetton_info(&self) -> Result<JettonInfo, TokenIssuerError>;
+
+    /// Next sequence number of the faucet wallet.
+    async fn seqno(&self) -> Result<u32, TokenIssuerError>;
+
+    /// Jetton wallet holding the tokens of `owner`.
+    async fn jetton_wallet(&self, owner: &str) -> Result<String, TokenIssuerError>;
+
+    /// Signs an external message transferring `amount` jettons to `receiver`, as a BOC.
+    async fn sign_transfer(
+        &self,
+        receiver: &str,
+        amount: u128,
+        seqno: u32,
+    ) -> Result<Vec<u8>, TokenIssuerError>;
+
+    /// Sends a signed message and returns its hex encoded hash.
+    async fn send_message(&self, message: &[u8]) -> Result<String, TokenIssuerError>;
+
+    /// Whether the faucet wallet processed the signed `message`, looked up
+    /// among its recent transactions.
+    async fn message_processed(&self, message: &[u8]) -> Result<bool, TokenIssuerError>;
+
+    /// Address of the NFT collection certificates are minted in, `None` if there is none.
+    fn nft_collection(&self) -> Option<String>;
+
+    /// Index the next item minted in the NFT collection gets.
+    async fn next_nft_index(&self) -> Result<u64, TokenIssuerError>;
+
+    /// Signs an external message minting NFT `item_index` for `owner`, as a BOC.
+    ///
+    /// The item content is `content_url`, where the metadata of the item is served.
+    async fn sign_nft_mint(
+        &self,
+        owner: &str,
+        item_index: u64,
+        content_url: &str,
+        seqno: u32,
+    ) -> Result<Vec<u8>, TokenIssuerError>;
+
+    /// Whole jettons held by the faucet wallet.
+    async fn jetton_balance(&self) -> Result<u128, TokenIssuerError> {
+        self.jetton_balance_of(&self.wallet_address()).await
+    }
+
+    /// Checks that `receiver` is an address and the faucet holds `amount` jettons.
+    async fn check_transfer(&self, receiver: &str, amount: u128) -> Result<(), TokenIssuerError> {
+        self.validate_address(receiver)?;
+        let available = self.jetton_balance().await?;
+        if available < amount {
+            return Err(TokenIssuerError::InsufficientFunds {
+                requested: amount,
+                available,
+            });
+        }
+        Ok(())
+    }
+
+    /// Signs a transfer with the current seqno, if the faucet holds `amount` jettons.
+    async fn signed_transfer(
+        &self,
+        receiver: &str,
+        amount: u128,
+    ) -> Result<Vec<u8>, TokenIssuerError> {
+        self.check_transfer(receiver, amount).await?;
+        let seqno = self.seqno().await?;
+        self.sign_transfer(receiver, amount, seqno).await
+    }
+
+    /// Signs and sends a transfer, returns the hash of the message.
+    async fn transfer(&self, receiver: &str, amount: u128) -> Result<String, TokenIssuerError> {
+        let message = self.signed_transfer(receiver, amount).await?;
+        self.send_message(&message).await
+    }
+}
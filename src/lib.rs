@@ -1,4 +1,6 @@
 pub mod compatibility;
+pub mod config;
+pub mod issuer;
 pub mod metrics;
 pub mod middleware;
 pub mod routes;
@@ -1,14 +1,24 @@
 use axum::{
     extract::{MatchedPath, Request},
     routing::get,
-    Router,
+    Extension, Router,
 };
 use dotenv::dotenv;
 use konnekt_session::server::v2::{create_session_route, ConnectionHandler, MemoryStorage};
-use konnektoren_api::middleware;
+use konnektoren_api::issuer::TokenIssuer;
+use konnektoren_api::middleware::{self, auth::AdminAuth};
+#[cfg(feature = "ton")]
+use konnektoren_api::ton::{TonConfig, TonTokenIssuer};
 use konnektoren_api::{
     routes::{self, health},
-    storage::Storage,
+    services::{
+        certificate::{CertificateConfig, CertificateMinter},
+        claim_eligibility::ClaimPolicy,
+        claim_queue::{ClaimQueue, ClaimQueueConfig},
+        faucet::{FaucetConfig, FaucetMonitor},
+        v1::{leaderboard::LeaderboardConfig, ton_proof::TonProofConfig},
+    },
+    storage::{create_storage, StorageConfig},
     telemetry::init_telemetry,
 };
 #[cfg(feature = "metrics")]
@@ -16,7 +26,6 @@ use opentelemetry::metrics;
 use routes::openapi::ApiDoc;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::Mutex;
 use tower_http::cors::CorsLayer;
 #[cfg(feature = "tracing")]
 use tower_http::trace::{self, TraceLayer};
@@ -35,17 +44,96 @@ async fn main() {
     #[cfg(feature = "metrics")]
     let metrics = konnektoren_api::metrics::Metrics::new().expect("Failed to initialize metrics");
 
-    #[cfg(feature = "redis")]
-    let repo: Arc<Mutex<dyn Storage>> = {
-        let redis_url = std::env::var("REDIS_URL").expect("REDIS_URL env must be set");
-        Arc::new(Mutex::new(konnektoren_api::storage::RedisStorage::new(
-            &redis_url,
-        )))
+    let storage_config = StorageConfig::from_env().unwrap_or_else(|err| {
+        log::error!("Invalid storage configuration: {}", err);
+        std::process::exit(1);
+    });
+    let repo = create_storage(&storage_config).await.unwrap_or_else(|err| {
+        log::error!("Failed to initialize storage: {}", err);
+        std::process::exit(1);
+    });
+    log::info!("Using {} storage", storage_config.backend());
+
+    let admin_auth = AdminAuth::from_env();
+    if !admin_auth.is_enabled() {
+        log::warn!("ADMIN_API_KEYS is not set, admin endpoints will reject every request");
+    }
+    let ton_proof = TonProofConfig::from_env();
+    if ton_proof.domains().is_empty() {
+        log::warn!("TON_PROOF_DOMAINS is not set, wallet sign-ins will be rejected");
+    }
+    let claim_policy = ClaimPolicy::from_env().unwrap_or_else(|err| {
+        log::error!("Invalid claim configuration: {}", err);
+        std::process::exit(1);
+    });
+    if claim_policy.challenges.is_empty() {
+        log::warn!("CLAIM_CHALLENGES is not set, challenges will not earn tokens");
+    }
+    let leaderboard_config = LeaderboardConfig::from_env().unwrap_or_else(|err| {
+        log::error!("Invalid leaderboard configuration: {}", err);
+        std::process::exit(1);
+    });
+
+    #[cfg(feature = "ton")]
+    let issuer: Option<Arc<dyn TokenIssuer>> = {
+        let ton_config = TonConfig::from_env().unwrap_or_else(|err| {
+            log::error!("Invalid TON configuration: {}", err);
+            std::process::exit(1);
+        });
+        let issuer = TonTokenIssuer::from_env(&ton_config)
+            .await
+            .unwrap_or_else(|err| {
+                log::error!("Failed to connect to TON {}: {:#}", ton_config.network, err);
+                std::process::exit(1);
+            });
+        log::info!("Issuing tokens from {}", issuer.wallet_address());
+        Some(Arc::new(issuer))
     };
+    #[cfg(not(feature = "ton"))]
+    let issuer: Option<Arc<dyn TokenIssuer>> = None;
+
+    let claim_queue = issuer.clone().map(|issuer| {
+        let config = ClaimQueueConfig::from_env().unwrap_or_else(|err| {
+            log::error!("Invalid claim queue configuration: {}", err);
+            std::process::exit(1);
+        });
+        ClaimQueue::new(issuer, config)
+    });
+    if let Some(claim_queue) = &claim_queue {
+        tokio::spawn(claim_queue.worker(repo.clone()).run());
+    }
+
+    let faucet_monitor = issuer.clone().map(|issuer| {
+        let config = FaucetConfig::from_env().unwrap_or_else(|err| {
+            log::error!("Invalid faucet configuration: {}", err);
+            std::process::exit(1);
+        });
+        Arc::new(FaucetMonitor::new(issuer, config))
+    });
+    #[cfg(feature = "metrics")]
+    if let Some(monitor) = faucet_monitor.clone() {
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                match monitor.faucet_info(chrono::Utc::now()).await {
+                    Ok(info) => metrics.record_faucet(&info),
+                    Err(err) => log::warn!("Failed to read the faucet balance: {}", err),
+                }
+            }
+        });
+    }
 
-    #[cfg(not(feature = "redis"))]
-    let repo: Arc<Mutex<dyn Storage>> =
-        Arc::new(Mutex::new(konnektoren_api::storage::MemoryRepository::new()));
+    let certificate_minter = match (claim_queue.clone(), CertificateConfig::from_env()) {
+        (Some(claim_queue), Some(config)) => CertificateMinter::new(claim_queue, config),
+        _ => None,
+    };
+    if issuer.is_some() && certificate_minter.is_none() {
+        log::info!(
+            "NFT_COLLECTION_ADDRESS or CERTIFICATE_BASE_URL is not set, certificates will not be minted"
+        );
+    }
 
     #[cfg(feature = "konnekt-session")]
     let session_server = {
@@ -53,11 +141,30 @@ async fn main() {
         ConnectionHandler::new(memory_storage.clone(), memory_storage.clone())
     };
 
+    let readiness = match faucet_monitor.clone() {
+        Some(monitor) => get(health::readiness_check).layer(Extension(monitor)),
+        None => get(health::readiness_check),
+    };
+
     let app = Router::new()
         .route("/health", get(health::health_check))
-        .route("/ready", get(health::readiness_check))
-        .nest("/api/v1", routes::v1::create_router())
-        .nest("/api/v2", routes::v2::create_router())
+        .route("/ready", readiness)
+        .nest(
+            "/api/v1",
+            routes::v1::create_router(
+                admin_auth.clone(),
+                ton_proof,
+                claim_policy.clone(),
+                leaderboard_config,
+                claim_queue.clone(),
+                faucet_monitor,
+                certificate_minter,
+            ),
+        )
+        .nest(
+            "/api/v2",
+            routes::v2::create_router(admin_auth, claim_policy, claim_queue),
+        )
         .with_state(repo);
 
     #[cfg(feature = "tracing")]
@@ -1,7 +1,8 @@
+use crate::services::faucet::FaucetInfo;
 use axum::{extract::State, response::IntoResponse};
 use opentelemetry::metrics::{Meter, MeterProvider};
 use opentelemetry_sdk::metrics::MeterProvider as SdkMeterProvider;
-use prometheus::{Encoder, TextEncoder};
+use prometheus::{Encoder, IntGauge, TextEncoder};
 use std::sync::Arc;
 
 pub async fn metrics_handler(State(metrics): State<Metrics>) -> impl IntoResponse {
@@ -14,6 +15,9 @@ pub struct Metrics {
     pub request_counter: Arc<opentelemetry::metrics::Counter<u64>>,
     pub request_duration: Arc<opentelemetry::metrics::Histogram<f64>>,
     pub registry: Arc<prometheus::Registry>,
+    pub faucet_ton_balance: IntGauge,
+    pub faucet_jetton_balance: IntGauge,
+    pub faucet_low_balance: IntGauge,
 }
 
 impl Metrics {
@@ -43,14 +47,41 @@ impl Metrics {
             .with_description("HTTP request duration in seconds")
             .init();
 
+        let faucet_ton_balance = IntGauge::new(
+            "faucet_ton_balance_nanotons",
+            "TON balance of the faucet wallet in nanotons",
+        )?;
+        let faucet_jetton_balance = IntGauge::new(
+            "faucet_jetton_balance",
+            "Jetton balance of the faucet wallet",
+        )?;
+        let faucet_low_balance = IntGauge::new(
+            "faucet_low_balance",
+            "1 if a faucet balance is below its configured minimum",
+        )?;
+        registry.register(Box::new(faucet_ton_balance.clone()))?;
+        registry.register(Box::new(faucet_jetton_balance.clone()))?;
+        registry.register(Box::new(faucet_low_balance.clone()))?;
+
         Ok(Metrics {
             meter,
             request_counter: Arc::new(request_counter),
             request_duration: Arc::new(request_duration),
             registry: Arc::new(registry),
+            faucet_ton_balance,
+            faucet_jetton_balance,
+            faucet_low_balance,
         })
     }
 
+    pub fn record_faucet(&self, info: &FaucetInfo) {
+        let gauge_value = |value: u128| i64::try_from(value).unwrap_or(i64::MAX);
+        self.faucet_ton_balance.set(gauge_value(info.ton_balance));
+        self.faucet_jetton_balance
+            .set(gauge_value(info.jetton_balance));
+        self.faucet_low_balance.set(info.low_balance.into());
+    }
+
     pub fn gather_metrics(&self) -> String {
         let metric_families = self.registry.gather();
         let encoder = TextEncoder::new();
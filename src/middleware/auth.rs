@@ -0,0 +1,202 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::fmt;
+use std::sync::Arc;
+
+pub const ADMIN_API_KEYS_VAR: &str = "ADMIN_API_KEYS";
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
+/// Static API keys allowed to call admin endpoints, configured with the
+/// comma separated `ADMIN_API_KEYS` variable.
+///
+/// Without keys every admin request is rejected.
+#[derive(Clone, Default)]
+pub struct AdminAuth {
+    keys: Arc<Vec<String>>,
+}
+
+impl AdminAuth {
+    pub fn new<I, K>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        let keys = keys
+            .into_iter()
+            .map(Into::into)
+            .filter(|key| !key.is_empty())
+            .collect();
+        Self {
+            keys: Arc::new(keys),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    pub fn from_vars<F>(var: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let keys = var(ADMIN_API_KEYS_VAR).unwrap_or_default();
+        Self::new(keys.split(',').map(str::trim))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// Checks the `Authorization: Bearer <key>` and `X-API-Key` headers, a
+    /// valid key in either one authorizes. The bearer token may also be a
+    /// profile token meant for the route, which must not shadow the API key.
+    pub fn authorize(&self, headers: &HeaderMap) -> Result<(), (StatusCode, String)> {
+        let api_key = headers
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim);
+        let candidates: Vec<&str> = bearer_token(headers).into_iter().chain(api_key).collect();
+        if candidates.is_empty() {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                "Missing admin credentials".to_string(),
+            ));
+        }
+
+        // Check every candidate, so the time taken does not tell which one matched
+        let authorized = candidates
+            .iter()
+            .fold(false, |authorized, key| self.is_admin_key(key) | authorized);
+        if authorized {
+            Ok(())
+        } else {
+            Err((
+                StatusCode::UNAUTHORIZED,
+                "Invalid admin credentials".to_string(),
+            ))
+        }
+    }
+
+    fn is_admin_key(&self, key: &str) -> bool {
+        self.keys
+            .iter()
+            .any(|allowed| constant_time_eq(allowed.as_bytes(), key.as_bytes()))
+    }
+}
+
+impl fmt::Debug for AdminAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdminAuth")
+            .field("keys", &self.keys.len())
+            .finish()
+    }
+}
+
+/// Rejects requests without a valid admin key with `401 Unauthorized`.
+pub async fn require_admin(
+    State(auth): State<AdminAuth>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match auth.authorize(request.headers()) {
+        Ok(()) => next.run(request).await,
+        Err(rejection) => {
+            let mut response = rejection.into_response();
+            response.headers_mut().insert(
+                header::WWW_AUTHENTICATE,
+                header::HeaderValue::from_static("Bearer"),
+            );
+            response
+        }
+    }
+}
+
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+/// Compares without returning early, so the time taken does not leak a key prefix.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_admin_keys_from_vars() {
+        let auth = AdminAuth::from_vars(|_| Some(" key1, ,key2 ".to_string()));
+        assert_eq!(*auth.keys, vec!["key1".to_string(), "key2".to_string()]);
+
+        let auth = AdminAuth::from_vars(|_| None);
+        assert!(!auth.is_enabled());
+    }
+
+    #[test]
+    fn test_authorize() {
+        let auth = AdminAuth::new(["secret"]);
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            auth.authorize(&headers).unwrap_err().0,
+            StatusCode::UNAUTHORIZED
+        );
+
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer nope"),
+        );
+        assert!(auth.authorize(&headers).is_err());
+
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer secret"),
+        );
+        assert!(auth.authorize(&headers).is_ok());
+
+        headers.remove(header::AUTHORIZATION);
+        headers.insert(API_KEY_HEADER, HeaderValue::from_static("secret"));
+        assert!(auth.authorize(&headers).is_ok());
+
+        let disabled = AdminAuth::default();
+        assert!(disabled.authorize(&headers).is_err());
+    }
+
+    #[test]
+    fn test_profile_tokens_do_not_shadow_the_api_key() {
+        let auth = AdminAuth::new(["secret"]);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer profile-token"),
+        );
+        headers.insert(API_KEY_HEADER, HeaderValue::from_static("secret"));
+        assert!(auth.authorize(&headers).is_ok());
+
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer secret"),
+        );
+        headers.insert(API_KEY_HEADER, HeaderValue::from_static("nope"));
+        assert!(auth.authorize(&headers).is_ok());
+
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer profile-token"),
+        );
+        assert!(auth.authorize(&headers).is_err());
+    }
+}
@@ -1,3 +1,5 @@
+pub mod auth;
+
 #[cfg(feature = "tracing")]
 pub mod trace;
 
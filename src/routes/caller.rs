@@ -0,0 +1,120 @@
+use crate::middleware::auth::bearer_token;
+use crate::services::v1::profile::{authorize_profile, ProfileError};
+use crate::storage::{RepositoryError, Storage};
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, StatusCode};
+use std::sync::Arc;
+
+pub const PROFILE_ID_HEADER: &str = "X-Profile-ID";
+
+/// Identity of the caller of a request.
+///
+/// The profile id of the `X-Profile-ID` header, trusted only together with an
+/// `Authorization: Bearer` owner token or session token of that profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallerId(pub String);
+
+impl CallerId {
+    pub async fn authenticate(
+        headers: &HeaderMap,
+        repository: Arc<dyn Storage>,
+    ) -> Result<Self, (StatusCode, String)> {
+        let profile_id = headers
+            .get(PROFILE_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .ok_or((
+                StatusCode::UNAUTHORIZED,
+                format!(
+                    "Missing caller identity, set the {} header and a token of the profile",
+                    PROFILE_ID_HEADER
+                ),
+            ))?;
+
+        match authorize_profile(profile_id, bearer_token(headers), repository).await {
+            Ok(()) => Ok(CallerId(profile_id.to_string())),
+            Err(err) => {
+                let status = match &err {
+                    ProfileError::MissingToken(_) => StatusCode::UNAUTHORIZED,
+                    ProfileError::InvalidToken(_) => StatusCode::FORBIDDEN,
+                    ProfileError::Unowned(_) => StatusCode::CONFLICT,
+                    ProfileError::Repository(RepositoryError::NotFound(_)) => StatusCode::NOT_FOUND,
+                    ProfileError::Repository(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                Err((status, err.to_string()))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<dyn Storage>> for CallerId {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<dyn Storage>,
+    ) -> Result<Self, Self::Rejection> {
+        CallerId::authenticate(&parts.headers, state.clone()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::v1::profile::save_profile;
+    use crate::storage::MemoryRepository;
+    use axum::http::{header, HeaderValue};
+    use konnektoren_core::prelude::PlayerProfile;
+
+    #[tokio::test]
+    async fn test_caller_id_needs_a_token_of_the_profile() {
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let token = save_profile(
+            PlayerProfile::new("profile".to_string()),
+            None,
+            repository.clone(),
+        )
+        .await
+        .unwrap()
+        .token
+        .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "traceparent",
+            HeaderValue::from_static("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"),
+        );
+        let status = |headers: &HeaderMap| {
+            let repository = repository.clone();
+            let headers = headers.clone();
+            async move {
+                CallerId::authenticate(&headers, repository)
+                    .await
+                    .map_err(|(status, _)| status)
+            }
+        };
+        assert_eq!(status(&headers).await, Err(StatusCode::UNAUTHORIZED));
+
+        headers.insert(PROFILE_ID_HEADER, HeaderValue::from_static("profile"));
+        assert_eq!(status(&headers).await, Err(StatusCode::UNAUTHORIZED));
+
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer wrong"),
+        );
+        assert_eq!(status(&headers).await, Err(StatusCode::FORBIDDEN));
+
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
+        );
+        assert_eq!(status(&headers).await, Ok(CallerId("profile".to_string())));
+
+        headers.insert(PROFILE_ID_HEADER, HeaderValue::from_static("unknown"));
+        assert_eq!(status(&headers).await, Err(StatusCode::NOT_FOUND));
+    }
+}
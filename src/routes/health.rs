@@ -1,11 +1,11 @@
 use axum::extract::State;
 use axum::http::StatusCode;
-use axum::Json;
+use axum::{Extension, Json};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tokio::sync::Mutex;
 use utoipa::ToSchema;
 
+use crate::services::faucet::FaucetMonitor;
 use crate::storage::Storage;
 
 #[derive(Serialize, Deserialize, ToSchema)]
@@ -26,6 +26,9 @@ pub struct HealthChecks {
     pub database: String,
     #[cfg(feature = "redis")]
     pub redis: String,
+    /// `low_balance` is a warning, the service stays ready
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub faucet: Option<String>,
 }
 
 /// Health check endpoint - always returns OK if the service is running
@@ -58,12 +61,14 @@ pub async fn health_check() -> Result<Json<HealthResponse>, StatusCode> {
     )
 )]
 pub async fn readiness_check(
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
+    faucet: Option<Extension<Arc<FaucetMonitor>>>,
 ) -> Result<Json<ReadinessResponse>, (StatusCode, Json<ReadinessResponse>)> {
     let mut checks = HealthChecks {
         database: "unknown".to_string(),
         #[cfg(feature = "redis")]
         redis: "unknown".to_string(),
+        faucet: None,
     };
 
     let mut all_healthy = true;
@@ -88,6 +93,18 @@ pub async fn readiness_check(
         }
     }
 
+    if let Some(Extension(faucet)) = faucet {
+        let status = match faucet.faucet_info(chrono::Utc::now()).await {
+            Ok(info) if info.low_balance => "low_balance",
+            Ok(_) => "healthy",
+            Err(err) => {
+                log::warn!("Failed to read the faucet balance: {}", err);
+                "unavailable"
+            }
+        };
+        checks.faucet = Some(status.to_string());
+    }
+
     let response = ReadinessResponse {
         status: if all_healthy { "ready" } else { "not_ready" }.to_string(),
         checks,
@@ -101,24 +118,22 @@ pub async fn readiness_check(
 }
 
 async fn test_storage_connection(
-    repository: &Arc<Mutex<dyn Storage>>,
+    repository: &Arc<dyn Storage>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     use crate::storage::ProfileRepository;
 
     // Try to perform a simple operation to test the connection
-    let storage = repository.lock().await;
-    let _ = ProfileRepository::fetch_all(&*storage).await?;
+    let _ = ProfileRepository::fetch_all(&**repository).await?;
     Ok(())
 }
 
 #[cfg(feature = "redis")]
 async fn test_redis_connection(
-    repository: &Arc<Mutex<dyn Storage>>,
+    repository: &Arc<dyn Storage>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // For Redis, we can test the connection by trying to get active count
     use crate::storage::WindowedCounterRepository;
 
-    let storage = repository.lock().await;
-    let _ = storage.get_active_count("health_check").await?;
+    let _ = repository.get_active_count("health_check").await?;
     Ok(())
 }
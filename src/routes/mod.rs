@@ -1,3 +1,4 @@
+pub mod caller;
 pub mod health;
 pub mod openapi;
 pub mod v1;
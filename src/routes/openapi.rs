@@ -1,5 +1,24 @@
 use super::{v1, v2};
-use utoipa::OpenApi;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+/// Registers the bearer scheme used by admin-only endpoints.
+struct AdminSecurity;
+
+impl Modify for AdminSecurity {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "admin_token",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some("One of the keys configured in ADMIN_API_KEYS"))
+                    .build(),
+            ),
+        );
+    }
+}
 
 /// Documentation for the API.
 #[cfg(feature = "ton")]
@@ -11,6 +30,12 @@ use utoipa::OpenApi;
         super::health::readiness_check,
         super::v1::claim::claim_tokens,
         super::v2::claim::claim_tokens,
+        super::v2::claim::get_claim,
+        super::v1::faucet::get_faucet,
+        super::v1::faucet::get_jetton,
+        super::v1::faucet::get_jetton_balance,
+        super::v1::certificate::post_certificate,
+        super::v1::certificate::get_certificate,
     ),
     // Schema components for requests and responses used across the API.
     components(
@@ -21,8 +46,20 @@ use utoipa::OpenApi;
             v1::claim::ClaimRequest,
             v2::claim::ClaimV2Request,
             v2::claim::ClaimV2Response,
+            crate::storage::ClaimRecord,
+            crate::storage::ClaimStatus,
+            crate::storage::ClaimKind,
+            v1::faucet::JettonBalanceResponse,
+            crate::services::faucet::FaucetInfo,
+            crate::issuer::JettonInfo,
+            v1::certificate::CertificateRequest,
+            crate::storage::CertificateRecord,
+            crate::storage::CertificateStatus,
+            crate::services::certificate::CertificateMetadata,
+            crate::services::certificate::CertificateAttribute,
         )
     ),
+    modifiers(&AdminSecurity),
     tags(
         (name = "health", description = "Health check endpoints"),
         (name = "API", description = "Konnektoren API")
@@ -39,8 +76,10 @@ pub struct ApiDoc;
         super::v1::profile::get_profile,
         super::v1::profile::get_all_profiles,
         super::v1::profile::post_profile,
+        super::v1::profile::post_profile_token,
         super::v1::leaderboard::get_leaderboard,
         super::v1::leaderboard::get_challenge_leaderboard,
+        super::v1::leaderboard::get_player_rank,
         super::v1::leaderboard::post_performance_record,
         super::v1::leaderboard::post_challenge_performance_record,
         super::v1::review::get_reviews,
@@ -52,9 +91,18 @@ pub struct ApiDoc;
         // Coupon endpoints
         super::v1::coupon::create_handler,
         super::v1::coupon::get_handler,
+        super::v1::coupon::update_handler,
+        super::v1::coupon::delete_handler,
         super::v1::coupon::list_handler,
         super::v1::coupon::validate_handler,
         super::v1::coupon::redeem_handler,
+        super::v1::coupon::redemptions_handler,
+        super::v1::coupon::create_campaign_handler,
+        super::v1::coupon::get_campaign_handler,
+        super::v1::wallet::challenge_handler,
+        super::v1::wallet::sign_in_handler,
+        super::v2::claim::get_claim,
+        super::v1::certificate::get_certificate,
         #[cfg(feature = "chat")]
         super::v1::chat::send_message,
         #[cfg(feature = "chat")]
@@ -66,14 +114,35 @@ pub struct ApiDoc;
         schemas(
             v1::profile::ProfileV1Response,
             v1::profile::ProfilesV1Response,
+            v1::profile::ProfileTokenV1Response,
             v1::leaderboard::LeaderboardV1Response,
+            v1::leaderboard::LeaderboardEntry,
+            crate::services::v1::leaderboard::LeaderboardPeriod,
+            crate::services::v1::leaderboard::PlayerRank,
             v1::review::Review,
             v1::review::ReviewsResponse,
             v1::challenge_presence::ChallengePresenceStats,
             v1::coupon::CouponResponse,
             v1::coupon::CouponsResponse,
+            v1::coupon::CouponRedemptionsResponse,
+            crate::storage::CouponRedemption,
+            v1::coupon::CampaignRequest,
+            v1::coupon::CampaignResponse,
+            crate::storage::CouponCampaign,
+            crate::storage::CouponUpdate,
+            v1::wallet::WalletChallengeResponse,
+            v1::wallet::WalletSignInRequest,
+            v1::wallet::WalletSessionResponse,
+            crate::services::v1::ton_proof::TonProof,
+            crate::services::v1::ton_proof::TonProofDomain,
+            crate::storage::ClaimRecord,
+            crate::storage::ClaimStatus,
+            crate::storage::ClaimKind,
+            crate::services::certificate::CertificateMetadata,
+            crate::services::certificate::CertificateAttribute,
         )
     ),
+    modifiers(&AdminSecurity),
     tags(
         (name = "API", description = "Konnektoren API")
     )
@@ -99,6 +168,13 @@ mod tests {
         assert!(paths.contains_key("/api/v1/coupons/{code}"));
         assert!(paths.contains_key("/api/v1/coupons/{code}/validate/{challenge_id}"));
         assert!(paths.contains_key("/api/v1/coupons/{code}/redeem/{challenge_id}"));
+        assert!(paths.contains_key("/api/v1/coupons/{code}/redemptions"));
+        assert!(paths.contains_key("/api/v1/coupon-campaigns"));
+        assert!(paths.contains_key("/api/v1/coupon-campaigns/{name}"));
+        assert!(paths.contains_key("/api/v1/wallet/challenge"));
+        assert!(paths.contains_key("/api/v1/wallet/sign-in"));
+        assert!(paths.contains_key("/api/v2/claim/{id}"));
+        assert!(paths.contains_key("/api/v1/certificates/{id}"));
 
         #[cfg(feature = "chat")]
         {
@@ -106,4 +182,22 @@ mod tests {
             assert!(paths.contains_key("/api/v1/chat/receive/{channel}"));
         }
     }
+
+    #[test]
+    fn api_doc_marks_admin_endpoints() {
+        let api_doc = ApiDoc::openapi();
+        let components = api_doc.components.expect("components");
+        assert!(components.security_schemes.contains_key("admin_token"));
+
+        let paths = api_doc.paths.paths;
+        let profiles = &paths["/api/v1/profiles"].operations;
+        let secured = |operation: &utoipa::openapi::path::Operation| {
+            operation
+                .security
+                .as_ref()
+                .is_some_and(|security| !security.is_empty())
+        };
+        assert!(secured(&profiles[&utoipa::openapi::PathItemType::Get]));
+        assert!(!secured(&profiles[&utoipa::openapi::PathItemType::Post]));
+    }
 }
@@ -0,0 +1,81 @@
+use crate::services::certificate::{
+    certificate_error_response, get_certificate_metadata_service, queue_certificate_service,
+    CertificateMetadata, CertificateMinter,
+};
+use crate::storage::{CertificateRecord, CertificateStatus, Storage};
+use axum::extract::{Json, Path, State};
+use axum::http::StatusCode;
+use axum::Extension;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateRequest {
+    /// Profile id of the player, the game path must be completed in a record submitted for it
+    #[schema(example = "example_user")]
+    pub profile_id: String,
+    #[schema(example = "konnektoren")]
+    pub game_path_id: String,
+    /// Address the NFT is minted for
+    #[schema(example = "0QB-_k5Rule-nKr6HWPIlkDyHb1xhDdbI77q7uwAFqmUmKjP")]
+    pub address: String,
+}
+
+#[utoipa::path(
+    post,
+    operation_id = "post_certificate_v1",
+    tag = "certificate_v1",
+    path = "/certificates",
+    context_path = "/api/v1",
+    request_body = CertificateRequest,
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "The NFT was minted", body = CertificateRecord),
+        (status = 202, description = "The mint is queued, repeat the request to poll its status", body = CertificateRecord),
+        (status = 400, description = "Invalid address"),
+        (status = 401, description = "Missing or invalid admin credentials"),
+        (status = 404, description = "The player is not registered or has not completed the game path"),
+        (status = 409, description = "The certificate was requested for another address"),
+        (status = 500, description = "The mint failed"),
+    )
+)]
+pub async fn post_certificate(
+    State(repository): State<Arc<dyn Storage>>,
+    Extension(minter): Extension<CertificateMinter>,
+    Json(payload): Json<CertificateRequest>,
+) -> Result<(StatusCode, Json<CertificateRecord>), (StatusCode, String)> {
+    let certificate = queue_certificate_service(payload, &minter, repository)
+        .await
+        .map_err(certificate_error_response)?;
+    let status = match certificate.status {
+        CertificateStatus::Sent => StatusCode::OK,
+        _ => StatusCode::ACCEPTED,
+    };
+    Ok((status, Json(certificate)))
+}
+
+#[utoipa::path(
+    get,
+    operation_id = "get_certificate_v1",
+    tag = "certificate_v1",
+    path = "/certificates/{id}",
+    context_path = "/api/v1",
+    params(
+        ("id" = String, Path, description = "Id of the certificate"),
+    ),
+    responses(
+        (status = 200, description = "NFT metadata of the certificate", body = CertificateMetadata),
+        (status = 404, description = "Certificate not found"),
+    )
+)]
+pub async fn get_certificate(
+    State(repository): State<Arc<dyn Storage>>,
+    Path(id): Path<String>,
+) -> Result<Json<CertificateMetadata>, (StatusCode, String)> {
+    get_certificate_metadata_service(&id, repository)
+        .await
+        .map(Json)
+        .map_err(certificate_error_response)
+}
@@ -4,7 +4,6 @@ use axum::http::StatusCode;
 use axum::Json;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tokio::sync::Mutex;
 use utoipa::ToSchema;
 
 #[derive(Serialize, Deserialize, ToSchema)]
@@ -27,11 +26,10 @@ pub struct ChallengePresenceStats {
     )
 )]
 pub async fn get_challenge_presence(
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
     Path(challenge_id): Path<String>,
 ) -> Result<Json<ChallengePresenceStats>, (StatusCode, String)> {
-    let repo = repository.lock().await;
-    let count = repo
+    let count = repository
         .get_active_count(&challenge_id)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
@@ -54,11 +52,10 @@ pub async fn get_challenge_presence(
     )
 )]
 pub async fn record_challenge_presence(
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
     Path(challenge_id): Path<String>,
 ) -> Result<Json<ChallengePresenceStats>, (StatusCode, String)> {
-    let mut repo = repository.lock().await;
-    let count = repo
+    let count = repository
         .record_presence(&challenge_id)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
@@ -2,7 +2,6 @@ use crate::storage::Storage;
 use axum::extract::{Path, State};
 use axum::Json;
 use std::sync::Arc;
-use tokio::sync::Mutex;
 use yew_chat::prelude::{ReceiveError, ReceiveResponse, SendError, SendRequest};
 
 #[utoipa::path(
@@ -18,14 +17,12 @@ use yew_chat::prelude::{ReceiveError, ReceiveResponse, SendError, SendRequest};
     )
 )]
 pub async fn send_message(
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
     Path(channel): Path<String>,
     Json(message): Json<SendRequest>,
 ) -> Result<Json<()>, Json<SendError>> {
     let message = message.message;
     repository
-        .lock()
-        .await
         .send_message(&channel, message)
         .await
         .map(|_| Json(()))
@@ -44,12 +41,11 @@ pub async fn send_message(
     )
 )]
 pub async fn receive_messages(
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
     Path(channel): Path<String>,
 ) -> Result<Json<ReceiveResponse>, Json<ReceiveError>> {
-    let lock = repository.lock().await;
-
-    lock.receive_messages(&channel)
+    repository
+        .receive_messages(&channel)
         .await
         .map(|messages| Json(ReceiveResponse { messages }))
         .map_err(Json)
@@ -1,15 +1,22 @@
-use axum::extract::Json;
+use crate::services::claim_eligibility::ClaimPolicy;
+use crate::services::claim_error::claim_error_response;
+use crate::services::claim_queue::ClaimQueue;
+use crate::services::v1::claim::claim_tokens_service;
+use crate::storage::{ClaimRecord, ClaimStatus, Storage};
+use axum::extract::{Json, State};
+use axum::http::StatusCode;
+use axum::Extension;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use utoipa::ToSchema;
 
-#[cfg(feature = "ton")]
-use crate::services::v1::claim::claim_tokens_service;
-
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ClaimRequest {
+    /// Id of the claim request, must be positive
     #[schema(example = 123456)]
     pub id: i64,
+    /// Profile id of the player, the claimable amount is computed from their progress
     #[schema(example = "example_user")]
     pub user: String,
     #[serde(rename = "type")]
@@ -21,7 +28,6 @@ pub struct ClaimRequest {
     pub amount: u32,
 }
 
-#[cfg(feature = "ton")]
 #[utoipa::path(
     post,
     operation_id = "claim_v1",
@@ -29,13 +35,31 @@ pub struct ClaimRequest {
     path = "/claim",
     context_path = "/api/v1",
     request_body = ClaimRequest,
+    security(("admin_token" = [])),
     responses(
-        (status = 200, description = "Token claimed successfully"),
-        (status = 400, description = "Invalid request data"),
+        (status = 200, description = "The tokens of the claim were sent", body = ClaimRecord),
+        (status = 202, description = "The claim is queued, repeat the request to poll its status", body = ClaimRecord),
+        (status = 400, description = "Invalid request data or destination address"),
+        (status = 401, description = "Missing or invalid admin credentials"),
+        (status = 403, description = "The amount exceeds the tokens the player earned"),
+        (status = 404, description = "The player is not registered"),
+        (status = 409, description = "The claim id was used for another claim"),
+        (status = 429, description = "Daily cap reached or claimed too recently"),
+        (status = 500, description = "The claim failed"),
     )
 )]
 pub async fn claim_tokens(
+    State(repository): State<Arc<dyn Storage>>,
+    Extension(policy): Extension<ClaimPolicy>,
+    Extension(queue): Extension<ClaimQueue>,
     Json(payload): Json<ClaimRequest>,
-) -> Result<Json<&'static str>, (axum::http::StatusCode, String)> {
-    claim_tokens_service(payload).await
+) -> Result<(StatusCode, Json<ClaimRecord>), (StatusCode, String)> {
+    let claim = claim_tokens_service(payload, &policy, &queue, repository)
+        .await
+        .map_err(claim_error_response)?;
+    let status = match claim.status {
+        ClaimStatus::Sent => StatusCode::OK,
+        _ => StatusCode::ACCEPTED,
+    };
+    Ok((status, Json(claim)))
 }
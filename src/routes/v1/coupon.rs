@@ -1,18 +1,22 @@
-use crate::services::v1::coupon::{self, CouponError};
-use crate::storage::Storage;
-use axum::extract::{Path, State};
-use axum::http::StatusCode;
+use crate::routes::caller::CallerId;
+use crate::services::v1::coupon::{self, CampaignSpec, CouponError};
+use crate::storage::{CouponCampaign, CouponRedemption, CouponUpdate, RepositoryError, Storage};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::Json;
+use chrono::{DateTime, Utc};
 use konnektoren_core::prelude::{Coupon, CouponRedemptionError};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct CouponResponse {
     #[schema()]
     pub coupon: Option<Coupon>,
+    /// Inactive coupons can neither be validated nor redeemed
+    pub active: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
@@ -21,6 +25,50 @@ pub struct CouponsResponse {
     pub coupons: Vec<Coupon>,
 }
 
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct CouponRedemptionsResponse {
+    #[schema()]
+    pub redemptions: Vec<CouponRedemption>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct CampaignRequest {
+    /// Unique name of the campaign
+    pub name: String,
+    /// Prepended to every generated code
+    #[serde(default)]
+    pub prefix: String,
+    /// Number of coupons to generate
+    pub count: usize,
+    pub challenge_ids: Vec<String>,
+    /// Uses of every coupon
+    pub uses: u32,
+    #[schema(value_type = String, format = DateTime)]
+    pub expiration_date: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct CampaignResponse {
+    pub campaign: CouponCampaign,
+    pub coupons: Vec<Coupon>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CampaignFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct CampaignFormatQuery {
+    /// Response format, `json` (default) or `csv`
+    #[serde(default)]
+    #[param(inline)]
+    pub format: CampaignFormat,
+}
+
 pub type CouponRequest = Coupon;
 
 fn coupon_example() -> Coupon {
@@ -39,14 +87,16 @@ fn coupon_example() -> Coupon {
     path = "/coupons",
     context_path = "/api/v1",
     request_body(content = CouponRequest, example = json!(coupon_example())),
+    security(("admin_token" = [])),
     responses(
         (status = 201, description = "Coupon created successfully", body = CouponResponse),
         (status = 409, description = "Coupon already exists"),
         (status = 500, description = "Internal server error"),
+        (status = 401, description = "Missing or invalid admin credentials"),
     )
 )]
 pub async fn create_handler(
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
     Json(coupon): Json<Coupon>,
 ) -> Result<(StatusCode, Json<CouponResponse>), (StatusCode, String)> {
     match coupon::create_coupon(coupon, repository).await {
@@ -54,8 +104,13 @@ pub async fn create_handler(
             StatusCode::CREATED,
             Json(CouponResponse {
                 coupon: Some(saved_coupon),
+                active: true,
             }),
         )),
+        Err(CouponError::Repository(RepositoryError::AlreadyExists(code))) => Err((
+            StatusCode::CONFLICT,
+            format!("Coupon {} already exists", code),
+        )),
         Err(err) => Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
     }
 }
@@ -77,30 +132,123 @@ pub async fn create_handler(
 )]
 pub async fn get_handler(
     Path(code): Path<String>,
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
 ) -> Result<Json<CouponResponse>, (StatusCode, String)> {
-    match coupon::get_coupon(code, repository).await {
-        Ok(Some(coupon)) => Ok(Json(CouponResponse {
+    let coupon = match coupon::get_coupon(code.clone(), repository.clone()).await {
+        Ok(Some(coupon)) => coupon,
+        Ok(None) => return Err((StatusCode::NOT_FOUND, "Coupon not found".to_string())),
+        Err(err) => return Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
+    };
+    match coupon::is_coupon_active(code, repository).await {
+        Ok(active) => Ok(Json(CouponResponse {
             coupon: Some(coupon),
+            active,
         })),
-        Ok(None) => Err((StatusCode::NOT_FOUND, "Coupon not found".to_string())),
+        Err(CouponError::Repository(RepositoryError::NotFound(_))) => {
+            Err((StatusCode::NOT_FOUND, "Coupon not found".to_string()))
+        }
         Err(err) => Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
     }
 }
 
+#[utoipa::path(
+    patch,
+    operation_id = "update_coupon_v1",
+    tag = "coupon_v1",
+    path = "/coupons/{code}",
+    params(
+        ("code", description = "Coupon code to update"),
+    ),
+    context_path = "/api/v1",
+    request_body(content = CouponUpdate, example = json!(coupon_update_example())),
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Coupon updated", body = CouponResponse),
+        (status = 400, description = "Invalid update"),
+        (status = 404, description = "Coupon not found"),
+        (status = 500, description = "Internal server error"),
+        (status = 401, description = "Missing or invalid admin credentials"),
+    )
+)]
+pub async fn update_handler(
+    Path(code): Path<String>,
+    State(repository): State<Arc<dyn Storage>>,
+    Json(update): Json<CouponUpdate>,
+) -> Result<Json<CouponResponse>, (StatusCode, String)> {
+    let updated = match coupon::update_coupon(code.clone(), update, repository.clone()).await {
+        Ok(updated) => updated,
+        Err(err) => return Err(coupon_error_response(err)),
+    };
+    let active = coupon::is_coupon_active(code, repository)
+        .await
+        .map_err(coupon_error_response)?;
+    Ok(Json(CouponResponse {
+        coupon: Some(updated),
+        active,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    operation_id = "delete_coupon_v1",
+    tag = "coupon_v1",
+    path = "/coupons/{code}",
+    params(
+        ("code", description = "Coupon code to delete"),
+    ),
+    context_path = "/api/v1",
+    security(("admin_token" = [])),
+    responses(
+        (status = 204, description = "Coupon deleted"),
+        (status = 404, description = "Coupon not found"),
+        (status = 500, description = "Internal server error"),
+        (status = 401, description = "Missing or invalid admin credentials"),
+    )
+)]
+pub async fn delete_handler(
+    Path(code): Path<String>,
+    State(repository): State<Arc<dyn Storage>>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    coupon::delete_coupon(code, repository)
+        .await
+        .map_err(coupon_error_response)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn coupon_update_example() -> CouponUpdate {
+    CouponUpdate {
+        expiration_date: Some(chrono::Utc::now() + chrono::Duration::days(30)),
+        uses_remaining: Some(10),
+        challenge_ids: None,
+        active: Some(true),
+    }
+}
+
+fn coupon_error_response(err: CouponError) -> (StatusCode, String) {
+    match err {
+        CouponError::Repository(RepositoryError::NotFound(_)) => {
+            (StatusCode::NOT_FOUND, "Coupon not found".to_string())
+        }
+        CouponError::InvalidUpdate(_) => (StatusCode::BAD_REQUEST, err.to_string()),
+        err => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
 #[utoipa::path(
     get,
     operation_id = "list_coupons_v1",
     tag = "coupon_v1",
     path = "/coupons",
     context_path = "/api/v1",
+    security(("admin_token" = [])),
     responses(
         (status = 200, description = "List of all coupons", body = CouponsResponse),
         (status = 500, description = "Internal server error"),
+        (status = 401, description = "Missing or invalid admin credentials"),
     )
 )]
 pub async fn list_handler(
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
 ) -> Result<Json<CouponsResponse>, (StatusCode, String)> {
     match coupon::list_coupons(repository).await {
         Ok(coupons) => Ok(Json(CouponsResponse { coupons })),
@@ -128,7 +276,7 @@ pub async fn list_handler(
 )]
 pub async fn validate_handler(
     Path((code, challenge_id)): Path<(String, String)>,
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
 ) -> Result<StatusCode, (StatusCode, String)> {
     match coupon::validate_coupon(code, challenge_id, repository).await {
         Ok(true) => Ok(StatusCode::OK),
@@ -148,27 +296,28 @@ pub async fn validate_handler(
     params(
         ("code", description = "Coupon code to redeem"),
         ("challenge_id", description = "Challenge ID to redeem for"),
+        ("X-Profile-ID" = String, Header, description = "Profile redeeming the coupon"),
+        ("Authorization" = String, Header, description = "`Bearer <token>` with the owner token or a session token of the profile"),
     ),
     context_path = "/api/v1",
     responses(
         (status = 200, description = "Coupon redeemed successfully"),
-        (status = 403, description = "Coupon not valid for this challenge"),
-        (status = 404, description = "Coupon not found"),
-        (status = 409, description = "Failed to redeem coupon"),
+        (status = 401, description = "Missing profile id or token"),
+        (status = 403, description = "Coupon not valid for this challenge, or the token does not belong to the profile"),
+        (status = 404, description = "Coupon or profile not found"),
+        (status = 409, description = "Coupon already redeemed by this caller"),
         (status = 410, description = "Coupon expired or no uses remaining"),
         (status = 500, description = "Internal server error"),
     )
 )]
 pub async fn redeem_handler(
     Path((code, challenge_id)): Path<(String, String)>,
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    CallerId(caller_id): CallerId,
+    State(repository): State<Arc<dyn Storage>>,
 ) -> Result<StatusCode, (StatusCode, String)> {
-    match coupon::redeem_coupon(code, challenge_id, repository).await {
+    match coupon::redeem_coupon(code, challenge_id, caller_id, repository).await {
         Ok(true) => Ok(StatusCode::OK),
         Ok(false) => Err((StatusCode::GONE, "Coupon cannot be redeemed".to_string())),
-        Err(CouponError::Repository(err)) => {
-            Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
-        }
         Err(CouponError::Redemption(err)) => {
             let (status, message) = match err {
                 CouponRedemptionError::Expired(date) => {
@@ -186,5 +335,193 @@ pub async fn redeem_handler(
             };
             Err((status, message))
         }
+        Err(err) => Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
+    }
+}
+
+#[utoipa::path(
+    get,
+    operation_id = "list_coupon_redemptions_v1",
+    tag = "coupon_v1",
+    path = "/coupons/{code}/redemptions",
+    params(
+        ("code", description = "Coupon code to list redemptions for"),
+    ),
+    context_path = "/api/v1",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Redemptions of the coupon, oldest first", body = CouponRedemptionsResponse),
+        (status = 404, description = "Coupon not found"),
+        (status = 500, description = "Internal server error"),
+        (status = 401, description = "Missing or invalid admin credentials"),
+    )
+)]
+pub async fn redemptions_handler(
+    Path(code): Path<String>,
+    State(repository): State<Arc<dyn Storage>>,
+) -> Result<Json<CouponRedemptionsResponse>, (StatusCode, String)> {
+    match coupon::list_redemptions(code, repository).await {
+        Ok(Some(redemptions)) => Ok(Json(CouponRedemptionsResponse { redemptions })),
+        Ok(None) => Err((StatusCode::NOT_FOUND, "Coupon not found".to_string())),
+        Err(err) => Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
+    }
+}
+
+fn campaign_example() -> CampaignRequest {
+    CampaignRequest {
+        name: "class-5a".to_string(),
+        prefix: "CLASS5A-".to_string(),
+        count: 30,
+        challenge_ids: vec!["challenge1".to_string()],
+        uses: 1,
+        expiration_date: chrono::Utc::now() + chrono::Duration::days(30),
+    }
+}
+
+#[utoipa::path(
+    post,
+    operation_id = "create_coupon_campaign_v1",
+    tag = "coupon_v1",
+    path = "/coupon-campaigns",
+    params(CampaignFormatQuery),
+    context_path = "/api/v1",
+    request_body(content = CampaignRequest, example = json!(campaign_example())),
+    security(("admin_token" = [])),
+    responses(
+        (status = 201, description = "Campaign created, coupons as JSON or CSV", body = CampaignResponse),
+        (status = 400, description = "Invalid campaign settings"),
+        (status = 409, description = "Campaign already exists"),
+        (status = 500, description = "Internal server error"),
+        (status = 401, description = "Missing or invalid admin credentials"),
+    )
+)]
+pub async fn create_campaign_handler(
+    Query(query): Query<CampaignFormatQuery>,
+    State(repository): State<Arc<dyn Storage>>,
+    Json(request): Json<CampaignRequest>,
+) -> Result<Response, (StatusCode, String)> {
+    let spec = CampaignSpec {
+        name: request.name,
+        prefix: request.prefix,
+        count: request.count,
+        challenge_ids: request.challenge_ids,
+        uses: request.uses,
+        expiration_date: request.expiration_date,
+    };
+
+    match coupon::create_campaign(spec, repository).await {
+        Ok((campaign, coupons)) => Ok((
+            StatusCode::CREATED,
+            campaign_response(campaign, coupons, query.format),
+        )
+            .into_response()),
+        Err(err @ CouponError::InvalidCampaign(_)) => {
+            Err((StatusCode::BAD_REQUEST, err.to_string()))
+        }
+        Err(err @ CouponError::CampaignExists(_)) => Err((StatusCode::CONFLICT, err.to_string())),
+        Err(err) => Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
+    }
+}
+
+#[utoipa::path(
+    get,
+    operation_id = "get_coupon_campaign_v1",
+    tag = "coupon_v1",
+    path = "/coupon-campaigns/{name}",
+    params(
+        ("name", description = "Name of the campaign"),
+        CampaignFormatQuery,
+    ),
+    context_path = "/api/v1",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Campaign coupons as JSON or CSV", body = CampaignResponse),
+        (status = 404, description = "Campaign not found"),
+        (status = 500, description = "Internal server error"),
+        (status = 401, description = "Missing or invalid admin credentials"),
+    )
+)]
+pub async fn get_campaign_handler(
+    Path(name): Path<String>,
+    Query(query): Query<CampaignFormatQuery>,
+    State(repository): State<Arc<dyn Storage>>,
+) -> Result<Response, (StatusCode, String)> {
+    match coupon::get_campaign(name, repository).await {
+        Ok(Some((campaign, coupons))) => Ok(campaign_response(campaign, coupons, query.format)),
+        Ok(None) => Err((StatusCode::NOT_FOUND, "Campaign not found".to_string())),
+        Err(err) => Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
+    }
+}
+
+fn campaign_response(
+    campaign: CouponCampaign,
+    coupons: Vec<Coupon>,
+    format: CampaignFormat,
+) -> Response {
+    match format {
+        CampaignFormat::Json => Json(CampaignResponse { campaign, coupons }).into_response(),
+        CampaignFormat::Csv => (
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}.csv\"", file_name(&campaign.name)),
+                ),
+            ],
+            coupons_csv(&coupons),
+        )
+            .into_response(),
+    }
+}
+
+/// Keeps only characters that are safe in a header and a file name.
+fn file_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect()
+}
+
+fn coupons_csv(coupons: &[Coupon]) -> String {
+    let mut csv = String::from("code,challenge_ids,uses_remaining,expiration_date\n");
+    for coupon in coupons {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            csv_field(&coupon.code),
+            csv_field(&coupon.challenge_ids.join(";")),
+            coupon.uses_remaining,
+            coupon.expiration_date.to_rfc3339(),
+        ));
+    }
+    csv
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coupons_csv() {
+        let expiration_date = "2030-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let coupons = vec![Coupon::new(
+            "CLASS-ABC".to_string(),
+            vec!["challenge,1".to_string(), "challenge2".to_string()],
+            2,
+            expiration_date,
+        )];
+
+        assert_eq!(
+            coupons_csv(&coupons),
+            "code,challenge_ids,uses_remaining,expiration_date\n\
+             CLASS-ABC,\"challenge,1;challenge2\",2,2030-01-01T00:00:00+00:00\n"
+        );
     }
 }
@@ -0,0 +1,100 @@
+use crate::issuer::{JettonInfo, TokenIssuerError};
+use crate::services::faucet::{FaucetInfo, FaucetMonitor};
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct JettonBalanceResponse {
+    #[schema(example = "0QB-_k5Rule-nKr6HWPIlkDyHb1xhDdbI77q7uwAFqmUmKjP")]
+    pub address: String,
+    /// Whole jettons held by the address
+    #[schema(value_type = u64, example = 5)]
+    pub balance: u128,
+}
+
+#[utoipa::path(
+    get,
+    operation_id = "get_faucet_v1",
+    tag = "faucet_v1",
+    path = "/faucet",
+    context_path = "/api/v1",
+    responses(
+        (status = 200, description = "Balances of the faucet wallet", body = FaucetInfo),
+        (status = 503, description = "The TON network is unreachable"),
+    )
+)]
+pub async fn get_faucet(
+    Extension(monitor): Extension<Arc<FaucetMonitor>>,
+) -> Result<Json<FaucetInfo>, (StatusCode, String)> {
+    monitor
+        .faucet_info(Utc::now())
+        .await
+        .map(Json)
+        .map_err(issuer_error_response)
+}
+
+#[utoipa::path(
+    get,
+    operation_id = "get_jetton_v1",
+    tag = "faucet_v1",
+    path = "/jetton",
+    context_path = "/api/v1",
+    responses(
+        (status = 200, description = "Data and metadata of the jetton", body = JettonInfo),
+        (status = 503, description = "The TON network is unreachable"),
+    )
+)]
+pub async fn get_jetton(
+    Extension(monitor): Extension<Arc<FaucetMonitor>>,
+) -> Result<Json<JettonInfo>, (StatusCode, String)> {
+    monitor
+        .jetton_info(Utc::now())
+        .await
+        .map(Json)
+        .map_err(issuer_error_response)
+}
+
+#[utoipa::path(
+    get,
+    operation_id = "get_jetton_balance_v1",
+    tag = "faucet_v1",
+    path = "/jetton/balance/{address}",
+    context_path = "/api/v1",
+    params(
+        ("address" = String, Path, description = "Address of the wallet owning the jettons"),
+    ),
+    responses(
+        (status = 200, description = "Jetton balance of the address", body = JettonBalanceResponse),
+        (status = 400, description = "Invalid address"),
+        (status = 503, description = "The TON network is unreachable"),
+    )
+)]
+pub async fn get_jetton_balance(
+    Extension(monitor): Extension<Arc<FaucetMonitor>>,
+    Path(address): Path<String>,
+) -> Result<Json<JettonBalanceResponse>, (StatusCode, String)> {
+    let balance = monitor
+        .jetton_balance(&address, Utc::now())
+        .await
+        .map_err(issuer_error_response)?;
+    Ok(Json(JettonBalanceResponse { address, balance }))
+}
+
+fn issuer_error_response(err: TokenIssuerError) -> (StatusCode, String) {
+    let status = match &err {
+        TokenIssuerError::InvalidAddress(_) => StatusCode::BAD_REQUEST,
+        TokenIssuerError::Network(_) => {
+            log::warn!("Faucet data unavailable: {}", err);
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        TokenIssuerError::InsufficientFunds { .. } | TokenIssuerError::Signing(_) => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, err.to_string())
+}
@@ -1,21 +1,101 @@
+use crate::middleware::auth::bearer_token;
+use crate::routes::caller::PROFILE_ID_HEADER;
 use crate::services::v1::leaderboard::{
-    add_performance_record, fetch_all_performance_records, AddPerformanceRecordResult,
+    add_performance_record, fetch_leaderboard_page, fetch_player_rank, player_rank_error_response,
+    submission_error_response, submission_player, AddPerformanceRecordResult, LeaderboardConfig,
+    LeaderboardPeriod, PlayerRank, LEADERBOARD_NAMESPACE,
 };
 use crate::storage::Storage;
-use axum::extract::{Path, State};
-use axum::http::StatusCode;
-use axum::Json;
-use chrono::Utc;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::{Extension, Json};
+use chrono::{NaiveDate, Utc};
 use konnektoren_core::challenges::PerformanceRecord;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
+
+/// A performance record with its position on the leaderboard.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LeaderboardEntry {
+    /// Position on the whole leaderboard, starting at 1
+    #[schema(example = 1)]
+    pub rank: usize,
+    #[serde(flatten)]
+    pub record: PerformanceRecord,
+}
 
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct LeaderboardV1Response {
     #[schema()]
-    pub performance_records: Vec<PerformanceRecord>,
+    pub performance_records: Vec<LeaderboardEntry>,
+    /// Records on the whole leaderboard
+    #[schema(example = 42)]
+    pub total: usize,
+    pub period: LeaderboardPeriod,
+    #[schema(example = 0)]
+    pub offset: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 10)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct LeaderboardQuery {
+    /// Time span of the board, all-time by default
+    #[serde(default)]
+    #[param(inline)]
+    pub period: LeaderboardPeriod,
+    /// A day in the period to read, the current period by default
+    #[param(value_type = Option<String>, format = Date, example = "2024-05-01")]
+    pub date: Option<NaiveDate>,
+    /// Best records to skip
+    #[serde(default)]
+    pub offset: usize,
+    /// Most records to return, all records by default
+    pub limit: Option<usize>,
+}
+
+/// The player to look up, by profile name or by profile id.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct PlayerRankQuery {
+    /// Name the player submits records under
+    pub profile_name: Option<String>,
+    /// Id of the profile, used if no `profile_name` is given and required if
+    /// players are keyed by profile id
+    pub profile_id: Option<String>,
+}
+
+/// The namespace of the leaderboard of `challenge_id`. Period boards are kept under
+/// namespaces with `:`, so challenge ids containing one are rejected.
+fn challenge_namespace(challenge_id: &str) -> Result<&str, (StatusCode, String)> {
+    if challenge_id.contains(':') {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Invalid challenge id {}", challenge_id),
+        ));
+    }
+    Ok(challenge_id)
+}
+
+async fn leaderboard_response(
+    namespace: &str,
+    query: LeaderboardQuery,
+    config: &LeaderboardConfig,
+    repository: Arc<dyn Storage>,
+) -> Result<Json<LeaderboardV1Response>, (StatusCode, String)> {
+    let day = query.date.unwrap_or_else(|| config.local_day(Utc::now()));
+    let namespace = query.period.namespace(namespace, day);
+    let page = fetch_leaderboard_page(&namespace, query.offset, query.limit, repository)
+        .await
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    Ok(Json(LeaderboardV1Response {
+        performance_records: page.entries,
+        total: page.total,
+        period: query.period,
+        offset: query.offset,
+        limit: query.limit,
+    }))
 }
 
 fn performance_record_example() -> PerformanceRecord {
@@ -35,21 +115,18 @@ fn performance_record_example() -> PerformanceRecord {
     tag = "leaderboard_v1",
     path = "/leaderboard",
     context_path = "/api/v1",
+    params(LeaderboardQuery),
     responses(
         (status = 200, description = "Leaderboard loaded successfully", body = LeaderboardV1Response),
         (status = 400, description = "Invalid request data"),
     )
 )]
 pub async fn get_leaderboard(
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    Query(query): Query<LeaderboardQuery>,
+    State(repository): State<Arc<dyn Storage>>,
+    Extension(config): Extension<LeaderboardConfig>,
 ) -> Result<Json<LeaderboardV1Response>, (StatusCode, String)> {
-    let namespace = "leaderboard";
-    let performance_records = fetch_all_performance_records(namespace, repository)
-        .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
-    Ok(Json(LeaderboardV1Response {
-        performance_records,
-    }))
+    leaderboard_response(LEADERBOARD_NAMESPACE, query, &config, repository).await
 }
 
 #[utoipa::path(
@@ -58,6 +135,10 @@ pub async fn get_leaderboard(
     tag = "leaderboard_v1",
     path = "/leaderboard/{challenge_id}",
     context_path = "/api/v1",
+    params(
+        ("challenge_id" = String, Path, description = "Id of the challenge, without `:`"),
+        LeaderboardQuery,
+    ),
     responses(
         (status = 200, description = "Leaderboard loaded successfully", body = LeaderboardV1Response),
         (status = 400, description = "Invalid request data"),
@@ -65,15 +146,41 @@ pub async fn get_leaderboard(
 )]
 pub async fn get_challenge_leaderboard(
     Path(challenge_id): Path<String>,
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    Query(query): Query<LeaderboardQuery>,
+    State(repository): State<Arc<dyn Storage>>,
+    Extension(config): Extension<LeaderboardConfig>,
 ) -> Result<Json<LeaderboardV1Response>, (StatusCode, String)> {
-    let namespace = challenge_id.as_str();
-    let performance_records = fetch_all_performance_records(namespace, repository)
+    let namespace = challenge_namespace(&challenge_id)?;
+    leaderboard_response(namespace, query, &config, repository).await
+}
+
+#[utoipa::path(
+    get,
+    operation_id = "get_player_rank_v1",
+    tag = "leaderboard_v1",
+    path = "/leaderboard/{challenge_id}/rank",
+    context_path = "/api/v1",
+    params(
+        ("challenge_id" = String, Path, description = "Id of the challenge, without `:`"),
+        PlayerRankQuery,
+    ),
+    responses(
+        (status = 200, description = "Rank of the player's best record", body = PlayerRank),
+        (status = 400, description = "Invalid challenge id, neither profile_name nor profile_id given, or no profile_id if players are keyed by profile id"),
+        (status = 404, description = "Unknown profile or no records of the player"),
+    )
+)]
+pub async fn get_player_rank(
+    Path(challenge_id): Path<String>,
+    Query(query): Query<PlayerRankQuery>,
+    State(repository): State<Arc<dyn Storage>>,
+    Extension(config): Extension<LeaderboardConfig>,
+) -> Result<Json<PlayerRank>, (StatusCode, String)> {
+    let namespace = challenge_namespace(&challenge_id)?;
+    fetch_player_rank(namespace, query, &config, repository)
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
-    Ok(Json(LeaderboardV1Response {
-        performance_records,
-    }))
+        .map(Json)
+        .map_err(player_rank_error_response)
 }
 
 #[utoipa::path(
@@ -83,21 +190,32 @@ pub async fn get_challenge_leaderboard(
     path = "/performance-record",
     context_path = "/api/v1",
     request_body(content = PerformanceRecord, example = json!(performance_record_example())),
+    params(
+        ("X-Profile-ID" = Option<String>, Header, description = "Profile the record is kept for, required if players are keyed by profile id and for token claims"),
+        ("Authorization" = Option<String>, Header, description = "`Bearer <token>` with a token of the profile, required with `X-Profile-ID`"),
+    ),
     responses(
-        (status = 200, description = "Performance record added successfully"),
+        (status = 200, description = "Performance record added, or the player's earlier record was at least as good"),
         (status = 400, description = "Invalid request data"),
+        (status = 401, description = "Missing profile id or token"),
+        (status = 403, description = "The token does not belong to the profile"),
+        (status = 404, description = "Profile not registered"),
     )
 )]
 pub async fn post_performance_record(
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
+    Extension(config): Extension<LeaderboardConfig>,
+    headers: HeaderMap,
     Json(performance_record): Json<PerformanceRecord>,
 ) -> Result<Json<PerformanceRecord>, (StatusCode, String)> {
-    let namespace = "leaderboard";
-    match add_performance_record(namespace, performance_record.clone(), repository).await {
-        Ok(AddPerformanceRecordResult::Success(record)) => Ok(Json(record)),
-        Ok(AddPerformanceRecordResult::LimitReached) => Ok(Json(performance_record)),
-        Err(err) => Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
-    }
+    submit_performance_record(
+        LEADERBOARD_NAMESPACE,
+        performance_record,
+        &headers,
+        &config,
+        repository,
+    )
+    .await
 }
 
 #[utoipa::path(
@@ -107,20 +225,65 @@ pub async fn post_performance_record(
     path = "/performance-record/{challenge_id}",
     context_path = "/api/v1",
     request_body(content = PerformanceRecord, example = json!(performance_record_example())),
+    params(
+        ("X-Profile-ID" = Option<String>, Header, description = "Profile the record is kept for, required if players are keyed by profile id and for token claims"),
+        ("Authorization" = Option<String>, Header, description = "`Bearer <token>` with a token of the profile, required with `X-Profile-ID`"),
+    ),
     responses(
-        (status = 200, description = "Performance record added successfully"),
+        (status = 200, description = "Performance record added, or the player's earlier record was at least as good"),
         (status = 400, description = "Invalid request data"),
+        (status = 401, description = "Missing profile id or token"),
+        (status = 403, description = "The token does not belong to the profile"),
+        (status = 404, description = "Profile not registered"),
     )
 )]
 pub async fn post_challenge_performance_record(
     Path(challenge_id): Path<String>,
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
+    Extension(config): Extension<LeaderboardConfig>,
+    headers: HeaderMap,
     Json(performance_record): Json<PerformanceRecord>,
 ) -> Result<Json<PerformanceRecord>, (StatusCode, String)> {
-    let namespace = challenge_id.as_str();
-    match add_performance_record(namespace, performance_record.clone(), repository).await {
+    let namespace = challenge_namespace(&challenge_id)?;
+    submit_performance_record(namespace, performance_record, &headers, &config, repository).await
+}
+
+/// Adds a record for the player it belongs to under the configured player key.
+async fn submit_performance_record(
+    namespace: &str,
+    performance_record: PerformanceRecord,
+    headers: &HeaderMap,
+    config: &LeaderboardConfig,
+    repository: Arc<dyn Storage>,
+) -> Result<Json<PerformanceRecord>, (StatusCode, String)> {
+    let profile_id = headers
+        .get(PROFILE_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+    let submitter = submission_player(
+        &performance_record,
+        profile_id,
+        bearer_token(headers),
+        config,
+        repository.clone(),
+    )
+    .await
+    .map_err(submission_error_response)?;
+
+    match add_performance_record(
+        namespace,
+        &submitter,
+        performance_record.clone(),
+        config,
+        repository,
+    )
+    .await
+    {
         Ok(AddPerformanceRecordResult::Success(record)) => Ok(Json(record)),
-        Ok(AddPerformanceRecordResult::LimitReached) => Ok(Json(performance_record)),
+        Ok(AddPerformanceRecordResult::LimitReached | AddPerformanceRecordResult::NotImproved) => {
+            Ok(Json(performance_record))
+        }
         Err(err) => Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
     }
 }
@@ -1,10 +1,13 @@
+pub mod certificate;
 pub mod challenge_presence;
 pub mod claim;
 pub mod coupon;
+pub mod faucet;
 pub mod leaderboard;
 pub mod profile;
 pub mod review;
 mod router;
+pub mod wallet;
 
 #[cfg(feature = "chat")]
 pub mod chat;
@@ -1,12 +1,15 @@
-use crate::services::v1::profile::{fetch_all_profiles, fetch_profile, save_profile};
-use crate::storage::Storage;
+use crate::middleware::auth::bearer_token;
+use crate::services::v1::profile::{
+    fetch_all_profiles, fetch_profile, issue_owner_token, save_profile, ProfileError,
+};
+use crate::storage::{RepositoryError, Storage};
 use axum::extract::{Path, State};
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::Json;
 use konnektoren_core::prelude::PlayerProfile;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tokio::sync::Mutex;
 use utoipa::ToSchema;
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
@@ -23,6 +26,16 @@ pub struct ProfilesV1Response {
 
 pub type ProfileV1Request = PlayerProfile;
 
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ProfileTokenV1Response {
+    pub profile_id: String,
+    /// Owner token of the profile, hand it to the player.
+    pub token: String,
+}
+
+/// Response header carrying the owner token of a newly registered profile.
+pub const PROFILE_TOKEN_HEADER: &str = "X-Profile-Token";
+
 fn profile_example() -> PlayerProfile {
     PlayerProfile::new("example_user_id".to_string())
 }
@@ -42,7 +55,7 @@ fn profile_example() -> PlayerProfile {
     )
 )]
 pub async fn get_profile(
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
     Path(profile_id): Path<String>,
 ) -> Result<Json<ProfileV1Response>, (StatusCode, String)> {
     let profile = fetch_profile(profile_id, repository)
@@ -59,13 +72,15 @@ pub async fn get_profile(
     tag = "profile_v1",
     path = "/profiles",
     context_path = "/api/v1",
+    security(("admin_token" = [])),
     responses(
         (status = 200, description = "Profiles loaded successfully", body = Vec<ProfileV1Response>),
         (status = 400, description = "Invalid request data"),
+        (status = 401, description = "Missing or invalid admin credentials"),
     )
 )]
 pub async fn get_all_profiles(
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
 ) -> Result<Json<ProfilesV1Response>, (StatusCode, String)> {
     let profiles = fetch_all_profiles(repository)
         .await
@@ -80,17 +95,75 @@ pub async fn get_all_profiles(
     path = "/profiles",
     context_path = "/api/v1",
     request_body(content = ProfileV1Request, example = json!(profile_example())),
+    params(
+        ("Authorization" = Option<String>, Header, description = "`Bearer <token>` with the owner token, required to update a registered profile"),
+    ),
     responses(
-        (status = 200, description = "Profile successfully saved", body = ProfileV1Response),
+        (status = 200, description = "Profile successfully saved", body = ProfileV1Request,
+            headers(("X-Profile-Token" = String, description = "Owner token, only sent when the profile was registered by this request"))),
         (status = 400, description = "Invalid request data"),
+        (status = 401, description = "The profile is registered and no token was sent"),
+        (status = 403, description = "The token does not own the profile"),
+        (status = 409, description = "The profile has no owner token yet, an admin has to issue it"),
     )
 )]
 pub async fn post_profile(
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
+    headers: HeaderMap,
     Json(profile): Json<PlayerProfile>,
-) -> Result<Json<PlayerProfile>, (StatusCode, String)> {
-    let profile = save_profile(profile, repository)
+) -> Result<Response, (StatusCode, String)> {
+    let saved = save_profile(profile, bearer_token(&headers), repository)
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
-    Ok(Json(profile))
+        .map_err(profile_error_response)?;
+
+    let mut response = Json(saved.profile).into_response();
+    if let Some(token) = saved.token {
+        let token = HeaderValue::from_str(&token)
+            .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        response.headers_mut().insert(PROFILE_TOKEN_HEADER, token);
+    }
+    Ok(response)
+}
+
+#[utoipa::path(
+    post,
+    operation_id = "issue_profile_token_v1",
+    tag = "profile_v1",
+    path = "/profiles/{profile_id}/token",
+    params(
+        ("profile_id", description = "Id of a stored profile without an owner token"),
+    ),
+    context_path = "/api/v1",
+    security(("admin_token" = [])),
+    responses(
+        (status = 201, description = "Owner token issued", body = ProfileTokenV1Response),
+        (status = 401, description = "Missing or invalid admin credentials"),
+        (status = 404, description = "Profile not found"),
+        (status = 409, description = "The profile already has an owner token"),
+    )
+)]
+pub async fn post_profile_token(
+    State(repository): State<Arc<dyn Storage>>,
+    Path(profile_id): Path<String>,
+) -> Result<(StatusCode, Json<ProfileTokenV1Response>), (StatusCode, String)> {
+    let token = issue_owner_token(&profile_id, repository)
+        .await
+        .map_err(profile_error_response)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(ProfileTokenV1Response { profile_id, token }),
+    ))
+}
+
+fn profile_error_response(err: ProfileError) -> (StatusCode, String) {
+    let status = match &err {
+        ProfileError::MissingToken(_) => StatusCode::UNAUTHORIZED,
+        ProfileError::InvalidToken(_) => StatusCode::FORBIDDEN,
+        ProfileError::Unowned(_) | ProfileError::Repository(RepositoryError::AlreadyExists(_)) => {
+            StatusCode::CONFLICT
+        }
+        ProfileError::Repository(RepositoryError::NotFound(_)) => StatusCode::NOT_FOUND,
+        ProfileError::Repository(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, err.to_string())
 }
@@ -4,7 +4,6 @@ use axum::http::StatusCode;
 use axum::Json;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tokio::sync::Mutex;
 use utoipa::ToSchema;
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, ToSchema)]
@@ -69,7 +68,7 @@ fn review_example() -> Review {
     )
 )]
 pub async fn get_reviews(
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
     Path(challenge_id): Path<String>,
 ) -> Result<Json<ReviewsResponse>, (StatusCode, String)> {
     let reviews = crate::services::v1::review::fetch_reviews(challenge_id, repository)
@@ -94,7 +93,7 @@ pub async fn get_reviews(
     )
 )]
 pub async fn get_average_rating(
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
     Path(challenge_id): Path<String>,
 ) -> Result<Json<f64>, (StatusCode, String)> {
     let average_rating =
@@ -117,7 +116,7 @@ pub async fn get_average_rating(
     )
 )]
 pub async fn post_review(
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
     Json(review): Json<Review>,
 ) -> Result<Json<Review>, (StatusCode, String)> {
     crate::services::v1::review::store_review(review.clone().into(), repository)
@@ -132,14 +131,16 @@ pub async fn post_review(
     tag = "review",
     path = "/reviews",
     context_path = "/api/v1",
+    security(("admin_token" = [])),
     responses(
         (status = 200, description = "Reviews loaded successfully", body = ReviewsResponse),
         (status = 400, description = "Invalid request data"),
         (status = 500, description = "Internal server error"),
+        (status = 401, description = "Missing or invalid admin credentials"),
     )
 )]
 pub async fn get_all_reviews(
-    State(repository): State<Arc<Mutex<dyn Storage>>>,
+    State(repository): State<Arc<dyn Storage>>,
 ) -> Result<Json<ReviewsResponse>, (StatusCode, String)> {
     let reviews = crate::services::v1::review::fetch_all_reviews(repository)
         .await
@@ -1,33 +1,113 @@
 use super::*;
+use crate::middleware::auth::{require_admin, AdminAuth};
+use crate::services::certificate::CertificateMinter;
+use crate::services::claim_eligibility::ClaimPolicy;
+use crate::services::claim_queue::ClaimQueue;
+use crate::services::faucet::FaucetMonitor;
+use crate::services::v1::leaderboard::LeaderboardConfig;
+use crate::services::v1::ton_proof::TonProofConfig;
 use crate::storage::{LeaderboardRepository, ProfileRepository, Storage};
 use axum::handler::Handler;
-use axum::routing::get;
-use axum::{routing::post, Router};
+use axum::middleware::from_fn_with_state;
+use axum::routing::{get, patch};
+use axum::{routing::post, Extension, Router};
 use std::sync::Arc;
-use tokio::sync::Mutex;
 
-pub fn create_router() -> Router<Arc<Mutex<dyn Storage>>> {
+/// Creates the v1 routes, admin-only routes require a key accepted by `admin_auth`.
+/// Wallet sign-ins are checked against `ton_proof`, token claims against `claim_policy`,
+/// and performance records are kept up to the capacities of `leaderboard_config`,
+/// which also sets the time zone of the period boards.
+/// Claims are only routed with a `claim_queue` to deliver the tokens, faucet
+/// and jetton data only with a `faucet_monitor`, and certificates are only
+/// minted with a `certificate_minter`. Their metadata is always served.
+pub fn create_router(
+    admin_auth: AdminAuth,
+    ton_proof: TonProofConfig,
+    claim_policy: ClaimPolicy,
+    leaderboard_config: LeaderboardConfig,
+    claim_queue: Option<ClaimQueue>,
+    faucet_monitor: Option<Arc<FaucetMonitor>>,
+    certificate_minter: Option<CertificateMinter>,
+) -> Router<Arc<dyn Storage>> {
+    let admin = from_fn_with_state(admin_auth, require_admin);
     let router = Router::new();
 
     let router = router.route("/profiles/:profile_id", get(profile::get_profile));
-    let router = router.route("/profiles", get(profile::get_all_profiles));
+    let router = router.route(
+        "/profiles",
+        get(profile::get_all_profiles).route_layer(admin.clone()),
+    );
     let router = router.route("/profiles", post(profile::post_profile));
+    let router = router.route(
+        "/profiles/:profile_id/token",
+        post(profile::post_profile_token).route_layer(admin.clone()),
+    );
 
-    #[cfg(feature = "ton")]
-    let router = router.route("/claim", post(claim::claim_tokens));
+    let router = router.route(
+        "/wallet/challenge",
+        post(wallet::challenge_handler).layer(Extension(ton_proof.clone())),
+    );
+    let router = router.route(
+        "/wallet/sign-in",
+        post(wallet::sign_in_handler).layer(Extension(ton_proof)),
+    );
+
+    let router = match claim_queue {
+        Some(claim_queue) => router.route(
+            "/claim",
+            post(claim::claim_tokens)
+                .layer(Extension(claim_policy))
+                .layer(Extension(claim_queue))
+                .route_layer(admin.clone()),
+        ),
+        None => router,
+    };
+    let router = match faucet_monitor {
+        Some(monitor) => router
+            .route(
+                "/faucet",
+                get(faucet::get_faucet).layer(Extension(monitor.clone())),
+            )
+            .route(
+                "/jetton",
+                get(faucet::get_jetton).layer(Extension(monitor.clone())),
+            )
+            .route(
+                "/jetton/balance/:address",
+                get(faucet::get_jetton_balance).layer(Extension(monitor)),
+            ),
+        None => router,
+    };
+    let router = match certificate_minter {
+        Some(minter) => router.route(
+            "/certificates",
+            post(certificate::post_certificate)
+                .layer(Extension(minter))
+                .route_layer(admin.clone()),
+        ),
+        None => router,
+    };
+    let router = router.route("/certificates/:id", get(certificate::get_certificate));
 
-    let router = router.route("/leaderboard", get(leaderboard::get_leaderboard));
+    let router = router.route(
+        "/leaderboard",
+        get(leaderboard::get_leaderboard).layer(Extension(leaderboard_config.clone())),
+    );
     let router = router.route(
         "/performance-record",
-        post(leaderboard::post_performance_record),
+        post(leaderboard::post_performance_record).layer(Extension(leaderboard_config.clone())),
     );
     let router = router.route(
         "/leaderboard/:challenge_id",
-        get(leaderboard::get_challenge_leaderboard),
+        get(leaderboard::get_challenge_leaderboard).layer(Extension(leaderboard_config.clone())),
+    );
+    let router = router.route(
+        "/leaderboard/:challenge_id/rank",
+        get(leaderboard::get_player_rank).layer(Extension(leaderboard_config.clone())),
     );
     let router = router.route(
         "/performance-record/:challenge_id",
-        post(leaderboard::post_challenge_performance_record),
+        post(leaderboard::post_challenge_performance_record).layer(Extension(leaderboard_config)),
     );
     let router = router.route(
         "/reviews/:challenge_id/average",
@@ -35,12 +115,26 @@ pub fn create_router() -> Router<Arc<Mutex<dyn Storage>>> {
     );
     let router = router.route("/reviews/:challenge_id", get(review::get_reviews));
     let router = router.route("/reviews", post(review::post_review));
-    let router = router.route("/reviews", get(review::get_all_reviews));
+    let router = router.route(
+        "/reviews",
+        get(review::get_all_reviews).route_layer(admin.clone()),
+    );
 
-    // Coupon routes
-    let router = router.route("/coupons", post(coupon::create_handler));
-    let router = router.route("/coupons", get(coupon::list_handler));
-    let router = router.route("/coupons/:code", get(coupon::get_handler));
+    // Coupon routes, validation and redemption stay public
+    let router = router.route(
+        "/coupons",
+        post(coupon::create_handler)
+            .get(coupon::list_handler)
+            .route_layer(admin.clone()),
+    );
+    let router = router.route(
+        "/coupons/:code",
+        get(coupon::get_handler).merge(
+            patch(coupon::update_handler)
+                .delete(coupon::delete_handler)
+                .route_layer(admin.clone()),
+        ),
+    );
     let router = router.route(
         "/coupons/:code/validate/:challenge_id",
         get(coupon::validate_handler),
@@ -49,6 +143,19 @@ pub fn create_router() -> Router<Arc<Mutex<dyn Storage>>> {
         "/coupons/:code/redeem/:challenge_id",
         post(coupon::redeem_handler),
     );
+    let router = router.route(
+        "/coupons/:code/redemptions",
+        get(coupon::redemptions_handler).route_layer(admin.clone()),
+    );
+    // Campaigns live outside /coupons, where any name is a coupon code
+    let router = router.route(
+        "/coupon-campaigns",
+        post(coupon::create_campaign_handler).route_layer(admin.clone()),
+    );
+    let router = router.route(
+        "/coupon-campaigns/:name",
+        get(coupon::get_campaign_handler).route_layer(admin),
+    );
 
     #[cfg(feature = "chat")]
     let router = router.route("/chat/send/:channel", post(chat::send_message));
@@ -66,3 +173,642 @@ pub fn create_router() -> Router<Arc<Mutex<dyn Storage>>> {
 
     router
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::claim_eligibility::tests::{eligible_player, policy};
+    use crate::services::v1::profile::save_profile;
+    use crate::storage::{CertificateRecord, CouponRepository, MemoryRepository};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use konnektoren_core::challenges::PerformanceRecord;
+    use konnektoren_core::prelude::{Coupon, PlayerProfile};
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    const ADMIN_KEY: &str = "admin-key";
+
+    fn app(storage: Arc<dyn Storage>) -> Router {
+        leaderboard_app(storage, LeaderboardConfig::default())
+    }
+
+    fn leaderboard_app(storage: Arc<dyn Storage>, leaderboard_config: LeaderboardConfig) -> Router {
+        let ton_proof = TonProofConfig::new("secret", ["konnektoren.help"]);
+        create_router(
+            AdminAuth::new([ADMIN_KEY]),
+            ton_proof,
+            policy(),
+            leaderboard_config,
+            None,
+            None,
+            None,
+        )
+        .with_state(storage)
+    }
+
+    fn get(uri: &str) -> Request<Body> {
+        Request::builder().uri(uri).body(Body::empty()).unwrap()
+    }
+
+    fn admin_get(uri: &str) -> Request<Body> {
+        Request::builder()
+            .uri(uri)
+            .header("Authorization", format!("Bearer {}", ADMIN_KEY))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_requests_to_different_repositories_run_in_parallel() {
+        let storage = Arc::new(MemoryRepository::with_blocking_leaderboard());
+        let app = app(storage.clone());
+
+        let leaderboard = tokio::spawn(app.clone().oneshot(get("/leaderboard")));
+        storage.leaderboard_entered().await;
+
+        // The leaderboard request is now blocked inside the storage
+        let response = tokio::time::timeout(
+            Duration::from_secs(1),
+            app.clone().oneshot(admin_get("/profiles")),
+        )
+        .await
+        .expect("profile request waited for the leaderboard request")
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = tokio::time::timeout(
+            Duration::from_secs(1),
+            app.oneshot(get("/challenges/challenge1/presence")),
+        )
+        .await
+        .expect("presence request waited for the leaderboard request")
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!leaderboard.is_finished());
+
+        storage.release_leaderboard();
+        let response = leaderboard.await.unwrap().unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    fn redeem(uri: &str, caller: Option<(&str, &str)>) -> Request<Body> {
+        let request = Request::builder().method("POST").uri(uri);
+        let request = match caller {
+            Some((profile_id, token)) => request
+                .header("X-Profile-ID", profile_id)
+                .header("Authorization", format!("Bearer {}", token)),
+            None => request,
+        };
+        request.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_coupon_redemptions_are_keyed_by_caller() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let coupon = Coupon::new(
+            "TEST123".to_string(),
+            vec!["challenge1".to_string()],
+            2,
+            chrono::Utc::now() + chrono::Duration::days(7),
+        );
+        CouponRepository::save(&*storage, coupon).await.unwrap();
+        let mut tokens = Vec::new();
+        for profile_id in ["alice", "bob"] {
+            let profile = PlayerProfile::new(profile_id.to_string());
+            let saved = save_profile(profile, None, storage.clone()).await.unwrap();
+            tokens.push(saved.token.unwrap());
+        }
+        let (alice, bob) = (tokens[0].as_str(), tokens[1].as_str());
+        let app = app(storage);
+        let uri = "/coupons/TEST123/redeem/challenge1";
+
+        let response = app.clone().oneshot(redeem(uri, None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        // Another player's profile id is not accepted with your own token
+        let response = app
+            .clone()
+            .oneshot(redeem(uri, Some(("bob", alice))))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .clone()
+            .oneshot(redeem(uri, Some(("alice", alice))))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app
+            .clone()
+            .oneshot(redeem(uri, Some(("alice", alice))))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let response = app
+            .clone()
+            .oneshot(redeem(uri, Some(("bob", bob))))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(admin_get("/coupons/TEST123/redemptions"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let redemptions: coupon::CouponRedemptionsResponse = serde_json::from_slice(&body).unwrap();
+        let redeemed_by: Vec<&str> = redemptions
+            .redemptions
+            .iter()
+            .map(|redemption| redemption.redeemed_by.as_str())
+            .collect();
+        assert_eq!(redeemed_by, vec!["alice", "bob"]);
+
+        let response = app
+            .oneshot(admin_get("/coupons/MISSING/redemptions"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_admin_routes_require_admin_key() {
+        let app = app(Arc::new(MemoryRepository::new()));
+
+        for uri in [
+            "/profiles",
+            "/reviews",
+            "/coupons",
+            "/coupons/TEST123/redemptions",
+            "/coupon-campaigns/class-5a",
+        ] {
+            let response = app.clone().oneshot(get(uri)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", uri);
+            assert!(response.headers().contains_key("WWW-Authenticate"));
+
+            let request = Request::builder()
+                .uri(uri)
+                .header("Authorization", "Bearer wrong-key")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", uri);
+        }
+
+        let response = app.clone().oneshot(admin_get("/coupons")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let request = Request::builder()
+            .uri("/reviews")
+            .header("X-API-Key", ADMIN_KEY)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        // A profile token sent along does not hide the admin key
+        let request = Request::builder()
+            .uri("/reviews")
+            .header("Authorization", "Bearer profile-token")
+            .header("X-API-Key", ADMIN_KEY)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Public routes on the same paths are not affected
+        let response = app.clone().oneshot(get("/leaderboard")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let request = Request::builder()
+            .method("PATCH")
+            .uri("/coupons/TEST123")
+            .header("Content-Type", "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.oneshot(get("/coupons/TEST123")).await.unwrap();
+        assert_ne!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_coupon_codes_do_not_clash_with_campaigns() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let coupon = Coupon::new(
+            "campaigns".to_string(),
+            vec!["challenge1".to_string()],
+            1,
+            chrono::Utc::now() + chrono::Duration::days(7),
+        );
+        CouponRepository::save(&*storage, coupon.clone())
+            .await
+            .unwrap();
+        let app = app(storage);
+
+        let response = app
+            .clone()
+            .oneshot(get("/coupons/campaigns"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let fetched: coupon::CouponResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(fetched.coupon, Some(coupon));
+
+        let request = Request::builder()
+            .method("DELETE")
+            .uri("/coupons/campaigns")
+            .header("Authorization", format!("Bearer {}", ADMIN_KEY))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert!(response.status().is_success());
+        let response = app.oneshot(get("/coupons/campaigns")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    fn post_profile(profile: &PlayerProfile, token: Option<&str>) -> Request<Body> {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/profiles")
+            .header("Content-Type", "application/json");
+        let request = match token {
+            Some(token) => request.header("Authorization", format!("Bearer {}", token)),
+            None => request,
+        };
+        request
+            .body(Body::from(serde_json::to_vec(profile).unwrap()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_profiles_can_only_be_changed_by_their_owner() {
+        let app = app(Arc::new(MemoryRepository::new()));
+        let mut profile = PlayerProfile::new("player1".to_string());
+
+        let response = app
+            .clone()
+            .oneshot(post_profile(&profile, None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let token = response.headers()[profile::PROFILE_TOKEN_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        profile.name = "hijacked".to_string();
+        let response = app
+            .clone()
+            .oneshot(post_profile(&profile, None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app
+            .clone()
+            .oneshot(post_profile(&profile, Some("wrong")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        profile.name = "renamed".to_string();
+        let response = app
+            .oneshot(post_profile(&profile, Some(&token)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!response
+            .headers()
+            .contains_key(profile::PROFILE_TOKEN_HEADER));
+    }
+
+    #[tokio::test]
+    async fn test_profiles_stored_without_owner_cannot_be_overwritten() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let mut profile = PlayerProfile::new("legacy".to_string());
+        ProfileRepository::save(&*storage, profile.clone())
+            .await
+            .unwrap();
+        let app = app(storage);
+
+        profile.name = "hijacked".to_string();
+        let response = app
+            .clone()
+            .oneshot(post_profile(&profile, None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert!(!response
+            .headers()
+            .contains_key(profile::PROFILE_TOKEN_HEADER));
+
+        let issue = |key: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/profiles/legacy/token")
+                .header("Authorization", format!("Bearer {}", key))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = app.clone().oneshot(issue("wrong")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.clone().oneshot(issue(ADMIN_KEY)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let issued: profile::ProfileTokenV1Response = serde_json::from_slice(&body).unwrap();
+        let response = app.clone().oneshot(issue(ADMIN_KEY)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        profile.name = "renamed".to_string();
+        let response = app
+            .oneshot(post_profile(&profile, Some(&issued.token)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_sign_in_issues_profile_session() {
+        use crate::services::v1::ton_proof::tests::{signed_proof, ADDRESS, PUBLIC_KEY};
+
+        let app = app(Arc::new(MemoryRepository::new()));
+        let profile = PlayerProfile::new("player1".to_string());
+        let response = app
+            .clone()
+            .oneshot(post_profile(&profile, None))
+            .await
+            .unwrap();
+        let owner_token = response.headers()[profile::PROFILE_TOKEN_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/wallet/challenge")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let challenge: wallet::WalletChallengeResponse = serde_json::from_slice(&body).unwrap();
+
+        let sign_in = wallet::WalletSignInRequest {
+            address: ADDRESS.to_string(),
+            public_key: PUBLIC_KEY.to_string(),
+            proof: signed_proof(&challenge.payload, chrono::Utc::now()),
+            profile_id: Some("player1".to_string()),
+        };
+        let request = |token: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/wallet/sign-in")
+                .header("Content-Type", "application/json")
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::from(serde_json::to_vec(&sign_in).unwrap()))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(request("wrong")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app.clone().oneshot(request(&owner_token)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let session: wallet::WalletSessionResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(session.profile_id, "player1");
+        assert_eq!(session.address, ADDRESS);
+
+        // The proof signed in once and cannot be replayed
+        let response = app.clone().oneshot(request(&owner_token)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app
+            .oneshot(post_profile(&profile, Some(&session.token)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_faucet_routes_need_a_monitor() {
+        use crate::issuer::MemoryTokenIssuer;
+        use crate::services::faucet::{FaucetConfig, FaucetInfo};
+
+        let storage: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let response = app(storage.clone()).oneshot(get("/faucet")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let issuer = Arc::new(MemoryTokenIssuer::new());
+        issuer.set_balance(1000);
+        let monitor = Arc::new(FaucetMonitor::new(issuer, FaucetConfig::default()));
+        let app = create_router(
+            AdminAuth::new([ADMIN_KEY]),
+            TonProofConfig::new("secret", ["konnektoren.help"]),
+            policy(),
+            LeaderboardConfig::default(),
+            None,
+            Some(monitor),
+            None,
+        )
+        .with_state(storage);
+
+        let response = app.clone().oneshot(get("/faucet")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let info: FaucetInfo = serde_json::from_slice(&body).unwrap();
+        assert_eq!(info.jetton_balance, 1000);
+
+        let response = app.clone().oneshot(get("/jetton")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.oneshot(get("/jetton/balance/%20")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_certificate_metadata_is_served_at_its_url() {
+        use crate::issuer::MemoryTokenIssuer;
+        use crate::services::certificate::{CertificateConfig, CertificateMetadata};
+        use crate::services::claim_queue::ClaimQueueConfig;
+
+        let storage: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        eligible_player(&storage, "alice", 2).await;
+        let config = CertificateConfig::from_vars(|key| {
+            (key == "CERTIFICATE_BASE_URL").then(|| "https://api.example.com".to_string())
+        })
+        .unwrap();
+        let queue = ClaimQueue::new(
+            Arc::new(MemoryTokenIssuer::new()),
+            ClaimQueueConfig::default(),
+        );
+        let minter = CertificateMinter::new(queue, config);
+        let app = create_router(
+            AdminAuth::new([ADMIN_KEY]),
+            TonProofConfig::new("secret", ["konnektoren.help"]),
+            policy(),
+            LeaderboardConfig::default(),
+            None,
+            None,
+            minter,
+        )
+        .with_state(storage);
+
+        let request = |key: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/certificates")
+                .header("Content-Type", "application/json")
+                .header("Authorization", format!("Bearer {}", key))
+                .body(Body::from(
+                    r#"{"profileId":"alice","gamePathId":"game_path","address":"alice-wallet"}"#,
+                ))
+                .unwrap()
+        };
+        let response = app.clone().oneshot(request("wrong")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app.clone().oneshot(request(ADMIN_KEY)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let certificate: CertificateRecord = serde_json::from_slice(&body).unwrap();
+        let path = certificate
+            .metadata_url
+            .strip_prefix("https://api.example.com/api/v1")
+            .unwrap();
+
+        let response = app.clone().oneshot(get(path)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let metadata: CertificateMetadata = serde_json::from_slice(&body).unwrap();
+        assert!(metadata.description.contains("game_path"));
+
+        let response = app.oneshot(get("/certificates/missing")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_leaderboard_pages_carry_ranks() {
+        use super::leaderboard::LeaderboardV1Response;
+
+        let storage: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let app = app(storage);
+        for percentage in [40, 90, 60] {
+            let record = PerformanceRecord {
+                profile_name: format!("player{}", percentage),
+                performance_percentage: percentage,
+                ..Default::default()
+            };
+            let request = Request::builder()
+                .method("POST")
+                .uri("/performance-record/challenge")
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_vec(&record).unwrap()))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = app
+            .clone()
+            .oneshot(get("/leaderboard/challenge?offset=1&limit=1"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let leaderboard: LeaderboardV1Response = serde_json::from_slice(&body).unwrap();
+        assert_eq!(leaderboard.total, 3);
+        assert_eq!(leaderboard.performance_records.len(), 1);
+        let entry = &leaderboard.performance_records[0];
+        assert_eq!(entry.rank, 2);
+        assert_eq!(entry.record.profile_name, "player60");
+
+        // Period boards are read with `period`, unknown periods are rejected
+        let response = app
+            .clone()
+            .oneshot(get("/leaderboard/challenge?period=monthly"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app
+            .oneshot(get("/leaderboard/challenge?period=yearly"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_challenge_ids_cannot_reach_period_boards() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let app = app(storage);
+        let request = Request::builder()
+            .method("POST")
+            .uri("/performance-record/x:daily:2024-04-01")
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                serde_json::to_vec(&PerformanceRecord::default()).unwrap(),
+            ))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        for uri in [
+            "/leaderboard/x:daily:2024-04-01",
+            "/leaderboard/x:daily:2024-04-01/rank?profile_name=alice",
+        ] {
+            let response = app.clone().oneshot(get(uri)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_records_keyed_by_profile_id_need_the_profile() {
+        use crate::services::v1::leaderboard::PlayerKey;
+
+        let storage: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let app = leaderboard_app(
+            storage,
+            LeaderboardConfig {
+                player_key: PlayerKey::ProfileId,
+                ..Default::default()
+            },
+        );
+        let post = |profile_id: Option<&str>| {
+            let mut request = Request::builder()
+                .method("POST")
+                .uri("/performance-record")
+                .header("Content-Type", "application/json");
+            if let Some(profile_id) = profile_id {
+                request = request.header("X-Profile-ID", profile_id);
+            }
+            request
+                .body(Body::from(
+                    serde_json::to_vec(&PerformanceRecord::default()).unwrap(),
+                ))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(post(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.oneshot(post(Some("unknown"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
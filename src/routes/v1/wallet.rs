@@ -0,0 +1,134 @@
+use crate::middleware::auth::bearer_token;
+use crate::services::v1::profile::ProfileError;
+use crate::services::v1::ton_proof::{TonProof, TonProofConfig, TonProofError};
+use crate::services::v1::wallet::{self, WalletError, WalletSignIn};
+use crate::storage::{RepositoryError, Storage};
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::{Extension, Json};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct WalletChallengeResponse {
+    /// Payload to request a `ton_proof` for
+    pub payload: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct WalletSignInRequest {
+    /// Raw wallet address, `workchain:hash`
+    #[schema(example = "0:e8956476d5fd9a57568164609c821ffbe090d73348dde70c0bfa0912afe426e9")]
+    pub address: String,
+    /// Hex ed25519 public key of the wallet
+    pub public_key: String,
+    pub proof: TonProof,
+    /// Profile to link, only needed on the first sign-in with a wallet
+    pub profile_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct WalletSessionResponse {
+    pub profile_id: String,
+    pub address: String,
+    /// Session token, send as `Authorization: Bearer <token>` to update the profile
+    pub token: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub expires_at: DateTime<Utc>,
+}
+
+#[utoipa::path(
+    post,
+    operation_id = "wallet_challenge_v1",
+    tag = "wallet_v1",
+    path = "/wallet/challenge",
+    context_path = "/api/v1",
+    responses(
+        (status = 200, description = "Payload for a TON Connect ton_proof request", body = WalletChallengeResponse),
+    )
+)]
+pub async fn challenge_handler(
+    Extension(config): Extension<TonProofConfig>,
+) -> Json<WalletChallengeResponse> {
+    let (payload, expires_at) = config.create_payload(Utc::now());
+    Json(WalletChallengeResponse {
+        payload,
+        expires_at,
+    })
+}
+
+#[utoipa::path(
+    post,
+    operation_id = "wallet_sign_in_v1",
+    tag = "wallet_v1",
+    path = "/wallet/sign-in",
+    context_path = "/api/v1",
+    request_body = WalletSignInRequest,
+    params(
+        ("Authorization" = Option<String>, Header, description = "`Bearer <token>` with the owner token of `profile_id`, required to link a new wallet"),
+    ),
+    responses(
+        (status = 200, description = "Wallet verified, session issued", body = WalletSessionResponse),
+        (status = 400, description = "Malformed proof or missing profile id"),
+        (status = 401, description = "Invalid proof or missing owner token"),
+        (status = 403, description = "The token does not own the profile"),
+        (status = 404, description = "Profile not registered"),
+        (status = 409, description = "Wallet is linked to another profile"),
+    )
+)]
+pub async fn sign_in_handler(
+    State(repository): State<Arc<dyn Storage>>,
+    Extension(config): Extension<TonProofConfig>,
+    headers: HeaderMap,
+    Json(request): Json<WalletSignInRequest>,
+) -> Result<Json<WalletSessionResponse>, (StatusCode, String)> {
+    let request = WalletSignIn {
+        address: request.address,
+        public_key: request.public_key,
+        proof: request.proof,
+        profile_id: request.profile_id,
+    };
+    let session = wallet::sign_in(
+        &config,
+        request,
+        bearer_token(&headers),
+        repository,
+        Utc::now(),
+    )
+    .await
+    .map_err(wallet_error_response)?;
+
+    Ok(Json(WalletSessionResponse {
+        profile_id: session.profile_id,
+        address: session.address,
+        token: session.token,
+        expires_at: session.expires_at,
+    }))
+}
+
+fn wallet_error_response(err: WalletError) -> (StatusCode, String) {
+    let status = match &err {
+        WalletError::Proof(TonProofError::Malformed(_)) | WalletError::ProfileRequired(_) => {
+            StatusCode::BAD_REQUEST
+        }
+        WalletError::Proof(_) | WalletError::Profile(ProfileError::MissingToken(_)) => {
+            StatusCode::UNAUTHORIZED
+        }
+        WalletError::Profile(ProfileError::InvalidToken(_)) => StatusCode::FORBIDDEN,
+        WalletError::Profile(ProfileError::Repository(RepositoryError::NotFound(_))) => {
+            StatusCode::NOT_FOUND
+        }
+        WalletError::LinkedToOtherProfile(_) | WalletError::Profile(ProfileError::Unowned(_)) => {
+            StatusCode::CONFLICT
+        }
+        WalletError::Profile(ProfileError::Repository(_)) | WalletError::Repository(_) => {
+            log::error!("Wallet sign-in failed: {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, err.to_string())
+}
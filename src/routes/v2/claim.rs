@@ -1,15 +1,21 @@
-use axum::extract::Json;
+use crate::services::claim_eligibility::ClaimPolicy;
+use crate::services::claim_error::claim_error_response;
+use crate::services::claim_queue::ClaimQueue;
+use crate::services::v2::claim::{claim_tokens_service, get_claim_service};
+use crate::storage::{ClaimRecord, Storage};
+use axum::extract::{Json, Path, State};
+use axum::Extension;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use utoipa::ToSchema;
 
-#[cfg(feature = "ton")]
-use crate::services::v2::claim::claim_tokens_service;
-
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ClaimV2Request {
+    /// Id of the claim request, must be positive
     #[schema(example = 123456)]
     pub id: i64,
+    /// Profile id of the player, the claimable amount is computed from their progress
     #[schema(example = "example_user")]
     pub user: String,
     #[serde(rename = "type")]
@@ -31,7 +37,6 @@ pub struct ClaimV2Response {
     pub destination: String,
 }
 
-#[cfg(feature = "ton")]
 #[utoipa::path(
     post,
     operation_id = "claim_v2",
@@ -39,13 +44,50 @@ pub struct ClaimV2Response {
     path = "/claim",
     context_path = "/api/v2",
     request_body = ClaimV2Request,
+    security(("admin_token" = [])),
     responses(
-        (status = 200, description = "Token claimed successfully", body = ClaimV2Response),
-        (status = 400, description = "Invalid request data"),
+        (status = 200, description = "Token claimed successfully, repeated claim ids get the recorded message until it expires", body = ClaimV2Response),
+        (status = 400, description = "Invalid request data or destination address"),
+        (status = 401, description = "Missing or invalid admin credentials"),
+        (status = 403, description = "The amount exceeds the tokens the player earned"),
+        (status = 402, description = "The faucet does not hold enough tokens"),
+        (status = 404, description = "The player is not registered"),
+        (status = 409, description = "The claim id is pending or was used for another claim"),
+        (status = 429, description = "Daily cap reached or claimed too recently"),
+        (status = 500, description = "The claim failed"),
+        (status = 503, description = "The TON network is unreachable, or the faucet wallet has an unconfirmed message"),
     )
 )]
 pub async fn claim_tokens(
+    State(repository): State<Arc<dyn Storage>>,
+    Extension(policy): Extension<ClaimPolicy>,
+    Extension(queue): Extension<ClaimQueue>,
     Json(payload): Json<ClaimV2Request>,
 ) -> Result<Json<ClaimV2Response>, (axum::http::StatusCode, String)> {
-    claim_tokens_service(payload).await
+    claim_tokens_service(payload, &policy, &queue, repository)
+        .await
+        .map_err(claim_error_response)
+}
+
+#[utoipa::path(
+    get,
+    operation_id = "get_claim_v2",
+    tag = "claim_v2",
+    path = "/claim/{id}",
+    context_path = "/api/v2",
+    params(
+        ("id" = i64, Path, description = "Id of the claim request"),
+    ),
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Recorded claim", body = ClaimRecord),
+        (status = 401, description = "Missing or invalid admin credentials"),
+        (status = 404, description = "No claim with this id"),
+    )
+)]
+pub async fn get_claim(
+    State(repository): State<Arc<dyn Storage>>,
+    Path(id): Path<i64>,
+) -> Result<Json<ClaimRecord>, (axum::http::StatusCode, String)> {
+    get_claim_service(id, repository).await
 }
@@ -1,15 +1,91 @@
+use crate::middleware::auth::{require_admin, AdminAuth};
+use crate::services::claim_eligibility::ClaimPolicy;
+use crate::services::claim_queue::ClaimQueue;
 use crate::storage::{ProfileRepository, Storage};
-use axum::{routing::post, Router};
+use axum::routing::{get, post};
+use axum::{Extension, Router};
 use std::sync::Arc;
-use tokio::sync::Mutex;
 
 pub mod claim;
 
-pub fn create_router() -> Router<Arc<Mutex<dyn Storage>>> {
+/// Creates the v2 routes, token claims are checked against `claim_policy`.
+/// Claims are only routed with a `claim_queue` to sign the transfers with
+/// the seqno of the faucet wallet.
+pub fn create_router(
+    admin_auth: AdminAuth,
+    claim_policy: ClaimPolicy,
+    claim_queue: Option<ClaimQueue>,
+) -> Router<Arc<dyn Storage>> {
+    let admin = axum::middleware::from_fn_with_state(admin_auth, require_admin);
     let router = Router::new();
 
-    #[cfg(feature = "ton")]
-    let router = router.route("/claim", post(claim::claim_tokens));
+    let router = match claim_queue {
+        Some(claim_queue) => router.route(
+            "/claim",
+            post(claim::claim_tokens)
+                .layer(Extension(claim_policy))
+                .layer(Extension(claim_queue))
+                .route_layer(admin.clone()),
+        ),
+        None => router,
+    };
+    let router = router.route("/claim/:id", get(claim::get_claim).route_layer(admin));
 
     router
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{ClaimKind, ClaimRecord, ClaimRepository, MemoryRepository};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    const ADMIN_KEY: &str = "admin-key";
+
+    fn claim_request(id: i64, key: Option<&str>) -> Request<Body> {
+        let request = Request::builder().uri(format!("/claim/{}", id));
+        let request = match key {
+            Some(key) => request.header("Authorization", format!("Bearer {}", key)),
+            None => request,
+        };
+        request.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_claim_status() {
+        let storage = Arc::new(MemoryRepository::new());
+        let claim = ClaimRecord::new(
+            42,
+            ClaimKind::Transfer,
+            "alice".to_string(),
+            "address".to_string(),
+            5,
+        );
+        storage.create_claim(claim.clone()).await.unwrap();
+        let app = create_router(AdminAuth::new([ADMIN_KEY]), ClaimPolicy::default(), None)
+            .with_state(storage as Arc<dyn Storage>);
+
+        let response = app.clone().oneshot(claim_request(42, None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app
+            .clone()
+            .oneshot(claim_request(43, Some(ADMIN_KEY)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app
+            .oneshot(claim_request(42, Some(ADMIN_KEY)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let recorded: ClaimRecord = serde_json::from_slice(&body).unwrap();
+        assert_eq!(recorded, claim);
+    }
+}
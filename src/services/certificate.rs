@@ -0,0 +1,572 @@
+use crate::issuer::TokenIssuerError;
+use crate::routes::v1::certificate::CertificateRequest;
+use crate::services::claim_queue::ClaimQueue;
+use crate::storage::{
+    CertificateRecord, CertificateRepository, CertificateStatus, ClaimKind, ClaimRecord,
+    ClaimRepository, ClaimStatus, LeaderboardRepository, ProfileRepository, RepositoryError,
+    Storage,
+};
+use axum::http::StatusCode;
+use chrono::Utc;
+use konnektoren_core::challenges::PerformanceRecord;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+pub const BASE_URL_VAR: &str = "CERTIFICATE_BASE_URL";
+pub const IMAGE_URL_VAR: &str = "CERTIFICATE_IMAGE_URL";
+
+#[derive(Debug, Error)]
+pub enum CertificateError {
+    #[error("Repository error: {0}")]
+    Repository(#[from] RepositoryError),
+    #[error("{0}")]
+    Issuer(#[from] TokenIssuerError),
+    #[error("Player {0} is not registered")]
+    UnknownPlayer(String),
+    #[error("Player {profile_id} has not completed game path {game_path_id}")]
+    NotCompleted {
+        profile_id: String,
+        game_path_id: String,
+    },
+    #[error("Certificate {0} was already requested for another address")]
+    Conflict(String),
+    #[error("Certificate {0} not found")]
+    NotFound(String),
+    #[error("The mint of certificate {0} failed")]
+    Failed(String),
+}
+
+/// Where the metadata of certificates is served.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CertificateConfig {
+    /// Public URL of the API, without a trailing slash.
+    pub base_url: String,
+    pub image_url: Option<String>,
+}
+
+impl CertificateConfig {
+    /// The config from the environment, `None` if `CERTIFICATE_BASE_URL` is not set.
+    pub fn from_env() -> Option<Self> {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    pub fn from_vars<F>(var: F) -> Option<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let value = |key| {
+            var(key)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Some(Self {
+            base_url: value(BASE_URL_VAR)?.trim_end_matches('/').to_string(),
+            image_url: value(IMAGE_URL_VAR),
+        })
+    }
+
+    /// The URL the metadata of certificate `id` is served at, it never changes.
+    pub fn metadata_url(&self, id: &str) -> String {
+        format!("{}/api/v1/certificates/{}", self.base_url, id)
+    }
+}
+
+/// Mints certificates in the NFT collection of the issuer of a claim queue.
+#[derive(Clone)]
+pub struct CertificateMinter {
+    queue: ClaimQueue,
+    collection: String,
+    config: CertificateConfig,
+}
+
+impl CertificateMinter {
+    /// A minter queueing mints in `queue`, `None` if its issuer has no NFT collection.
+    pub fn new(queue: ClaimQueue, config: CertificateConfig) -> Option<Self> {
+        let collection = queue.issuer().nft_collection()?;
+        Some(Self {
+            queue,
+            collection,
+            config,
+        })
+    }
+}
+
+/// A property of a certificate NFT.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CertificateAttribute {
+    #[schema(example = "Game path")]
+    pub trait_type: String,
+    #[schema(example = "konnektoren")]
+    pub value: String,
+}
+
+/// Metadata of a certificate NFT in the format of TEP-64, read by wallets and explorers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CertificateMetadata {
+    #[schema(example = "Konnektoren certificate: konnektoren")]
+    pub name: String,
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    pub attributes: Vec<CertificateAttribute>,
+}
+
+impl From<&CertificateRecord> for CertificateMetadata {
+    fn from(certificate: &CertificateRecord) -> Self {
+        let attribute = |trait_type: &str, value: String| CertificateAttribute {
+            trait_type: trait_type.to_string(),
+            value,
+        };
+        Self {
+            name: format!("Konnektoren certificate: {}", certificate.game_path_id),
+            description: format!(
+                "{} completed the game path {} with {}% on {}.",
+                certificate.profile_name,
+                certificate.game_path_id,
+                certificate.performance_percentage,
+                certificate.completed_at.format("%Y-%m-%d")
+            ),
+            image: certificate.image.clone(),
+            attributes: vec![
+                attribute("Player", certificate.profile_name.clone()),
+                attribute("Game path", certificate.game_path_id.clone()),
+                attribute(
+                    "Performance",
+                    format!("{}%", certificate.performance_percentage),
+                ),
+                attribute("Challenges", certificate.total_challenges.to_string()),
+                attribute("Completed", certificate.completed_at.to_rfc3339()),
+            ],
+        }
+    }
+}
+
+/// Id of the certificate of a player for a game path, a player gets one per game path.
+pub fn certificate_id(profile_id: &str, game_path_id: &str) -> String {
+    let digest = Sha256::digest(format!("{}\n{}", profile_id, game_path_id));
+    hex::encode(&digest[..16])
+}
+
+/// Id of the claim minting certificate `id`, claims of clients have positive ids.
+pub fn mint_claim_id(id: &str) -> i64 {
+    let digest = Sha256::digest(id.as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    -((u64::from_be_bytes(bytes) >> 1) as i64) - 1
+}
+
+/// Whether `record` covers every challenge of its game path.
+fn is_completed(record: &PerformanceRecord) -> bool {
+    record.total_challenges > 0 && record.challenges_performance.len() >= record.total_challenges
+}
+
+/// The best completed record of the game path submitted for the profile `profile_id`.
+async fn completed_record(
+    profile_id: &str,
+    game_path_id: &str,
+    repository: &Arc<dyn Storage>,
+) -> Result<Option<PerformanceRecord>, RepositoryError> {
+    Ok(repository
+        .fetch_profile_submissions(profile_id)
+        .await?
+        .into_iter()
+        .filter(|record| record.game_path_id == game_path_id && is_completed(record))
+        .max_by_key(|record| record.performance_percentage))
+}
+
+/// Queues the mint of the certificate NFT of a completed game path in the
+/// claim queue, once per player and game path.
+///
+/// Repeated requests get the recorded certificate, its status tells whether
+/// the mint is still queued.
+pub async fn queue_certificate_service(
+    payload: CertificateRequest,
+    minter: &CertificateMinter,
+    repository: Arc<dyn Storage>,
+) -> Result<CertificateRecord, CertificateError> {
+    minter.queue.issuer().validate_address(&payload.address)?;
+
+    let id = certificate_id(&payload.profile_id, &payload.game_path_id);
+    let certificate = match repository.fetch_certificate(&id).await? {
+        Some(certificate) => certificate,
+        None => {
+            let certificate = new_certificate(&payload, id.clone(), minter, &repository).await?;
+            match repository.create_certificate(certificate).await {
+                Ok(certificate) => certificate,
+                // Requested concurrently, the recorded certificate is queued
+                Err(RepositoryError::AlreadyExists(_)) => repository
+                    .fetch_certificate(&id)
+                    .await?
+                    .ok_or_else(|| CertificateError::NotFound(id.clone()))?,
+                Err(err) => return Err(err.into()),
+            }
+        }
+    };
+    if certificate.owner != payload.address {
+        return Err(CertificateError::Conflict(id));
+    }
+
+    match certificate.status {
+        CertificateStatus::Sent => Ok(certificate),
+        CertificateStatus::Failed => Err(CertificateError::Failed(id)),
+        CertificateStatus::Queued | CertificateStatus::Prepared => {
+            queue_mint(certificate, minter, &repository).await
+        }
+    }
+}
+
+/// Makes sure the mint of `certificate` waits in the claim queue, the claim
+/// id is derived from the certificate so it is only queued once.
+async fn queue_mint(
+    certificate: CertificateRecord,
+    minter: &CertificateMinter,
+    repository: &Arc<dyn Storage>,
+) -> Result<CertificateRecord, CertificateError> {
+    let claim_id = mint_claim_id(&certificate.id);
+    if repository.fetch_claim(claim_id).await?.is_none() {
+        let claim = ClaimRecord {
+            status: ClaimStatus::Queued,
+            certificate_id: Some(certificate.id.clone()),
+            ..ClaimRecord::new(
+                claim_id,
+                ClaimKind::CertificateMint,
+                certificate.profile_id.clone(),
+                certificate.owner.clone(),
+                0,
+            )
+        };
+        match repository.create_claim(claim).await {
+            Ok(_) | Err(RepositoryError::AlreadyExists(_)) => {}
+            Err(err) => return Err(err.into()),
+        }
+        log::info!(
+            "Queued certificate {} as claim {}",
+            certificate.id,
+            claim_id
+        );
+        minter.queue.notify();
+    }
+
+    if certificate.status == CertificateStatus::Queued {
+        return Ok(certificate);
+    }
+    // The message signed by earlier versions expired, the worker signs a new
+    // one with a reserved item index
+    Ok(repository
+        .update_certificate(CertificateRecord {
+            status: CertificateStatus::Queued,
+            item_index: None,
+            claim_id: Some(claim_id),
+            raw_transaction: None,
+            updated_at: Utc::now(),
+            ..certificate
+        })
+        .await?)
+}
+
+async fn new_certificate(
+    payload: &CertificateRequest,
+    id: String,
+    minter: &CertificateMinter,
+    repository: &Arc<dyn Storage>,
+) -> Result<CertificateRecord, CertificateError> {
+    let profile = match ProfileRepository::fetch(&**repository, payload.profile_id.clone()).await {
+        Ok(profile) => profile,
+        Err(RepositoryError::NotFound(_)) => {
+            return Err(CertificateError::UnknownPlayer(payload.profile_id.clone()))
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let record = completed_record(&payload.profile_id, &payload.game_path_id, repository)
+        .await?
+        .ok_or_else(|| CertificateError::NotCompleted {
+            profile_id: payload.profile_id.clone(),
+            game_path_id: payload.game_path_id.clone(),
+        })?;
+
+    let now = Utc::now();
+    Ok(CertificateRecord {
+        metadata_url: minter.config.metadata_url(&id),
+        claim_id: Some(mint_claim_id(&id)),
+        id,
+        profile_id: payload.profile_id.clone(),
+        profile_name: profile.name,
+        game_path_id: payload.game_path_id.clone(),
+        owner: payload.address.clone(),
+        performance_percentage: record.performance_percentage,
+        total_challenges: record.total_challenges,
+        completed_at: record.date,
+        collection: minter.collection.clone(),
+        item_index: None,
+        image: minter.config.image_url.clone(),
+        status: CertificateStatus::Queued,
+        raw_transaction: None,
+        tx_hash: None,
+        created_at: now,
+        updated_at: now,
+    })
+}
+
+pub async fn get_certificate_metadata_service(
+    id: &str,
+    repository: Arc<dyn Storage>,
+) -> Result<CertificateMetadata, CertificateError> {
+    match repository.fetch_certificate(id).await? {
+        Some(certificate) => Ok(CertificateMetadata::from(&certificate)),
+        None => Err(CertificateError::NotFound(id.to_string())),
+    }
+}
+
+pub(crate) fn certificate_error_response(err: CertificateError) -> (StatusCode, String) {
+    let status = match &err {
+        CertificateError::Issuer(TokenIssuerError::InvalidAddress(_)) => StatusCode::BAD_REQUEST,
+        CertificateError::UnknownPlayer(_)
+        | CertificateError::NotCompleted { .. }
+        | CertificateError::NotFound(_) => StatusCode::NOT_FOUND,
+        CertificateError::Conflict(_) => StatusCode::CONFLICT,
+        CertificateError::Issuer(TokenIssuerError::InsufficientFunds { .. }) => {
+            StatusCode::PAYMENT_REQUIRED
+        }
+        CertificateError::Issuer(TokenIssuerError::Network(_)) => {
+            log::error!("Certificate failed: {}", err);
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        CertificateError::Issuer(TokenIssuerError::Signing(_))
+        | CertificateError::Failed(_)
+        | CertificateError::Repository(_) => {
+            log::error!("Certificate failed: {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::issuer::MemoryTokenIssuer;
+    use crate::services::claim_queue::{ClaimQueueConfig, ClaimWorker};
+    use crate::services::v1::leaderboard::{DEFAULT_CAPACITY, LEADERBOARD_NAMESPACE};
+    use crate::storage::MemoryRepository;
+    use konnektoren_core::prelude::PlayerProfile;
+
+    fn record(profile_name: &str, completed: usize) -> PerformanceRecord {
+        PerformanceRecord {
+            game_path_id: "konnektoren".to_string(),
+            profile_name: profile_name.to_string(),
+            challenges_performance: (0..completed)
+                .map(|i| (format!("challenge-{}", i), 90, 1000))
+                .collect(),
+            total_challenges: 3,
+            performance_percentage: 90,
+            date: Utc::now(),
+        }
+    }
+
+    fn request(profile_id: &str) -> CertificateRequest {
+        CertificateRequest {
+            profile_id: profile_id.to_string(),
+            game_path_id: "konnektoren".to_string(),
+            address: format!("{}-wallet", profile_id),
+        }
+    }
+
+    async fn register(repository: &Arc<dyn Storage>, profile_id: &str, completed: usize) {
+        let mut profile = PlayerProfile::new(profile_id.to_string());
+        profile.name = profile_id.to_uppercase();
+        ProfileRepository::save(&**repository, profile)
+            .await
+            .unwrap();
+        repository
+            .record_submission(
+                LEADERBOARD_NAMESPACE,
+                &profile_id.to_uppercase(),
+                Some(profile_id),
+                record(&profile_id.to_uppercase(), completed),
+            )
+            .await
+            .unwrap();
+    }
+
+    async fn setup(
+        completed: usize,
+    ) -> (
+        Arc<MemoryTokenIssuer>,
+        CertificateMinter,
+        ClaimWorker,
+        Arc<dyn Storage>,
+    ) {
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        register(&repository, "alice", completed).await;
+
+        let issuer = Arc::new(MemoryTokenIssuer::new());
+        let config = CertificateConfig::from_vars(|key| match key {
+            BASE_URL_VAR => Some("https://api.example.com/".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        let queue = ClaimQueue::new(issuer.clone(), ClaimQueueConfig::default());
+        let worker = queue.worker(repository.clone());
+        let minter = CertificateMinter::new(queue, config).unwrap();
+        (issuer, minter, worker, repository)
+    }
+
+    async fn fetch(repository: &Arc<dyn Storage>, id: &str) -> CertificateRecord {
+        repository.fetch_certificate(id).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_certificate_is_minted_once_by_the_claim_queue() {
+        let (issuer, minter, worker, repository) = setup(3).await;
+
+        let queued = queue_certificate_service(request("alice"), &minter, repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(queued.status, CertificateStatus::Queued);
+        assert_eq!(queued.item_index, None);
+        assert_eq!(
+            queued.metadata_url,
+            format!("https://api.example.com/api/v1/certificates/{}", queued.id)
+        );
+        let claim_id = queued.claim_id.unwrap();
+        assert!(claim_id < 0);
+        let claim = repository.fetch_claim(claim_id).await.unwrap().unwrap();
+        assert_eq!(claim.kind, ClaimKind::CertificateMint);
+        assert_eq!(claim.status, ClaimStatus::Queued);
+        // Only the worker mints
+        assert!(issuer.minted_nfts().is_empty());
+
+        let repeated = queue_certificate_service(request("alice"), &minter, repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(repeated, queued);
+
+        let now = Utc::now();
+        worker.process(now).await.unwrap();
+        let minted = issuer.minted_nfts();
+        assert_eq!(minted.len(), 1);
+        assert_eq!(minted[0].owner, "alice-wallet");
+        assert_eq!(minted[0].item_index, 0);
+        assert_eq!(minted[0].content_url, queued.metadata_url);
+        assert_eq!(fetch(&repository, &queued.id).await.item_index, Some(0));
+
+        worker.process(now).await.unwrap();
+        let sent = fetch(&repository, &queued.id).await;
+        assert_eq!(sent.status, CertificateStatus::Sent);
+        assert!(sent.tx_hash.is_some());
+
+        // Sent certificates are not minted again
+        let repeated = queue_certificate_service(request("alice"), &minter, repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(repeated, sent);
+        worker.process(now).await.unwrap();
+        assert_eq!(issuer.minted_nfts().len(), 1);
+
+        let metadata = get_certificate_metadata_service(&sent.id, repository)
+            .await
+            .unwrap();
+        assert_eq!(metadata.name, "Konnektoren certificate: konnektoren");
+        assert!(metadata.description.starts_with("ALICE completed"));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_queue_one_mint() {
+        let (issuer, minter, worker, repository) = setup(3).await;
+        register(&repository, "bob", 3).await;
+
+        let (first, second, bob) = tokio::join!(
+            queue_certificate_service(request("alice"), &minter, repository.clone()),
+            queue_certificate_service(request("alice"), &minter, repository.clone()),
+            queue_certificate_service(request("bob"), &minter, repository.clone()),
+        );
+        assert_eq!(first.unwrap().id, second.unwrap().id);
+        let bob = bob.unwrap();
+        let mints = repository
+            .fetch_queued_claims()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|claim| claim.kind == ClaimKind::CertificateMint)
+            .count();
+        assert_eq!(mints, 2);
+
+        let now = Utc::now();
+        for _ in 0..4 {
+            worker.process(now).await.unwrap();
+        }
+        // Each certificate gets its own item index
+        let mut indexes: Vec<u64> = issuer
+            .minted_nfts()
+            .into_iter()
+            .map(|mint| mint.item_index)
+            .collect();
+        indexes.sort();
+        assert_eq!(indexes, vec![0, 1]);
+        assert_eq!(
+            fetch(&repository, &bob.id).await.status,
+            CertificateStatus::Sent
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_mints_fail_the_certificate() {
+        let (issuer, minter, worker, repository) = setup(3).await;
+        let queued = queue_certificate_service(request("alice"), &minter, repository.clone())
+            .await
+            .unwrap();
+
+        issuer.fail_with(TokenIssuerError::Signing("broken key".to_string()));
+        worker.process(Utc::now()).await.unwrap();
+        assert_eq!(
+            fetch(&repository, &queued.id).await.status,
+            CertificateStatus::Failed
+        );
+        let result = queue_certificate_service(request("alice"), &minter, repository).await;
+        assert!(matches!(result, Err(CertificateError::Failed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_certificates_need_a_completed_game_path() {
+        let (_, minter, _, repository) = setup(2).await;
+        let result = queue_certificate_service(request("alice"), &minter, repository.clone()).await;
+        assert!(matches!(result, Err(CertificateError::NotCompleted { .. })));
+
+        // A completed record under her name that was not submitted for her profile
+        repository
+            .add_performance_record(
+                LEADERBOARD_NAMESPACE,
+                "ALICE",
+                record("ALICE", 3),
+                DEFAULT_CAPACITY,
+            )
+            .await
+            .unwrap();
+        let result = queue_certificate_service(request("alice"), &minter, repository.clone()).await;
+        assert!(matches!(result, Err(CertificateError::NotCompleted { .. })));
+
+        let result = queue_certificate_service(request("bob"), &minter, repository.clone()).await;
+        assert!(matches!(result, Err(CertificateError::UnknownPlayer(_))));
+
+        let result = get_certificate_metadata_service("missing", repository).await;
+        assert!(matches!(result, Err(CertificateError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_certificate_belongs_to_the_first_address() {
+        let (_, minter, _, repository) = setup(3).await;
+        queue_certificate_service(request("alice"), &minter, repository.clone())
+            .await
+            .unwrap();
+
+        let other_address = CertificateRequest {
+            address: "bob-wallet".to_string(),
+            ..request("alice")
+        };
+        let result = queue_certificate_service(other_address, &minter, repository).await;
+        assert!(matches!(result, Err(CertificateError::Conflict(_))));
+    }
+}
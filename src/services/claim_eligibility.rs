@@ -0,0 +1,528 @@
+use crate::config::{setting, ConfigError};
+use crate::storage::{
+    ClaimKind, ClaimRecord, ClaimRepository, ClaimStatus, CouponRedemption, CouponRepository,
+    LeaderboardRepository, ProfileRepository, RepositoryError, Storage,
+};
+use chrono::{DateTime, Duration, Utc};
+use konnektoren_core::challenges::PerformanceRecord;
+use std::collections::HashSet;
+use std::sync::Arc;
+use thiserror::Error;
+
+pub const CHALLENGES_VAR: &str = "CLAIM_CHALLENGES";
+pub const TOKENS_PER_CHALLENGE_VAR: &str = "CLAIM_TOKENS_PER_CHALLENGE";
+pub const TOKENS_PER_COUPON_VAR: &str = "CLAIM_TOKENS_PER_COUPON";
+pub const PASS_PERCENTAGE_VAR: &str = "CLAIM_PASS_PERCENTAGE";
+pub const DAILY_CAP_VAR: &str = "CLAIM_DAILY_CAP";
+pub const COOLDOWN_SECONDS_VAR: &str = "CLAIM_COOLDOWN_SECONDS";
+
+#[derive(Debug, Error)]
+pub enum EligibilityError {
+    #[error("Repository error: {0}")]
+    Repository(#[from] RepositoryError),
+    #[error("Player {0} is not registered")]
+    UnknownPlayer(String),
+    #[error("Claim amount must be at least 1")]
+    InvalidAmount,
+    #[error("Requested {requested} tokens, but only {available} are claimable")]
+    ExceedsEntitlement { requested: u32, available: u32 },
+    #[error("Requested {requested} tokens, but only {remaining} more can be claimed today")]
+    DailyCapReached { requested: u32, remaining: u32 },
+    #[error("Claimed too recently, the next claim is possible at {0}")]
+    CoolingDown(DateTime<Utc>),
+}
+
+/// How many tokens players earn and how fast they may claim them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClaimPolicy {
+    /// Ids of the challenges that earn tokens. Records name their challenges
+    /// themselves, so only the ids of challenges that exist may count.
+    pub challenges: HashSet<String>,
+    /// Tokens for every challenge completed with at least `pass_percentage`.
+    pub tokens_per_challenge: u32,
+    pub tokens_per_coupon: u32,
+    pub pass_percentage: u8,
+    /// Most tokens a player can claim in 24 hours.
+    pub daily_cap: u32,
+    /// Minimum time between two claims of a player.
+    pub cooldown: Duration,
+}
+
+impl Default for ClaimPolicy {
+    fn default() -> Self {
+        Self {
+            challenges: HashSet::new(),
+            tokens_per_challenge: 1,
+            tokens_per_coupon: 1,
+            pass_percentage: 50,
+            daily_cap: 10,
+            cooldown: Duration::hours(1),
+        }
+    }
+}
+
+impl ClaimPolicy {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    /// Builds the policy from a variable lookup, unset variables keep their
+    /// default. Challenge ids are given separated by commas.
+    pub fn from_vars<F>(var: F) -> Result<Self, ConfigError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let defaults = Self::default();
+        let cooldown_seconds =
+            setting(&var, COOLDOWN_SECONDS_VAR, defaults.cooldown.num_seconds())?;
+        Ok(Self {
+            challenges: var(CHALLENGES_VAR)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|challenge_id| !challenge_id.is_empty())
+                .map(str::to_string)
+                .collect(),
+            tokens_per_challenge: setting(
+                &var,
+                TOKENS_PER_CHALLENGE_VAR,
+                defaults.tokens_per_challenge,
+            )?,
+            tokens_per_coupon: setting(&var, TOKENS_PER_COUPON_VAR, defaults.tokens_per_coupon)?,
+            pass_percentage: setting(&var, PASS_PERCENTAGE_VAR, defaults.pass_percentage)?,
+            daily_cap: setting(&var, DAILY_CAP_VAR, defaults.daily_cap)?,
+            cooldown: Duration::seconds(cooldown_seconds.max(0)),
+        })
+    }
+}
+
+/// What a player has earned and claimed so far.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entitlement {
+    pub earned: u32,
+    /// Tokens of pending and sent claims, failed claims do not count.
+    pub claimed: u32,
+    pub claimed_today: u32,
+    pub last_claim_at: Option<DateTime<Utc>>,
+}
+
+impl Entitlement {
+    /// Computes the entitlement of a player from the records submitted for
+    /// their profile and their stored redemptions and claims. Challenges
+    /// outside the policy earn nothing.
+    pub fn compute(
+        policy: &ClaimPolicy,
+        performance_records: &[PerformanceRecord],
+        redemptions: &[CouponRedemption],
+        claims: &[ClaimRecord],
+        now: DateTime<Utc>,
+    ) -> Self {
+        let completed_challenges: HashSet<&str> = performance_records
+            .iter()
+            .flat_map(|record| &record.challenges_performance)
+            .filter(|(challenge_id, percentage, _)| {
+                *percentage >= policy.pass_percentage && policy.challenges.contains(challenge_id)
+            })
+            .map(|(challenge_id, _, _)| challenge_id.as_str())
+            .collect();
+        let earned = (completed_challenges.len() as u32)
+            .saturating_mul(policy.tokens_per_challenge)
+            .saturating_add((redemptions.len() as u32).saturating_mul(policy.tokens_per_coupon));
+
+        // Certificate mints of the player send no tokens
+        let claims: Vec<&ClaimRecord> = claims
+            .iter()
+            .filter(|claim| {
+                claim.status != ClaimStatus::Failed && claim.kind != ClaimKind::CertificateMint
+            })
+            .collect();
+        let day_start = now - Duration::days(1);
+        Self {
+            earned,
+            claimed: claims.iter().map(|claim| claim.amount).sum(),
+            claimed_today: claims
+                .iter()
+                .filter(|claim| claim.created_at > day_start)
+                .map(|claim| claim.amount)
+                .sum(),
+            last_claim_at: claims.iter().map(|claim| claim.created_at).max(),
+        }
+    }
+
+    pub fn available(&self) -> u32 {
+        self.earned.saturating_sub(self.claimed)
+    }
+
+    /// Checks that `amount` tokens can be claimed at `now`.
+    pub fn check(
+        &self,
+        policy: &ClaimPolicy,
+        amount: u32,
+        now: DateTime<Utc>,
+    ) -> Result<(), EligibilityError> {
+        if amount == 0 {
+            return Err(EligibilityError::InvalidAmount);
+        }
+        if let Some(next_claim_at) = self.last_claim_at.map(|last| last + policy.cooldown) {
+            if next_claim_at > now {
+                return Err(EligibilityError::CoolingDown(next_claim_at));
+            }
+        }
+        if amount > self.available() {
+            return Err(EligibilityError::ExceedsEntitlement {
+                requested: amount,
+                available: self.available(),
+            });
+        }
+        let remaining = policy.daily_cap.saturating_sub(self.claimed_today);
+        if amount > remaining {
+            return Err(EligibilityError::DailyCapReached {
+                requested: amount,
+                remaining,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Loads the entitlement of `user`, a profile id, ignoring the claim `exclude_claim`.
+///
+/// Completed challenges come from the records submitted for the profile to any
+/// leaderboard, whether they made it onto a board or not, coupons from the
+/// redemptions of the profile.
+pub async fn load_entitlement(
+    user: &str,
+    policy: &ClaimPolicy,
+    exclude_claim: Option<i64>,
+    repository: Arc<dyn Storage>,
+    now: DateTime<Utc>,
+) -> Result<Entitlement, EligibilityError> {
+    match ProfileRepository::fetch(&*repository, user.to_string()).await {
+        Ok(_) => {}
+        Err(RepositoryError::NotFound(_)) => {
+            return Err(EligibilityError::UnknownPlayer(user.to_string()))
+        }
+        Err(err) => return Err(err.into()),
+    }
+    let performance_records = repository.fetch_profile_submissions(user).await?;
+    let redemptions = repository.fetch_user_redemptions(user).await?;
+    let claims: Vec<ClaimRecord> = repository
+        .fetch_user_claims(user)
+        .await?
+        .into_iter()
+        .filter(|claim| Some(claim.id) != exclude_claim)
+        .collect();
+
+    Ok(Entitlement::compute(
+        policy,
+        &performance_records,
+        &redemptions,
+        &claims,
+        now,
+    ))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::services::v1::leaderboard::{
+        add_performance_record, AddPerformanceRecordResult, LeaderboardConfig, Submitter,
+        LEADERBOARD_NAMESPACE,
+    };
+    use crate::storage::MemoryRepository;
+    use konnektoren_core::prelude::PlayerProfile;
+
+    /// The default policy with the challenges the test records complete.
+    pub(crate) fn policy() -> ClaimPolicy {
+        let challenges = ["c1", "c2", "c3", "c4"]
+            .into_iter()
+            .map(str::to_string)
+            .chain((0..10).map(|i| format!("challenge{}", i)))
+            .collect();
+        ClaimPolicy {
+            challenges,
+            ..ClaimPolicy::default()
+        }
+    }
+
+    pub(crate) fn performance_record(
+        profile_name: &str,
+        challenges: &[(&str, u8)],
+    ) -> PerformanceRecord {
+        PerformanceRecord {
+            game_path_id: "game_path".to_string(),
+            profile_name: profile_name.to_string(),
+            challenges_performance: challenges
+                .iter()
+                .map(|(challenge_id, percentage)| (challenge_id.to_string(), *percentage, 1000))
+                .collect(),
+            total_challenges: challenges.len(),
+            performance_percentage: 100,
+            date: Utc::now(),
+        }
+    }
+
+    /// Registers `user` with `completed` challenges submitted for the profile to
+    /// the global leaderboard.
+    pub(crate) async fn eligible_player(
+        repository: &Arc<dyn Storage>,
+        user: &str,
+        completed: usize,
+    ) {
+        let mut profile = PlayerProfile::new(user.to_string());
+        profile.name = format!("{} name", user);
+        ProfileRepository::save(&**repository, profile.clone())
+            .await
+            .unwrap();
+        let challenges: Vec<(String, u8)> = (0..completed)
+            .map(|i| (format!("challenge{}", i), 100))
+            .collect();
+        let challenges: Vec<(&str, u8)> = challenges
+            .iter()
+            .map(|(challenge_id, percentage)| (challenge_id.as_str(), *percentage))
+            .collect();
+        let submitter = Submitter {
+            player: profile.name.clone(),
+            profile_id: Some(user.to_string()),
+        };
+        add_performance_record(
+            LEADERBOARD_NAMESPACE,
+            &submitter,
+            performance_record(&profile.name, &challenges),
+            &LeaderboardConfig::default(),
+            repository.clone(),
+        )
+        .await
+        .unwrap();
+    }
+
+    fn claim(id: i64, amount: u32, created_at: DateTime<Utc>) -> ClaimRecord {
+        ClaimRecord {
+            created_at,
+            ..ClaimRecord::new(
+                id,
+                ClaimKind::Transfer,
+                "alice".to_string(),
+                "address".to_string(),
+                amount,
+            )
+        }
+    }
+
+    #[test]
+    fn test_policy_from_vars() {
+        let policy = ClaimPolicy::from_vars(|key| match key {
+            CHALLENGES_VAR => Some("c1, c2,".to_string()),
+            DAILY_CAP_VAR => Some("25".to_string()),
+            COOLDOWN_SECONDS_VAR => Some("60".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(
+            policy.challenges,
+            HashSet::from(["c1".to_string(), "c2".to_string()])
+        );
+        assert_eq!(policy.daily_cap, 25);
+        assert_eq!(policy.cooldown, Duration::seconds(60));
+        assert_eq!(policy.tokens_per_challenge, 1);
+
+        let result =
+            ClaimPolicy::from_vars(|key| (key == PASS_PERCENTAGE_VAR).then(|| "lots".to_string()));
+        assert_eq!(
+            result,
+            Err(ConfigError::InvalidSetting(
+                PASS_PERCENTAGE_VAR,
+                "lots".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_entitlement_counts_completed_challenges_and_coupons() {
+        let policy = ClaimPolicy {
+            tokens_per_challenge: 2,
+            tokens_per_coupon: 3,
+            ..policy()
+        };
+        let records = vec![
+            performance_record("Alice", &[("c1", 100), ("c2", 40)]),
+            // Replaying a challenge earns nothing new
+            performance_record("Alice", &[("c1", 90), ("c3", 50)]),
+        ];
+        let redemptions = vec![CouponRedemption {
+            redeemed_by: "alice".to_string(),
+            challenge_id: "c1".to_string(),
+            redeemed_at: Utc::now(),
+        }];
+        let now = Utc::now();
+        let mut failed = claim(3, 100, now - Duration::hours(3));
+        failed.status = ClaimStatus::Failed;
+        let mint = ClaimRecord {
+            kind: ClaimKind::CertificateMint,
+            ..claim(-4, 0, now - Duration::hours(1))
+        };
+        let claims = vec![
+            claim(1, 2, now - Duration::days(2)),
+            claim(2, 1, now - Duration::hours(2)),
+            failed,
+            mint,
+        ];
+
+        let entitlement = Entitlement::compute(&policy, &records, &redemptions, &claims, now);
+        assert_eq!(entitlement.earned, 2 * 2 + 3);
+        assert_eq!(entitlement.claimed, 3);
+        assert_eq!(entitlement.claimed_today, 1);
+        assert_eq!(entitlement.available(), 4);
+        assert_eq!(entitlement.last_claim_at, Some(now - Duration::hours(2)));
+    }
+
+    #[test]
+    fn test_unknown_challenges_earn_nothing() {
+        let records = vec![performance_record(
+            "Alice",
+            &[("made-up", 100), ("another-made-up", 100)],
+        )];
+        let entitlement = Entitlement::compute(&policy(), &records, &[], &[], Utc::now());
+        assert_eq!(entitlement.earned, 0);
+
+        // Without configured challenges no challenge earns tokens
+        let records = vec![performance_record("Alice", &[("c1", 100)])];
+        let entitlement =
+            Entitlement::compute(&ClaimPolicy::default(), &records, &[], &[], Utc::now());
+        assert_eq!(entitlement.earned, 0);
+    }
+
+    #[test]
+    fn test_check_enforces_entitlement_cap_and_cooldown() {
+        let policy = ClaimPolicy {
+            daily_cap: 5,
+            cooldown: Duration::hours(1),
+            ..policy()
+        };
+        let now = Utc::now();
+        let entitlement = Entitlement {
+            earned: 20,
+            claimed: 4,
+            claimed_today: 4,
+            last_claim_at: Some(now - Duration::minutes(30)),
+        };
+
+        assert!(matches!(
+            entitlement.check(&policy, 0, now),
+            Err(EligibilityError::InvalidAmount)
+        ));
+        assert!(matches!(
+            entitlement.check(&policy, 1, now),
+            Err(EligibilityError::CoolingDown(at)) if at == now + Duration::minutes(30)
+        ));
+
+        let later = now + Duration::hours(1);
+        assert!(matches!(
+            entitlement.check(&policy, 17, later),
+            Err(EligibilityError::ExceedsEntitlement {
+                requested: 17,
+                available: 16
+            })
+        ));
+        assert!(matches!(
+            entitlement.check(&policy, 2, later),
+            Err(EligibilityError::DailyCapReached {
+                requested: 2,
+                remaining: 1
+            })
+        ));
+        assert!(entitlement.check(&policy, 1, later).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_load_entitlement() {
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let policy = policy();
+
+        let result = load_entitlement("alice", &policy, None, repository.clone(), Utc::now()).await;
+        assert!(matches!(result, Err(EligibilityError::UnknownPlayer(_))));
+
+        eligible_player(&repository, "alice", 3).await;
+        repository
+            .create_claim(claim(1, 2, Utc::now()))
+            .await
+            .unwrap();
+
+        let entitlement = load_entitlement("alice", &policy, None, repository.clone(), Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(entitlement.earned, 3);
+        assert_eq!(entitlement.available(), 1);
+
+        let entitlement = load_entitlement("alice", &policy, Some(1), repository, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(entitlement.available(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_entitlement_counts_submissions_of_the_profile_only() {
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let policy = policy();
+        let config = LeaderboardConfig {
+            capacity: 1,
+            ..Default::default()
+        };
+        let mut profile = PlayerProfile::new("alice".to_string());
+        profile.name = "alice name".to_string();
+        ProfileRepository::save(&*repository, profile)
+            .await
+            .unwrap();
+        let leader = Submitter {
+            player: "leader".to_string(),
+            profile_id: None,
+        };
+        add_performance_record(
+            LEADERBOARD_NAMESPACE,
+            &leader,
+            performance_record("leader", &[("c1", 100)]),
+            &config,
+            repository.clone(),
+        )
+        .await
+        .unwrap();
+
+        // Alice does not make it onto the full board, her submission still counts
+        let alice = Submitter {
+            player: "alice name".to_string(),
+            profile_id: Some("alice".to_string()),
+        };
+        let mut record = performance_record("alice name", &[("c1", 60), ("c2", 100)]);
+        record.performance_percentage = 10;
+        let result = add_performance_record(
+            LEADERBOARD_NAMESPACE,
+            &alice,
+            record,
+            &config,
+            repository.clone(),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(result, AddPerformanceRecordResult::LimitReached));
+
+        // Records under her name without her profile earn her nothing
+        let impostor = Submitter {
+            player: "alice name".to_string(),
+            profile_id: None,
+        };
+        add_performance_record(
+            "challenge",
+            &impostor,
+            performance_record("alice name", &[("c3", 100), ("c4", 100)]),
+            &config,
+            repository.clone(),
+        )
+        .await
+        .unwrap();
+
+        let entitlement = load_entitlement("alice", &policy, None, repository, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(entitlement.earned, 2);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::issuer::TokenIssuerError;
+use crate::services::claim_ledger::{claim_ledger_status, ClaimLedgerError};
+use crate::storage::{ClaimRecord, ClaimStatus, RepositoryError};
+use axum::http::StatusCode;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ClaimError {
+    #[error("Invalid request type {0}")]
+    InvalidRequestType(String),
+    #[error("{0}")]
+    Ledger(#[from] ClaimLedgerError),
+    #[error("{0}")]
+    Issuer(#[from] TokenIssuerError),
+    #[error("Claim {0} is still pending")]
+    Pending(i64),
+    #[error("The faucet wallet has an unconfirmed message, try again shortly")]
+    WalletBusy,
+    #[error("{error}")]
+    Failed { id: i64, error: String },
+}
+
+impl From<RepositoryError> for ClaimError {
+    fn from(err: RepositoryError) -> Self {
+        ClaimError::Ledger(err.into())
+    }
+}
+
+impl ClaimError {
+    /// The error repeated requests for a recorded claim get, `None` if the tokens were sent.
+    pub fn recorded(claim: &ClaimRecord) -> Option<Self> {
+        match claim.status {
+            ClaimStatus::Sent => None,
+            ClaimStatus::Pending | ClaimStatus::Queued | ClaimStatus::Submitted => {
+                Some(ClaimError::Pending(claim.id))
+            }
+            ClaimStatus::Failed => Some(ClaimError::Failed {
+                id: claim.id,
+                error: claim
+                    .error
+                    .clone()
+                    .unwrap_or_else(|| format!("Claim {} failed", claim.id)),
+            }),
+        }
+    }
+}
+
+pub(crate) fn claim_error_response(err: ClaimError) -> (StatusCode, String) {
+    let status = match &err {
+        ClaimError::Ledger(ledger) => claim_ledger_status(ledger),
+        ClaimError::InvalidRequestType(_)
+        | ClaimError::Issuer(TokenIssuerError::InvalidAddress(_)) => StatusCode::BAD_REQUEST,
+        ClaimError::Issuer(TokenIssuerError::InsufficientFunds { .. }) => {
+            log::warn!("Claim rejected: {}", err);
+            StatusCode::PAYMENT_REQUIRED
+        }
+        ClaimError::Issuer(TokenIssuerError::Network(_)) => {
+            log::error!("Claim failed: {}", err);
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        ClaimError::WalletBusy => StatusCode::SERVICE_UNAVAILABLE,
+        ClaimError::Pending(_) => StatusCode::CONFLICT,
+        ClaimError::Issuer(TokenIssuerError::Signing(_)) | ClaimError::Failed { .. } => {
+            log::error!("Claim failed: {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::ClaimKind;
+
+    #[test]
+    fn test_claim_error_status() {
+        let status = |err| claim_error_response(err).0;
+        assert_eq!(
+            status(TokenIssuerError::InvalidAddress("x".to_string()).into()),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(
+                TokenIssuerError::InsufficientFunds {
+                    requested: 2,
+                    available: 1
+                }
+                .into()
+            ),
+            StatusCode::PAYMENT_REQUIRED
+        );
+        assert_eq!(
+            status(TokenIssuerError::Network("offline".to_string()).into()),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            status(ClaimLedgerError::Conflict(1).into()),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            status(ClaimError::WalletBusy),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[test]
+    fn test_recorded_claims() {
+        let mut claim = ClaimRecord::new(
+            1,
+            ClaimKind::Transfer,
+            "alice".to_string(),
+            "address".to_string(),
+            1,
+        );
+        assert!(matches!(
+            ClaimError::recorded(&claim),
+            Some(ClaimError::Pending(1))
+        ));
+        claim.status = ClaimStatus::Failed;
+        claim.error = Some("out of funds".to_string());
+        assert_eq!(
+            claim_error_response(ClaimError::recorded(&claim).unwrap()),
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "out of funds".to_string()
+            )
+        );
+        claim.status = ClaimStatus::Sent;
+        assert!(ClaimError::recorded(&claim).is_none());
+    }
+}
@@ -0,0 +1,276 @@
+use crate::services::claim_eligibility::{load_entitlement, ClaimPolicy, EligibilityError};
+use crate::storage::{ClaimRecord, ClaimRepository, ClaimStatus, RepositoryError, Storage};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ClaimLedgerError {
+    #[error("Repository error: {0}")]
+    Repository(#[from] RepositoryError),
+    #[error("Claim {0} was already made with different details")]
+    Conflict(i64),
+    #[error("Claim ids must be positive, got {0}")]
+    InvalidId(i64),
+    #[error("{0}")]
+    NotEligible(#[from] EligibilityError),
+}
+
+/// Outcome of recording a claim in the ledger.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClaimStart {
+    /// The claim is new, the caller delivers the tokens.
+    New(ClaimRecord),
+    /// A claim with the same id was recorded before, nothing must be sent again.
+    Existing(ClaimRecord),
+}
+
+/// Records `claim` as pending, or returns the claim recorded before under its id.
+///
+/// New claims must be covered by the entitlement of the user under `policy`.
+/// Fails with [`ClaimLedgerError::Conflict`] if the id was used for another
+/// user, address, amount or claim kind.
+pub async fn start_claim(
+    claim: ClaimRecord,
+    policy: &ClaimPolicy,
+    repository: Arc<dyn Storage>,
+    now: DateTime<Utc>,
+) -> Result<ClaimStart, ClaimLedgerError> {
+    // Non-positive ids are taken by the certificate mints of the claim queue
+    if claim.id <= 0 {
+        return Err(ClaimLedgerError::InvalidId(claim.id));
+    }
+    if let Some(existing) = repository.fetch_claim(claim.id).await? {
+        return existing_claim(existing, &claim);
+    }
+
+    load_entitlement(&claim.user, policy, None, repository.clone(), now)
+        .await?
+        .check(policy, claim.amount, now)?;
+
+    let created = match repository.create_claim(claim.clone()).await {
+        Ok(created) => created,
+        Err(RepositoryError::AlreadyExists(_)) => {
+            let existing = repository
+                .fetch_claim(claim.id)
+                .await?
+                .ok_or_else(|| RepositoryError::NotFound(claim.id.to_string()))?;
+            return existing_claim(existing, &claim);
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    // Concurrent claims of the user may all have passed the check above,
+    // checking again with them recorded rejects the claims that race.
+    let recheck = load_entitlement(
+        &claim.user,
+        policy,
+        Some(created.id),
+        repository.clone(),
+        now,
+    )
+    .await
+    .and_then(|entitlement| entitlement.check(policy, created.amount, now));
+    if let Err(err) = recheck {
+        fail_claim(created, err.to_string(), repository).await?;
+        return Err(err.into());
+    }
+    Ok(ClaimStart::New(created))
+}
+
+fn existing_claim(
+    existing: ClaimRecord,
+    claim: &ClaimRecord,
+) -> Result<ClaimStart, ClaimLedgerError> {
+    if existing.is_same_request(claim) {
+        Ok(ClaimStart::Existing(existing))
+    } else {
+        Err(ClaimLedgerError::Conflict(claim.id))
+    }
+}
+
+/// Marks a pending claim as sent.
+pub async fn complete_claim(
+    mut claim: ClaimRecord,
+    tx_hash: Option<String>,
+    raw_transaction: Option<String>,
+    repository: Arc<dyn Storage>,
+) -> Result<ClaimRecord, ClaimLedgerError> {
+    claim.status = ClaimStatus::Sent;
+    claim.tx_hash = tx_hash;
+    claim.raw_transaction = raw_transaction;
+    claim.error = None;
+    claim.updated_at = Utc::now();
+    Ok(repository.update_claim(claim).await?)
+}
+
+/// Marks a pending claim as failed, keeping `error` for repeated requests.
+pub async fn fail_claim(
+    mut claim: ClaimRecord,
+    error: String,
+    repository: Arc<dyn Storage>,
+) -> Result<ClaimRecord, ClaimLedgerError> {
+    claim.status = ClaimStatus::Failed;
+    claim.error = Some(error);
+    claim.updated_at = Utc::now();
+    Ok(repository.update_claim(claim).await?)
+}
+
+pub async fn get_claim(
+    id: i64,
+    repository: Arc<dyn Storage>,
+) -> Result<Option<ClaimRecord>, ClaimLedgerError> {
+    Ok(repository.fetch_claim(id).await?)
+}
+
+pub(crate) fn claim_ledger_error_response(err: ClaimLedgerError) -> (StatusCode, String) {
+    (claim_ledger_status(&err), err.to_string())
+}
+
+pub(crate) fn claim_ledger_status(err: &ClaimLedgerError) -> StatusCode {
+    match err {
+        ClaimLedgerError::Conflict(_) => StatusCode::CONFLICT,
+        ClaimLedgerError::InvalidId(_) => StatusCode::BAD_REQUEST,
+        ClaimLedgerError::NotEligible(EligibilityError::UnknownPlayer(_)) => StatusCode::NOT_FOUND,
+        ClaimLedgerError::NotEligible(EligibilityError::InvalidAmount) => StatusCode::BAD_REQUEST,
+        ClaimLedgerError::NotEligible(EligibilityError::ExceedsEntitlement { .. }) => {
+            StatusCode::FORBIDDEN
+        }
+        ClaimLedgerError::NotEligible(
+            EligibilityError::DailyCapReached { .. } | EligibilityError::CoolingDown(_),
+        ) => StatusCode::TOO_MANY_REQUESTS,
+        ClaimLedgerError::Repository(_)
+        | ClaimLedgerError::NotEligible(EligibilityError::Repository(_)) => {
+            log::error!("Claim ledger failed: {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::claim_eligibility::tests::{eligible_player, policy};
+    use crate::storage::{ClaimKind, MemoryRepository};
+    use chrono::Duration;
+
+    fn claim(id: i64, amount: u32) -> ClaimRecord {
+        ClaimRecord::new(
+            id,
+            ClaimKind::Transfer,
+            "alice".to_string(),
+            "address".to_string(),
+            amount,
+        )
+    }
+
+    async fn repository() -> Arc<dyn Storage> {
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        eligible_player(&repository, "alice", 5).await;
+        repository
+    }
+
+    async fn start_new(claim: ClaimRecord, repository: &Arc<dyn Storage>) -> ClaimRecord {
+        match start_claim(claim, &policy(), repository.clone(), Utc::now()).await {
+            Ok(ClaimStart::New(claim)) => claim,
+            other => panic!("expected a new claim, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeated_claims_return_the_recorded_claim() {
+        let repository = repository().await;
+        let policy = policy();
+
+        let pending = start_new(claim(1, 5), &repository).await;
+        assert_eq!(pending.status, ClaimStatus::Pending);
+
+        // Repeating the claim is not limited by the cooldown
+        let start = start_claim(claim(1, 5), &policy, repository.clone(), Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(start, ClaimStart::Existing(pending.clone()));
+
+        let sent = complete_claim(pending, Some("hash".to_string()), None, repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(sent.status, ClaimStatus::Sent);
+        let start = start_claim(claim(1, 5), &policy, repository.clone(), Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(start, ClaimStart::Existing(sent.clone()));
+        assert_eq!(get_claim(1, repository.clone()).await.unwrap(), Some(sent));
+    }
+
+    #[tokio::test]
+    async fn test_claim_id_reused_for_another_request() {
+        let repository = repository().await;
+        start_new(claim(1, 5), &repository).await;
+
+        let result = start_claim(claim(1, 500), &policy(), repository.clone(), Utc::now()).await;
+        assert!(matches!(result, Err(ClaimLedgerError::Conflict(1))));
+
+        let result = start_claim(claim(-1, 5), &policy(), repository.clone(), Utc::now()).await;
+        assert!(matches!(result, Err(ClaimLedgerError::InvalidId(-1))));
+    }
+
+    #[tokio::test]
+    async fn test_claims_must_be_covered_by_the_entitlement() {
+        let repository = repository().await;
+        let policy = policy();
+
+        let result = start_claim(claim(1, 6), &policy, repository.clone(), Utc::now()).await;
+        assert!(matches!(
+            result,
+            Err(ClaimLedgerError::NotEligible(
+                EligibilityError::ExceedsEntitlement {
+                    requested: 6,
+                    available: 5
+                }
+            ))
+        ));
+        // Rejected claims are not recorded
+        assert_eq!(get_claim(1, repository.clone()).await.unwrap(), None);
+
+        start_new(claim(2, 3), &repository).await;
+        let result = start_claim(claim(3, 1), &policy, repository.clone(), Utc::now()).await;
+        assert!(matches!(
+            result,
+            Err(ClaimLedgerError::NotEligible(
+                EligibilityError::CoolingDown(_)
+            ))
+        ));
+
+        let later = Utc::now() + policy.cooldown + Duration::minutes(1);
+        let result = start_claim(claim(4, 3), &policy, repository.clone(), later).await;
+        assert!(matches!(
+            result,
+            Err(ClaimLedgerError::NotEligible(
+                EligibilityError::ExceedsEntitlement {
+                    requested: 3,
+                    available: 2
+                }
+            ))
+        ));
+        let start = start_claim(claim(5, 2), &policy, repository, later)
+            .await
+            .unwrap();
+        assert!(matches!(start, ClaimStart::New(_)));
+    }
+
+    #[tokio::test]
+    async fn test_failed_claims_keep_their_error() {
+        let repository = repository().await;
+        let pending = start_new(claim(2, 5), &repository).await;
+
+        fail_claim(pending, "out of funds".to_string(), repository.clone())
+            .await
+            .unwrap();
+        let Some(failed) = get_claim(2, repository).await.unwrap() else {
+            panic!("expected a recorded claim");
+        };
+        assert_eq!(failed.status, ClaimStatus::Failed);
+        assert_eq!(failed.error.as_deref(), Some("out of funds"));
+    }
+}
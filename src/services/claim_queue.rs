@@ -0,0 +1,760 @@
+use crate::config::{setting, ConfigError};
+use crate::issuer::{TokenIssuer, TokenIssuerError};
+use crate::services::claim_error::ClaimError;
+use crate::storage::{
+    CertificateRecord, CertificateRepository, CertificateStatus, ClaimKind, ClaimRecord,
+    ClaimRepository, ClaimStatus, RepositoryError, Storage,
+};
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+use tokio::sync::{Mutex, Notify};
+
+pub const MAX_ATTEMPTS_VAR: &str = "CLAIM_QUEUE_MAX_ATTEMPTS";
+pub const RETRY_SECONDS_VAR: &str = "CLAIM_QUEUE_RETRY_SECONDS";
+pub const POLL_SECONDS_VAR: &str = "CLAIM_QUEUE_POLL_SECONDS";
+pub const CONFIRM_TIMEOUT_SECONDS_VAR: &str = "CLAIM_QUEUE_CONFIRM_TIMEOUT_SECONDS";
+
+/// Longest wait between two attempts of a claim.
+const MAX_RETRY_DELAY_SECONDS: i64 = 3600;
+
+/// How the claim worker retries and confirms transfers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClaimQueueConfig {
+    /// Attempts to send a claim before it fails.
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubled on every further retry.
+    pub retry_delay: Duration,
+    /// How often the worker looks for due claims and confirmations.
+    pub poll_interval: Duration,
+    /// How long a transfer may stay unconfirmed before it is sent again.
+    /// Must exceed the time signed messages stay valid.
+    pub confirm_timeout: Duration,
+}
+
+impl Default for ClaimQueueConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            retry_delay: Duration::seconds(10),
+            poll_interval: Duration::seconds(5),
+            confirm_timeout: Duration::seconds(120),
+        }
+    }
+}
+
+impl ClaimQueueConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    /// Builds the config from a variable lookup, unset variables keep their default.
+    pub fn from_vars<F>(var: F) -> Result<Self, ConfigError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let defaults = Self::default();
+        let seconds = |key, default: Duration| {
+            setting(&var, key, default.num_seconds())
+                .map(|seconds| Duration::seconds(seconds.max(1)))
+        };
+        Ok(Self {
+            max_attempts: setting(&var, MAX_ATTEMPTS_VAR, defaults.max_attempts)?.max(1),
+            retry_delay: seconds(RETRY_SECONDS_VAR, defaults.retry_delay)?,
+            poll_interval: seconds(POLL_SECONDS_VAR, defaults.poll_interval)?,
+            confirm_timeout: seconds(CONFIRM_TIMEOUT_SECONDS_VAR, defaults.confirm_timeout)?,
+        })
+    }
+
+    /// Wait before the next attempt of a claim that was attempted `attempts` times.
+    fn retry_delay(&self, attempts: u32) -> Duration {
+        let max_delay = Duration::seconds(MAX_RETRY_DELAY_SECONDS);
+        let factor = 2i32.saturating_pow(attempts.saturating_sub(1));
+        self.retry_delay
+            .checked_mul(factor)
+            .map_or(max_delay, |delay| delay.min(max_delay))
+    }
+}
+
+/// Handle to the claim queue, wakes the [`ClaimWorker`] up for new claims.
+#[derive(Clone)]
+pub struct ClaimQueue {
+    issuer: Arc<dyn TokenIssuer>,
+    config: ClaimQueueConfig,
+    wakeup: Arc<Notify>,
+    /// Held while the seqno of the wallet is taken for a message, by the
+    /// worker or for a signed message claim.
+    wallet: Arc<Mutex<()>>,
+}
+
+impl ClaimQueue {
+    pub fn new(issuer: Arc<dyn TokenIssuer>, config: ClaimQueueConfig) -> Self {
+        Self {
+            issuer,
+            config,
+            wakeup: Arc::new(Notify::new()),
+            wallet: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn issuer(&self) -> &dyn TokenIssuer {
+        self.issuer.as_ref()
+    }
+
+    /// Tells the worker a claim was queued.
+    pub fn notify(&self) {
+        self.wakeup.notify_one();
+    }
+
+    /// The worker sending the claims of this queue, only one may run.
+    pub fn worker(&self, repository: Arc<dyn Storage>) -> ClaimWorker {
+        ClaimWorker {
+            queue: self.clone(),
+            config: self.config.clone(),
+            repository,
+        }
+    }
+
+    /// Signs the transfer of the signed message claim `claim` for the client to
+    /// send, with the seqno of the wallet, and records the claim as submitted
+    /// until the worker confirms its message.
+    ///
+    /// A recorded message is kept until it expires, an expired one the wallet
+    /// did not process is signed anew. Fails with [`ClaimError::WalletBusy`]
+    /// while another message of the wallet is unconfirmed.
+    pub async fn sign_message(
+        &self,
+        claim: ClaimRecord,
+        repository: Arc<dyn Storage>,
+        now: DateTime<Utc>,
+    ) -> Result<ClaimRecord, ClaimError> {
+        let _wallet = self.wallet.lock().await;
+        let issuer = self.issuer.as_ref();
+        // Another request may have signed the claim while this one waited
+        let mut claim = repository
+            .fetch_claim(claim.id)
+            .await?
+            .ok_or_else(|| RepositoryError::NotFound(claim.id.to_string()))?;
+        match claim.status {
+            ClaimStatus::Pending => {}
+            ClaimStatus::Submitted => {
+                if is_processed(issuer, &claim).await? {
+                    claim.status = ClaimStatus::Sent;
+                    claim.error = None;
+                    claim.updated_at = now;
+                    return Ok(repository.update_claim(claim).await?);
+                }
+                if now - claim.updated_at <= self.config.confirm_timeout {
+                    return Ok(claim);
+                }
+            }
+            _ => {
+                return match ClaimError::recorded(&claim) {
+                    Some(err) => Err(err),
+                    None => Ok(claim),
+                }
+            }
+        }
+
+        for other in repository.fetch_queued_claims().await? {
+            if other.id != claim.id
+                && other.status == ClaimStatus::Submitted
+                && now - other.updated_at <= self.config.confirm_timeout
+                && !is_processed(issuer, &other).await?
+            {
+                return Err(ClaimError::WalletBusy);
+            }
+        }
+
+        issuer
+            .check_transfer(&claim.address, claim.amount as u128)
+            .await?;
+        let seqno = issuer.seqno().await?;
+        let message = issuer
+            .sign_transfer(&claim.address, claim.amount as u128, seqno)
+            .await?;
+        log::info!("Signed claim {} with seqno {}", claim.id, seqno);
+        claim.attempts += 1;
+        claim.status = ClaimStatus::Submitted;
+        claim.seqno = Some(seqno);
+        claim.raw_transaction = Some(BASE64_STANDARD.encode(&message));
+        claim.error = None;
+        claim.updated_at = now;
+        Ok(repository.update_claim(claim).await?)
+    }
+}
+
+/// Whether the wallet processed the last message signed for `claim`.
+///
+/// Claims submitted before their messages were recorded only have the
+/// seqno, which the wallet may have used for another message.
+async fn is_processed(
+    issuer: &dyn TokenIssuer,
+    claim: &ClaimRecord,
+) -> Result<bool, TokenIssuerError> {
+    let message = claim
+        .raw_transaction
+        .as_deref()
+        .and_then(|message| BASE64_STANDARD.decode(message).ok());
+    match (message, claim.seqno) {
+        (Some(message), _) => issuer.message_processed(&message).await,
+        (None, Some(sent_with)) => Ok(issuer.seqno().await? > sent_with),
+        (None, None) => Ok(false),
+    }
+}
+
+/// Sends queued transfer and certificate mint claims one at a time.
+///
+/// The faucet wallet only accepts a message signed with its current seqno,
+/// so the next claim is sent once the wallet processed the previous one.
+/// Signed message claims hold the seqno the same way until the client sent
+/// their message or it expired. Other messages of the wallet use seqnos too,
+/// so a claim is confirmed by its own message.
+pub struct ClaimWorker {
+    queue: ClaimQueue,
+    repository: Arc<dyn Storage>,
+    config: ClaimQueueConfig,
+}
+
+impl ClaimWorker {
+    pub async fn run(self) {
+        let poll_interval = self
+            .config
+            .poll_interval
+            .to_std()
+            .unwrap_or(std::time::Duration::from_secs(5));
+        log::info!("Claim worker started");
+        loop {
+            if let Err(err) = self.process(Utc::now()).await {
+                log::error!("Claim worker failed: {}", err);
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(poll_interval) => {}
+                _ = self.queue.wakeup.notified() => {}
+            }
+        }
+    }
+
+    /// Confirms submitted claims, then sends the next due claim if the
+    /// wallet has no unconfirmed message.
+    pub async fn process(&self, now: DateTime<Utc>) -> Result<(), RepositoryError> {
+        let _wallet = self.queue.wallet.lock().await;
+        let mut claims = self.repository.fetch_queued_claims().await?;
+        claims.sort_by_key(|claim| (claim.created_at, claim.id));
+        let (submitted, queued): (Vec<_>, Vec<_>) = claims
+            .into_iter()
+            .partition(|claim| claim.status == ClaimStatus::Submitted);
+
+        if !submitted.is_empty() {
+            let mut unconfirmed = false;
+            for claim in submitted {
+                let processed = match is_processed(self.queue.issuer(), &claim).await {
+                    Ok(processed) => processed,
+                    Err(err) => {
+                        log::warn!("Cannot confirm claim {}: {}", claim.id, err);
+                        unconfirmed = true;
+                        continue;
+                    }
+                };
+                if processed {
+                    self.confirm(claim, now).await?;
+                } else if now - claim.updated_at > self.config.confirm_timeout
+                    && claim.kind == ClaimKind::SignedMessage
+                {
+                    self.expire(claim, now).await?;
+                } else if now - claim.updated_at > self.config.confirm_timeout {
+                    // The message expired without the wallet processing it,
+                    // so it is safe to send the tokens again.
+                    let error = "The transfer was not confirmed in time".to_string();
+                    self.retry(claim, error, now).await?;
+                } else {
+                    unconfirmed = true;
+                }
+            }
+            if unconfirmed {
+                return Ok(());
+            }
+        }
+
+        let due = queued
+            .into_iter()
+            .find(|claim| !claim.next_attempt_at.is_some_and(|at| at > now));
+        match due {
+            Some(claim) => self.submit(claim, now).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn submit(
+        &self,
+        mut claim: ClaimRecord,
+        now: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        let issuer = self.queue.issuer.as_ref();
+        claim.attempts += 1;
+        let (seqno, message) = match self.sign(&claim).await {
+            Ok(signed) => signed,
+            Err(SignError::Issuer(err)) => return self.retry_or_fail(claim, err, now).await,
+            Err(SignError::Repository(err)) => return Err(err),
+        };
+
+        // Recorded before sending, a claim must never be sent twice with
+        // different seqnos when the server stops while sending.
+        claim.status = ClaimStatus::Submitted;
+        claim.seqno = Some(seqno);
+        claim.raw_transaction = Some(BASE64_STANDARD.encode(&message));
+        claim.next_attempt_at = None;
+        claim.updated_at = now;
+        let mut claim = self.repository.update_claim(claim).await?;
+
+        match issuer.send_message(&message).await {
+            Ok(tx_hash) => {
+                log::info!("Sent claim {} with seqno {}: {}", claim.id, seqno, tx_hash);
+                claim.tx_hash = Some(tx_hash);
+                claim.error = None;
+            }
+            // The message may still have reached the network, the claim is
+            // sent again only if it expires unconfirmed.
+            Err(err) => {
+                log::warn!("Sending claim {} failed: {}", claim.id, err);
+                claim.error = Some(err.to_string());
+            }
+        }
+        self.repository.update_claim(claim).await?;
+        Ok(())
+    }
+
+    /// Signs the message of `claim` with the current seqno of the wallet.
+    async fn sign(&self, claim: &ClaimRecord) -> Result<(u32, Vec<u8>), SignError> {
+        let issuer = self.queue.issuer.as_ref();
+        if claim.kind == ClaimKind::CertificateMint {
+            let (certificate, item_index) = self.mint_certificate(claim).await?;
+            let seqno = issuer.seqno().await?;
+            let message = issuer
+                .sign_nft_mint(
+                    &certificate.owner,
+                    item_index,
+                    &certificate.metadata_url,
+                    seqno,
+                )
+                .await?;
+            return Ok((seqno, message));
+        }
+        issuer
+            .check_transfer(&claim.address, claim.amount as u128)
+            .await?;
+        let seqno = issuer.seqno().await?;
+        let message = issuer
+            .sign_transfer(&claim.address, claim.amount as u128, seqno)
+            .await?;
+        Ok((seqno, message))
+    }
+
+    /// The certificate a mint claim mints and its item index, reserved on the
+    /// first attempt. Retries reuse the index, a message that expired
+    /// unprocessed did not take it.
+    async fn mint_certificate(
+        &self,
+        claim: &ClaimRecord,
+    ) -> Result<(CertificateRecord, u64), SignError> {
+        let id = claim.certificate_id.clone().unwrap_or_default();
+        let certificate = self
+            .repository
+            .fetch_certificate(&id)
+            .await?
+            .ok_or_else(|| TokenIssuerError::Signing(format!("Certificate {} not found", id)))?;
+        if let Some(item_index) = certificate.item_index {
+            return Ok((certificate, item_index));
+        }
+
+        let next_index = self.queue.issuer.next_nft_index().await?;
+        let item_index = self
+            .repository
+            .reserve_item_index(&certificate.collection, next_index)
+            .await?;
+        log::info!(
+            "Reserved NFT {} of {} for certificate {}",
+            item_index,
+            certificate.collection,
+            certificate.id
+        );
+        let certificate = self
+            .repository
+            .update_certificate(CertificateRecord {
+                item_index: Some(item_index),
+                updated_at: Utc::now(),
+                ..certificate
+            })
+            .await?;
+        Ok((certificate, item_index))
+    }
+
+    async fn retry_or_fail(
+        &self,
+        claim: ClaimRecord,
+        err: TokenIssuerError,
+        now: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        match err {
+            TokenIssuerError::Network(_) | TokenIssuerError::InsufficientFunds { .. } => {
+                self.retry(claim, err.to_string(), now).await
+            }
+            TokenIssuerError::InvalidAddress(_) | TokenIssuerError::Signing(_) => {
+                self.fail(claim, err.to_string(), now).await
+            }
+        }
+    }
+
+    async fn retry(
+        &self,
+        mut claim: ClaimRecord,
+        error: String,
+        now: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        if claim.attempts >= self.config.max_attempts {
+            return self.fail(claim, error, now).await;
+        }
+        let next_attempt_at = now + self.config.retry_delay(claim.attempts);
+        log::warn!(
+            "Claim {} attempt {} failed, retrying at {}: {}",
+            claim.id,
+            claim.attempts,
+            next_attempt_at,
+            error
+        );
+        claim.status = ClaimStatus::Queued;
+        claim.error = Some(error);
+        claim.next_attempt_at = Some(next_attempt_at);
+        claim.updated_at = now;
+        self.repository.update_claim(claim).await?;
+        Ok(())
+    }
+
+    async fn fail(
+        &self,
+        mut claim: ClaimRecord,
+        error: String,
+        now: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        log::error!("Claim {} failed: {}", claim.id, error);
+        claim.status = ClaimStatus::Failed;
+        claim.error = Some(error);
+        claim.next_attempt_at = None;
+        claim.updated_at = now;
+        let claim = self.repository.update_claim(claim).await?;
+        self.settle_certificate(&claim, CertificateStatus::Failed, now)
+            .await
+    }
+
+    /// Releases the seqno of a signed message claim the client did not send
+    /// in time, the message is signed anew if the claim is requested again.
+    async fn expire(
+        &self,
+        mut claim: ClaimRecord,
+        now: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        log::warn!("The signed message of claim {} expired unsent", claim.id);
+        claim.status = ClaimStatus::Pending;
+        claim.error = Some("The signed message expired unsent".to_string());
+        claim.updated_at = now;
+        self.repository.update_claim(claim).await?;
+        Ok(())
+    }
+
+    async fn confirm(
+        &self,
+        mut claim: ClaimRecord,
+        now: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        log::info!("Claim {} confirmed", claim.id);
+        claim.status = ClaimStatus::Sent;
+        claim.error = None;
+        claim.updated_at = now;
+        let claim = self.repository.update_claim(claim).await?;
+        self.settle_certificate(&claim, CertificateStatus::Sent, now)
+            .await
+    }
+
+    /// Records the outcome of a mint claim on the certificate it mints.
+    async fn settle_certificate(
+        &self,
+        claim: &ClaimRecord,
+        status: CertificateStatus,
+        now: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        let Some(id) = &claim.certificate_id else {
+            return Ok(());
+        };
+        let Some(certificate) = self.repository.fetch_certificate(id).await? else {
+            return Ok(());
+        };
+        self.repository
+            .update_certificate(CertificateRecord {
+                status,
+                raw_transaction: claim.raw_transaction.clone(),
+                tx_hash: claim.tx_hash.clone(),
+                updated_at: now,
+                ..certificate
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+/// Why the message of a claim could not be signed.
+enum SignError {
+    Issuer(TokenIssuerError),
+    Repository(RepositoryError),
+}
+
+impl From<TokenIssuerError> for SignError {
+    fn from(err: TokenIssuerError) -> Self {
+        SignError::Issuer(err)
+    }
+}
+
+impl From<RepositoryError> for SignError {
+    fn from(err: RepositoryError) -> Self {
+        SignError::Repository(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::issuer::{IssuedTransfer, MemoryTokenIssuer};
+    use crate::storage::MemoryRepository;
+
+    fn queued_claim(id: i64, amount: u32) -> ClaimRecord {
+        ClaimRecord {
+            status: ClaimStatus::Queued,
+            ..ClaimRecord::new(
+                id,
+                ClaimKind::Transfer,
+                "alice".to_string(),
+                "alice-wallet".to_string(),
+                amount,
+            )
+        }
+    }
+
+    fn setup() -> (Arc<MemoryTokenIssuer>, Arc<dyn Storage>, ClaimWorker) {
+        let issuer = Arc::new(MemoryTokenIssuer::new());
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let worker =
+            ClaimQueue::new(issuer.clone(), ClaimQueueConfig::default()).worker(repository.clone());
+        (issuer, repository, worker)
+    }
+
+    async fn status(repository: &Arc<dyn Storage>, id: i64) -> ClaimRecord {
+        repository.fetch_claim(id).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_claims_are_sent_one_after_another() {
+        let (issuer, repository, worker) = setup();
+        repository.create_claim(queued_claim(1, 3)).await.unwrap();
+        repository.create_claim(queued_claim(2, 1)).await.unwrap();
+
+        let now = Utc::now();
+        worker.process(now).await.unwrap();
+        let first = status(&repository, 1).await;
+        assert_eq!(first.status, ClaimStatus::Submitted);
+        assert_eq!(first.seqno, Some(0));
+        assert!(first.tx_hash.is_some());
+        assert_eq!(status(&repository, 2).await.status, ClaimStatus::Queued);
+
+        // The wallet processed the first transfer, the next one uses the next seqno
+        worker.process(now).await.unwrap();
+        assert_eq!(status(&repository, 1).await.status, ClaimStatus::Sent);
+        assert_eq!(status(&repository, 2).await.seqno, Some(1));
+
+        worker.process(now).await.unwrap();
+        assert_eq!(status(&repository, 2).await.status, ClaimStatus::Sent);
+        assert_eq!(issuer.sent_transfers().len(), 2);
+        assert!(repository.fetch_queued_claims().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_transient_failures_are_retried_with_backoff() {
+        let (issuer, repository, worker) = setup();
+        let config = ClaimQueueConfig::default();
+        repository.create_claim(queued_claim(1, 3)).await.unwrap();
+
+        issuer.fail_with(TokenIssuerError::Network("offline".to_string()));
+        let now = Utc::now();
+        worker.process(now).await.unwrap();
+        let claim = status(&repository, 1).await;
+        assert_eq!(claim.status, ClaimStatus::Queued);
+        assert_eq!(claim.attempts, 1);
+        assert_eq!(claim.next_attempt_at, Some(now + config.retry_delay));
+
+        // Not retried before the backoff passed
+        issuer.recover();
+        worker.process(now).await.unwrap();
+        assert!(issuer.sent_transfers().is_empty());
+
+        worker.process(now + config.retry_delay).await.unwrap();
+        assert_eq!(
+            issuer.sent_transfers(),
+            vec![IssuedTransfer {
+                receiver: "alice-wallet".to_string(),
+                amount: 3,
+                seqno: 0,
+            }]
+        );
+        assert_eq!(status(&repository, 1).await.attempts, 2);
+    }
+
+    #[tokio::test]
+    async fn test_claims_fail_after_the_last_attempt() {
+        let (issuer, repository, worker) = setup();
+        let config = ClaimQueueConfig::default();
+        repository.create_claim(queued_claim(1, 3)).await.unwrap();
+        repository
+            .create_claim(ClaimRecord {
+                address: "not an address".to_string(),
+                ..queued_claim(2, 1)
+            })
+            .await
+            .unwrap();
+
+        issuer.set_balance(1);
+        let mut now = Utc::now();
+        for _ in 0..config.max_attempts {
+            worker.process(now).await.unwrap();
+            now += Duration::seconds(MAX_RETRY_DELAY_SECONDS);
+        }
+        let claim = status(&repository, 1).await;
+        assert_eq!(claim.status, ClaimStatus::Failed);
+        assert_eq!(claim.attempts, config.max_attempts);
+
+        // Invalid addresses are not retried
+        worker.process(now).await.unwrap();
+        let claim = status(&repository, 2).await;
+        assert_eq!(claim.status, ClaimStatus::Failed);
+        assert_eq!(claim.attempts, 1);
+        assert!(issuer.sent_transfers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unconfirmed_transfers_are_sent_again() {
+        let (issuer, repository, worker) = setup();
+        let config = ClaimQueueConfig::default();
+        let now = Utc::now();
+        // Sent with a seqno the wallet never processed
+        repository
+            .create_claim(ClaimRecord {
+                status: ClaimStatus::Submitted,
+                attempts: 1,
+                seqno: Some(5),
+                updated_at: now,
+                ..queued_claim(1, 3)
+            })
+            .await
+            .unwrap();
+        repository.create_claim(queued_claim(2, 1)).await.unwrap();
+
+        // Nothing else is sent while the transfer may still be processed
+        worker.process(now).await.unwrap();
+        assert_eq!(status(&repository, 1).await.status, ClaimStatus::Submitted);
+        assert!(issuer.sent_transfers().is_empty());
+
+        let later = now + config.confirm_timeout + Duration::seconds(1);
+        worker.process(later).await.unwrap();
+        let claim = status(&repository, 1).await;
+        assert_eq!(claim.status, ClaimStatus::Queued);
+        assert_eq!(claim.next_attempt_at, Some(later + config.retry_delay));
+        // The expired claim waits for its retry, the next claim goes first
+        assert_eq!(status(&repository, 2).await.status, ClaimStatus::Submitted);
+    }
+
+    #[tokio::test]
+    async fn test_claims_are_confirmed_by_their_own_message() {
+        let (issuer, repository, worker) = setup();
+        let config = ClaimQueueConfig::default();
+        let now = Utc::now();
+        // Signed with the current seqno, but another message of the wallet takes it
+        let message = issuer.sign_transfer("alice-wallet", 3, 0).await.unwrap();
+        repository
+            .create_claim(ClaimRecord {
+                status: ClaimStatus::Submitted,
+                attempts: 1,
+                seqno: Some(0),
+                raw_transaction: Some(BASE64_STANDARD.encode(&message)),
+                updated_at: now,
+                ..queued_claim(1, 3)
+            })
+            .await
+            .unwrap();
+        let other = issuer.signed_transfer("bob-wallet", 1).await.unwrap();
+        issuer.send_message(&other).await.unwrap();
+
+        worker.process(now).await.unwrap();
+        assert_eq!(status(&repository, 1).await.status, ClaimStatus::Submitted);
+
+        let later = now + config.confirm_timeout + Duration::seconds(1);
+        worker.process(later).await.unwrap();
+        assert_eq!(status(&repository, 1).await.status, ClaimStatus::Queued);
+        worker.process(later + config.retry_delay(1)).await.unwrap();
+        let claim = status(&repository, 1).await;
+        assert_eq!(claim.seqno, Some(1));
+
+        worker.process(later + config.retry_delay(1)).await.unwrap();
+        assert_eq!(status(&repository, 1).await.status, ClaimStatus::Sent);
+        let receivers: Vec<String> = issuer
+            .sent_transfers()
+            .into_iter()
+            .map(|transfer| transfer.receiver)
+            .collect();
+        assert_eq!(receivers, vec!["bob-wallet", "alice-wallet"]);
+    }
+
+    #[tokio::test]
+    async fn test_mints_of_missing_certificates_fail() {
+        let (issuer, repository, worker) = setup();
+        repository
+            .create_claim(ClaimRecord {
+                kind: ClaimKind::CertificateMint,
+                certificate_id: Some("missing".to_string()),
+                ..queued_claim(-1, 0)
+            })
+            .await
+            .unwrap();
+
+        worker.process(Utc::now()).await.unwrap();
+        let claim = status(&repository, -1).await;
+        assert_eq!(claim.status, ClaimStatus::Failed);
+        assert!(claim
+            .error
+            .is_some_and(|error| error.contains("Certificate missing not found")));
+        assert!(issuer.minted_nfts().is_empty());
+    }
+
+    #[test]
+    fn test_config_from_vars() {
+        let config = ClaimQueueConfig::from_vars(|key| match key {
+            MAX_ATTEMPTS_VAR => Some("3".to_string()),
+            RETRY_SECONDS_VAR => Some("30".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(config.max_attempts, 3);
+        assert_eq!(config.retry_delay, Duration::seconds(30));
+        assert_eq!(config.retry_delay(3), Duration::seconds(120));
+        assert_eq!(
+            config.retry_delay(30),
+            Duration::seconds(MAX_RETRY_DELAY_SECONDS)
+        );
+        assert_eq!(
+            config.poll_interval,
+            ClaimQueueConfig::default().poll_interval
+        );
+
+        let result = ClaimQueueConfig::from_vars(|key| {
+            (key == POLL_SECONDS_VAR).then(|| "often".to_string())
+        });
+        assert!(matches!(result, Err(ConfigError::InvalidSetting(_, _))));
+    }
+}
@@ -0,0 +1,254 @@
+use crate::config::{setting, ConfigError};
+use crate::issuer::{JettonInfo, TokenIssuer, TokenIssuerError};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use utoipa::ToSchema;
+
+pub const MIN_TON_BALANCE_VAR: &str = "FAUCET_MIN_TON_BALANCE";
+pub const MIN_JETTON_BALANCE_VAR: &str = "FAUCET_MIN_JETTON_BALANCE";
+pub const CACHE_SECONDS_VAR: &str = "FAUCET_CACHE_SECONDS";
+
+const NANOTONS_PER_TON: u128 = 1_000_000_000;
+
+/// When the faucet balance counts as low and how long chain data is cached.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaucetConfig {
+    /// Nanotons below which the faucet cannot pay fees much longer.
+    pub min_ton_balance: u128,
+    /// Jettons below which the faucet cannot serve claims much longer.
+    pub min_jetton_balance: u128,
+    pub cache_ttl: Duration,
+}
+
+impl Default for FaucetConfig {
+    fn default() -> Self {
+        Self {
+            min_ton_balance: 2 * NANOTONS_PER_TON,
+            min_jetton_balance: 100,
+            cache_ttl: Duration::seconds(30),
+        }
+    }
+}
+
+impl FaucetConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    /// Builds the config from a variable lookup, the TON minimum is given in whole TON.
+    pub fn from_vars<F>(var: F) -> Result<Self, ConfigError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let defaults = Self::default();
+        let min_ton: u128 = setting(
+            &var,
+            MIN_TON_BALANCE_VAR,
+            defaults.min_ton_balance / NANOTONS_PER_TON,
+        )?;
+        let cache_seconds: u32 = setting(
+            &var,
+            CACHE_SECONDS_VAR,
+            defaults.cache_ttl.num_seconds() as u32,
+        )?;
+        Ok(Self {
+            min_ton_balance: min_ton.saturating_mul(NANOTONS_PER_TON),
+            min_jetton_balance: setting(&var, MIN_JETTON_BALANCE_VAR, defaults.min_jetton_balance)?,
+            cache_ttl: Duration::seconds(cache_seconds.into()),
+        })
+    }
+}
+
+/// Balances of the faucet wallet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FaucetInfo {
+    #[schema(example = "EQBcofB8fWf9JoFqcxN3tkBOhXJldhZ2YmpL1v2mUikxGXO8")]
+    pub address: String,
+    /// TON to pay fees, in nanotons
+    #[schema(value_type = u64, example = 5000000000u64)]
+    pub ton_balance: u128,
+    /// Whole jettons left for claims
+    #[schema(value_type = u64, example = 1000)]
+    pub jetton_balance: u128,
+    /// Whether a balance is below its configured minimum
+    pub low_balance: bool,
+}
+
+/// Values of the last `ttl`, keeps repeated requests off the network.
+struct TtlCache<K, V> {
+    ttl: Duration,
+    entries: Mutex<HashMap<K, (DateTime<Utc>, V)>>,
+}
+
+impl<K: Eq + Hash, V: Clone> TtlCache<K, V> {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<K, (DateTime<Utc>, V)>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn get(&self, key: &K, now: DateTime<Utc>) -> Option<V> {
+        self.entries()
+            .get(key)
+            .filter(|(cached_at, _)| now - *cached_at < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    fn insert(&self, key: K, value: V, now: DateTime<Utc>) {
+        let mut entries = self.entries();
+        entries.retain(|_, (cached_at, _)| now - *cached_at < self.ttl);
+        entries.insert(key, (now, value));
+    }
+}
+
+/// Reads faucet and jetton data from the chain, cached for the configured TTL.
+pub struct FaucetMonitor {
+    issuer: Arc<dyn TokenIssuer>,
+    config: FaucetConfig,
+    faucet: TtlCache<(), FaucetInfo>,
+    jetton: TtlCache<(), JettonInfo>,
+    balances: TtlCache<String, u128>,
+}
+
+impl FaucetMonitor {
+    pub fn new(issuer: Arc<dyn TokenIssuer>, config: FaucetConfig) -> Self {
+        Self {
+            issuer,
+            faucet: TtlCache::new(config.cache_ttl),
+            jetton: TtlCache::new(config.cache_ttl),
+            balances: TtlCache::new(config.cache_ttl),
+            config,
+        }
+    }
+
+    pub async fn faucet_info(&self, now: DateTime<Utc>) -> Result<FaucetInfo, TokenIssuerError> {
+        if let Some(info) = self.faucet.get(&(), now) {
+            return Ok(info);
+        }
+        let ton_balance = self.issuer.ton_balance().await?;
+        let jetton_balance = self.issuer.jetton_balance().await?;
+        let info = FaucetInfo {
+            address: self.issuer.wallet_address(),
+            ton_balance,
+            jetton_balance,
+            low_balance: ton_balance < self.config.min_ton_balance
+                || jetton_balance < self.config.min_jetton_balance,
+        };
+        if info.low_balance {
+            log::warn!(
+                "Faucet balance is low: {} nanotons, {} jettons",
+                info.ton_balance,
+                info.jetton_balance
+            );
+        }
+        self.faucet.insert((), info.clone(), now);
+        Ok(info)
+    }
+
+    pub async fn jetton_info(&self, now: DateTime<Utc>) -> Result<JettonInfo, TokenIssuerError> {
+        if let Some(info) = self.jetton.get(&(), now) {
+            return Ok(info);
+        }
+        let info = self.issuer.jetton_info().await?;
+        self.jetton.insert((), info.clone(), now);
+        Ok(info)
+    }
+
+    /// Whole jettons held by `address`, checked before any network access.
+    pub async fn jetton_balance(
+        &self,
+        address: &str,
+        now: DateTime<Utc>,
+    ) -> Result<u128, TokenIssuerError> {
+        self.issuer.validate_address(address)?;
+        if let Some(balance) = self.balances.get(&address.to_string(), now) {
+            return Ok(balance);
+        }
+        let balance = self.issuer.jetton_balance_of(address).await?;
+        self.balances.insert(address.to_string(), balance, now);
+        Ok(balance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::issuer::MemoryTokenIssuer;
+
+    fn monitor(issuer: &Arc<MemoryTokenIssuer>) -> FaucetMonitor {
+        FaucetMonitor::new(issuer.clone(), FaucetConfig::default())
+    }
+
+    #[tokio::test]
+    async fn test_faucet_info_is_cached() {
+        let issuer = Arc::new(MemoryTokenIssuer::new());
+        issuer.set_balance(500);
+        issuer.set_ton_balance(5 * NANOTONS_PER_TON);
+        let monitor = monitor(&issuer);
+        let now = Utc::now();
+
+        let info = monitor.faucet_info(now).await.unwrap();
+        assert_eq!(info.jetton_balance, 500);
+        assert!(!info.low_balance);
+
+        issuer.transfer("alice", 450).await.unwrap();
+        assert_eq!(monitor.faucet_info(now).await.unwrap(), info);
+
+        let later = now + FaucetConfig::default().cache_ttl;
+        let info = monitor.faucet_info(later).await.unwrap();
+        assert_eq!(info.jetton_balance, 50);
+        assert!(info.low_balance);
+        assert_eq!(monitor.jetton_balance("alice", later).await.unwrap(), 450);
+    }
+
+    #[tokio::test]
+    async fn test_low_ton_balance() {
+        let issuer = Arc::new(MemoryTokenIssuer::new());
+        issuer.set_ton_balance(NANOTONS_PER_TON);
+        let info = monitor(&issuer).faucet_info(Utc::now()).await.unwrap();
+        assert!(info.low_balance);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_addresses_and_network_errors() {
+        let issuer = Arc::new(MemoryTokenIssuer::new());
+        let monitor = monitor(&issuer);
+        let now = Utc::now();
+        assert!(matches!(
+            monitor.jetton_balance("no address", now).await,
+            Err(TokenIssuerError::InvalidAddress(_))
+        ));
+
+        issuer.fail_with(TokenIssuerError::Network("offline".to_string()));
+        assert!(matches!(
+            monitor.jetton_info(now).await,
+            Err(TokenIssuerError::Network(_))
+        ));
+        issuer.recover();
+        let info = monitor.jetton_info(now).await.unwrap();
+        assert_eq!(info.symbol.as_deref(), Some("MEM"));
+    }
+
+    #[test]
+    fn test_config_from_vars() {
+        let config = FaucetConfig::from_vars(|key| match key {
+            MIN_TON_BALANCE_VAR => Some("10".to_string()),
+            CACHE_SECONDS_VAR => Some("5".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(config.min_ton_balance, 10 * NANOTONS_PER_TON);
+        assert_eq!(config.min_jetton_balance, 100);
+        assert_eq!(config.cache_ttl, Duration::seconds(5));
+    }
+}
@@ -1,2 +1,8 @@
+pub mod certificate;
+pub mod claim_eligibility;
+pub mod claim_error;
+pub mod claim_ledger;
+pub mod claim_queue;
+pub mod faucet;
 pub mod v1;
 pub mod v2;
@@ -1,41 +1,168 @@
 use crate::routes::v1::claim::ClaimRequest;
-use axum::{http::StatusCode, Json};
-use std::env;
+use crate::services::claim_eligibility::ClaimPolicy;
+use crate::services::claim_error::ClaimError;
+use crate::services::claim_ledger::{start_claim, ClaimStart};
+use crate::services::claim_queue::ClaimQueue;
+use crate::storage::{ClaimKind, ClaimRecord, ClaimStatus, Storage};
+use chrono::Utc;
+use std::sync::Arc;
 
-#[cfg(feature = "ton")]
-use crate::ton::{create_key_pair, create_testnet_client, send_jetton, transfer_jetton_token};
-
-#[cfg(feature = "ton")]
+/// Queues the claimed jettons once per claim id, if the player earned them under `policy`.
+///
+/// The claim worker of `queue` sends the tokens. Repeated requests with the
+/// same id get the recorded claim, so clients can poll its status.
 pub async fn claim_tokens_service(
     payload: ClaimRequest,
-) -> Result<Json<&'static str>, (StatusCode, String)> {
+    policy: &ClaimPolicy,
+    queue: &ClaimQueue,
+    repository: Arc<dyn Storage>,
+) -> Result<ClaimRecord, ClaimError> {
     if payload.request_type != "claim" {
-        return Err((StatusCode::BAD_REQUEST, "Invalid request type".into()));
+        return Err(ClaimError::InvalidRequestType(payload.request_type));
     }
+    queue.issuer().validate_address(&payload.address)?;
 
     log::info!("Received claim request: {:?}", payload);
 
-    let client = match create_testnet_client().await {
-        Ok(client) => client,
-        Err(err) => return Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
+    let claim = ClaimRecord {
+        status: ClaimStatus::Queued,
+        ..ClaimRecord::new(
+            payload.id,
+            ClaimKind::Transfer,
+            payload.user,
+            payload.address,
+            payload.amount,
+        )
     };
+    match start_claim(claim, policy, repository, Utc::now()).await? {
+        ClaimStart::New(claim) => {
+            log::info!("Queued claim {}", claim.id);
+            queue.notify();
+            Ok(claim)
+        }
+        ClaimStart::Existing(claim) => {
+            log::info!("Claim {} was already made: {:?}", claim.id, claim.status);
+            match ClaimError::recorded(&claim) {
+                Some(err @ ClaimError::Failed { .. }) => Err(err),
+                _ => Ok(claim),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::issuer::{IssuedTransfer, MemoryTokenIssuer, TokenIssuerError};
+    use crate::services::claim_eligibility::tests::{eligible_player, policy};
+    use crate::services::claim_ledger::ClaimLedgerError;
+    use crate::services::claim_queue::ClaimQueueConfig;
+    use crate::storage::{ClaimRepository, MemoryRepository};
+
+    fn request(id: i64, amount: u32) -> ClaimRequest {
+        ClaimRequest {
+            id,
+            user: "alice".to_string(),
+            request_type: "claim".to_string(),
+            address: "alice-wallet".to_string(),
+            amount,
+        }
+    }
+
+    async fn repository() -> Arc<dyn Storage> {
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        eligible_player(&repository, "alice", 5).await;
+        repository
+    }
+
+    #[tokio::test]
+    async fn test_claim_is_queued_and_sent_once() {
+        let repository = repository().await;
+        let issuer = Arc::new(MemoryTokenIssuer::new());
+        let queue = ClaimQueue::new(issuer.clone(), ClaimQueueConfig::default());
+        let worker = queue.worker(repository.clone());
+        let policy = policy();
+
+        let claim = claim_tokens_service(request(1, 3), &policy, &queue, repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(claim.status, ClaimStatus::Queued);
+        assert!(issuer.sent_transfers().is_empty());
+
+        worker.process(Utc::now()).await.unwrap();
+        let claim = claim_tokens_service(request(1, 3), &policy, &queue, repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(claim.status, ClaimStatus::Submitted);
+        worker.process(Utc::now()).await.unwrap();
+        worker.process(Utc::now()).await.unwrap();
+
+        assert_eq!(
+            issuer.sent_transfers(),
+            vec![IssuedTransfer {
+                receiver: "alice-wallet".to_string(),
+                amount: 3,
+                seqno: 0,
+            }]
+        );
+        let claim = repository.fetch_claim(1).await.unwrap().unwrap();
+        assert_eq!(claim.status, ClaimStatus::Sent);
+        assert!(claim.tx_hash.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_claim_rejects_invalid_requests() {
+        let repository = repository().await;
+        let queue = ClaimQueue::new(
+            Arc::new(MemoryTokenIssuer::new()),
+            ClaimQueueConfig::default(),
+        );
+        let policy = policy();
+
+        let mut invalid = request(1, 1);
+        invalid.request_type = "other".to_string();
+        let result = claim_tokens_service(invalid, &policy, &queue, repository.clone()).await;
+        assert!(matches!(result, Err(ClaimError::InvalidRequestType(_))));
+
+        // Invalid addresses are rejected before the claim is recorded
+        let mut invalid = request(2, 1);
+        invalid.address = "alice wallet".to_string();
+        let result = claim_tokens_service(invalid, &policy, &queue, repository.clone()).await;
+        assert!(matches!(
+            result,
+            Err(ClaimError::Issuer(TokenIssuerError::InvalidAddress(_)))
+        ));
+        assert_eq!(repository.fetch_claim(2).await.unwrap(), None);
+
+        let result = claim_tokens_service(request(3, 6), &policy, &queue, repository.clone()).await;
+        assert!(matches!(
+            result,
+            Err(ClaimError::Ledger(ClaimLedgerError::NotEligible(_)))
+        ));
+        assert!(repository.fetch_queued_claims().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_claims_keep_their_error() {
+        let repository = repository().await;
+        let issuer = Arc::new(MemoryTokenIssuer::new());
+        let config = ClaimQueueConfig {
+            max_attempts: 1,
+            ..ClaimQueueConfig::default()
+        };
+        let queue = ClaimQueue::new(issuer.clone(), config);
+        let worker = queue.worker(repository.clone());
+        let policy = policy();
+        issuer.set_balance(2);
+
+        claim_tokens_service(request(1, 3), &policy, &queue, repository.clone())
+            .await
+            .unwrap();
+        worker.process(Utc::now()).await.unwrap();
 
-    let mnemonic = env::var("MNEMONIC").unwrap();
-    let key_pair = create_key_pair(&mnemonic).await.unwrap();
-
-    let contract_address = env::var("CONTRACT_ADDRESS").unwrap();
-    let faucet_address = env::var("FAUCET_ADDRESS").unwrap();
-
-    match send_jetton(
-        &client,
-        &key_pair,
-        &contract_address,
-        &payload.address,
-        payload.amount as u128,
-    )
-    .await
-    {
-        Ok(_) => Ok(Json("Token claimed successfully")),
-        Err(err) => Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
+        // Repeating the claim does not retry the transfer
+        let result = claim_tokens_service(request(1, 3), &policy, &queue, repository.clone()).await;
+        assert!(matches!(result, Err(ClaimError::Failed { id: 1, .. })));
+        assert!(issuer.sent_transfers().is_empty());
     }
 }
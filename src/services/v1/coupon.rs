@@ -1,8 +1,18 @@
-use crate::storage::{CouponRepository, RepositoryError, Storage};
+use crate::storage::{
+    CouponCampaign, CouponRedemption, CouponRepository, CouponUpdate, Redemption, RepositoryError,
+    Storage,
+};
+use chrono::{DateTime, Utc};
 use konnektoren_core::prelude::{Coupon, CouponRedemptionError};
+use rand::Rng;
 use std::sync::Arc;
 use thiserror::Error;
-use tokio::sync::Mutex;
+
+pub const MAX_CAMPAIGN_SIZE: usize = 1000;
+const CODE_LENGTH: usize = 8;
+/// Characters used for generated codes, without look-alikes like 0/O and 1/I.
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const MAX_CODE_ATTEMPTS: usize = 10;
 
 #[derive(Debug, Error)]
 pub enum CouponError {
@@ -10,31 +20,89 @@ pub enum CouponError {
     Repository(#[from] RepositoryError),
     #[error("Coupon redemption error: {0}")]
     Redemption(#[from] CouponRedemptionError),
+    #[error("Invalid campaign: {0}")]
+    InvalidCampaign(String),
+    #[error("Campaign {0} already exists")]
+    CampaignExists(String),
+    #[error("Could not generate a unique coupon code")]
+    CodeGeneration,
+    #[error("Invalid coupon update: {0}")]
+    InvalidUpdate(String),
+}
+
+/// Settings shared by all coupons of a campaign.
+#[derive(Debug, Clone)]
+pub struct CampaignSpec {
+    pub name: String,
+    pub prefix: String,
+    pub count: usize,
+    pub challenge_ids: Vec<String>,
+    pub uses: u32,
+    pub expiration_date: DateTime<Utc>,
 }
 
+/// Stores a new coupon, fails with `RepositoryError::AlreadyExists` if the code is taken.
 pub async fn create_coupon(
     coupon: Coupon,
-    repository: Arc<Mutex<dyn Storage>>,
+    repository: Arc<dyn Storage>,
 ) -> Result<Coupon, CouponError> {
-    let mut storage = repository.lock().await;
-    CouponRepository::save(&mut *storage, coupon)
+    repository
+        .create(coupon)
+        .await
+        .map_err(CouponError::Repository)
+}
+
+/// Changes expiry, uses, challenges or the active flag of an existing coupon.
+pub async fn update_coupon(
+    code: String,
+    update: CouponUpdate,
+    repository: Arc<dyn Storage>,
+) -> Result<Coupon, CouponError> {
+    if update
+        .challenge_ids
+        .as_ref()
+        .is_some_and(|challenge_ids| challenge_ids.is_empty())
+    {
+        return Err(CouponError::InvalidUpdate(
+            "at least one challenge id is required".to_string(),
+        ));
+    }
+
+    repository
+        .update(&code, &update)
+        .await
+        .map_err(CouponError::Repository)
+}
+
+/// Deletes a coupon and its redemption history.
+pub async fn delete_coupon(code: String, repository: Arc<dyn Storage>) -> Result<(), CouponError> {
+    repository
+        .delete(&code)
+        .await
+        .map_err(CouponError::Repository)
+}
+
+pub async fn is_coupon_active(
+    code: String,
+    repository: Arc<dyn Storage>,
+) -> Result<bool, CouponError> {
+    repository
+        .is_active(&code)
         .await
         .map_err(CouponError::Repository)
 }
 
 pub async fn get_coupon(
     code: String,
-    repository: Arc<Mutex<dyn Storage>>,
+    repository: Arc<dyn Storage>,
 ) -> Result<Option<Coupon>, CouponError> {
-    let storage = repository.lock().await;
-    CouponRepository::fetch(&*storage, &code)
+    CouponRepository::fetch(&*repository, &code)
         .await
         .map_err(CouponError::Repository)
 }
 
-pub async fn list_coupons(repository: Arc<Mutex<dyn Storage>>) -> Result<Vec<Coupon>, CouponError> {
-    let storage = repository.lock().await;
-    CouponRepository::fetch_all(&*storage)
+pub async fn list_coupons(repository: Arc<dyn Storage>) -> Result<Vec<Coupon>, CouponError> {
+    CouponRepository::fetch_all(&*repository)
         .await
         .map_err(CouponError::Repository)
 }
@@ -42,48 +110,207 @@ pub async fn list_coupons(repository: Arc<Mutex<dyn Storage>>) -> Result<Vec<Cou
 pub async fn validate_coupon(
     code: String,
     challenge_id: String,
-    repository: Arc<Mutex<dyn Storage>>,
+    repository: Arc<dyn Storage>,
 ) -> Result<bool, CouponError> {
-    let storage = repository.lock().await;
-    let coupon = CouponRepository::fetch(&*storage, &code)
+    let coupon = CouponRepository::fetch(&*repository, &code)
         .await
         .map_err(CouponError::Repository)?;
 
     match coupon {
         Some(coupon) => Ok(coupon.challenge_ids.contains(&challenge_id)
             && coupon.expiration_date > chrono::Utc::now()
-            && coupon.uses_remaining > 0),
+            && coupon.uses_remaining > 0
+            && repository
+                .is_active(&code)
+                .await
+                .map_err(CouponError::Repository)?),
         None => Ok(false),
     }
 }
 
+/// Redeems one use of the coupon for the caller identified by `redeemed_by`.
 pub async fn redeem_coupon(
     code: String,
     challenge_id: String,
-    repository: Arc<Mutex<dyn Storage>>,
+    redeemed_by: String,
+    repository: Arc<dyn Storage>,
 ) -> Result<bool, CouponError> {
-    let mut storage = repository.lock().await;
+    match repository
+        .redeem(&code, &challenge_id, &redeemed_by)
+        .await
+        .map_err(CouponError::Repository)?
+    {
+        Redemption::Redeemed(..) => Ok(true),
+        Redemption::Rejected => Ok(false),
+        Redemption::Failed(err) => Err(CouponError::Redemption(err)),
+    }
+}
 
-    if let Some(mut coupon) = CouponRepository::fetch(&*storage, &code)
+/// Returns the redemption history, or `None` if the coupon does not exist.
+pub async fn list_redemptions(
+    code: String,
+    repository: Arc<dyn Storage>,
+) -> Result<Option<Vec<CouponRedemption>>, CouponError> {
+    if CouponRepository::fetch(&*repository, &code)
         .await
         .map_err(CouponError::Repository)?
+        .is_none()
     {
-        if !coupon.challenge_ids.contains(&challenge_id)
-            || coupon.expiration_date < chrono::Utc::now()
-            || coupon.uses_remaining == 0
-        {
-            return Ok(false);
+        return Ok(None);
+    }
+
+    let redemptions = repository
+        .fetch_redemptions(&code)
+        .await
+        .map_err(CouponError::Repository)?;
+    Ok(Some(redemptions))
+}
+
+/// Generates `spec.count` coupons with unique random codes and stores them as a campaign.
+///
+/// The name is reserved before any coupon is created, so concurrent requests
+/// for the same name cannot both succeed. If a step fails, the coupons created
+/// so far are deleted and the name is released again.
+pub async fn create_campaign(
+    spec: CampaignSpec,
+    repository: Arc<dyn Storage>,
+) -> Result<(CouponCampaign, Vec<Coupon>), CouponError> {
+    validate_campaign(&spec)?;
+    let campaign = CouponCampaign {
+        name: spec.name.clone(),
+        codes: Vec::new(),
+        created_at: Utc::now(),
+    };
+    let mut campaign = match repository.create_campaign(campaign).await {
+        Ok(campaign) => campaign,
+        Err(RepositoryError::AlreadyExists(_)) => {
+            return Err(CouponError::CampaignExists(spec.name))
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut coupons = Vec::with_capacity(spec.count);
+    for _ in 0..spec.count {
+        match create_unique_coupon(&spec, &*repository).await {
+            Ok(coupon) => coupons.push(coupon),
+            Err(err) => {
+                discard_campaign(&spec.name, &coupons, &*repository).await;
+                return Err(err);
+            }
         }
+    }
 
-        let trace_id = "trace-id".to_string(); // TODO: implement proper tracing
-        coupon.redeem(trace_id).map_err(CouponError::Redemption)?;
-        CouponRepository::save(&mut *storage, coupon)
-            .await
-            .map_err(CouponError::Repository)?;
-        Ok(true)
-    } else {
-        Ok(false)
+    campaign.codes = coupons.iter().map(|coupon| coupon.code.clone()).collect();
+    match repository.save_campaign(campaign).await {
+        Ok(campaign) => Ok((campaign, coupons)),
+        Err(err) => {
+            discard_campaign(&spec.name, &coupons, &*repository).await;
+            Err(err.into())
+        }
+    }
+}
+
+/// Deletes the coupons of a campaign that could not be created and releases its name.
+/// Failures are only logged, the caller reports the error that stopped the campaign.
+async fn discard_campaign(name: &str, coupons: &[Coupon], repository: &dyn Storage) {
+    for coupon in coupons {
+        if let Err(err) = repository.delete(&coupon.code).await {
+            log::error!(
+                "Error deleting coupon {} of campaign {}: {}",
+                coupon.code,
+                name,
+                err
+            );
+        }
+    }
+    if let Err(err) = repository.delete_campaign(name).await {
+        log::error!("Error releasing campaign {}: {}", name, err);
+    }
+}
+
+/// Returns a campaign with its coupons, or `None` if it does not exist.
+pub async fn get_campaign(
+    name: String,
+    repository: Arc<dyn Storage>,
+) -> Result<Option<(CouponCampaign, Vec<Coupon>)>, CouponError> {
+    let Some(campaign) = repository.fetch_campaign(&name).await? else {
+        return Ok(None);
+    };
+
+    let mut coupons = Vec::with_capacity(campaign.codes.len());
+    for code in &campaign.codes {
+        if let Some(coupon) = CouponRepository::fetch(&*repository, code).await? {
+            coupons.push(coupon);
+        }
+    }
+    Ok(Some((campaign, coupons)))
+}
+
+fn validate_campaign(spec: &CampaignSpec) -> Result<(), CouponError> {
+    if spec.name.trim().is_empty() {
+        return Err(CouponError::InvalidCampaign(
+            "name must not be empty".to_string(),
+        ));
     }
+    if spec.count == 0 || spec.count > MAX_CAMPAIGN_SIZE {
+        return Err(CouponError::InvalidCampaign(format!(
+            "count must be between 1 and {}",
+            MAX_CAMPAIGN_SIZE
+        )));
+    }
+    if !spec
+        .prefix
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(CouponError::InvalidCampaign(
+            "prefix may only contain letters, digits, '-' and '_'".to_string(),
+        ));
+    }
+    if spec.challenge_ids.is_empty() {
+        return Err(CouponError::InvalidCampaign(
+            "at least one challenge id is required".to_string(),
+        ));
+    }
+    if spec.uses == 0 {
+        return Err(CouponError::InvalidCampaign(
+            "uses must be at least 1".to_string(),
+        ));
+    }
+    if spec.expiration_date <= Utc::now() {
+        return Err(CouponError::InvalidCampaign(
+            "expiration date must be in the future".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Stores a coupon under a fresh random code, drawing again on collisions.
+async fn create_unique_coupon(
+    spec: &CampaignSpec,
+    repository: &dyn Storage,
+) -> Result<Coupon, CouponError> {
+    for _ in 0..MAX_CODE_ATTEMPTS {
+        let coupon = Coupon::new(
+            format!("{}{}", spec.prefix, random_code()),
+            spec.challenge_ids.clone(),
+            spec.uses,
+            spec.expiration_date,
+        );
+        match repository.create(coupon).await {
+            Ok(coupon) => return Ok(coupon),
+            Err(RepositoryError::AlreadyExists(_)) => continue,
+            Err(err) => return Err(CouponError::Repository(err)),
+        }
+    }
+    Err(CouponError::CodeGeneration)
+}
+
+fn random_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..CODE_LENGTH)
+        .map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char)
+        .collect()
 }
 
 #[cfg(test)]
@@ -91,10 +318,11 @@ mod tests {
     use super::*;
     use crate::storage::MemoryRepository;
     use chrono::{Duration, Utc};
+    use std::collections::HashSet;
 
     #[tokio::test]
     async fn test_create_coupon() {
-        let repository = Arc::new(Mutex::new(MemoryRepository::new()));
+        let repository = Arc::new(MemoryRepository::new());
         let coupon = Coupon::new(
             "TEST123".to_string(),
             vec!["challenge1".to_string()],
@@ -111,7 +339,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_redeem_expired_coupon() {
-        let repository = Arc::new(Mutex::new(MemoryRepository::new()));
+        let repository = Arc::new(MemoryRepository::new());
         let coupon = Coupon::new(
             "TEST123".to_string(),
             vec!["challenge1".to_string()],
@@ -119,12 +347,15 @@ mod tests {
             Utc::now() - Duration::days(1), // Expired coupon
         );
 
-        CouponRepository::save(&mut *repository.lock().await, coupon)
-            .await
-            .unwrap();
+        CouponRepository::save(&*repository, coupon).await.unwrap();
 
-        let result =
-            redeem_coupon("TEST123".to_string(), "challenge1".to_string(), repository).await;
+        let result = redeem_coupon(
+            "TEST123".to_string(),
+            "challenge1".to_string(),
+            "user1".to_string(),
+            repository,
+        )
+        .await;
 
         assert!(result.is_ok());
         assert!(!result.unwrap()); // Should return false for expired coupon
@@ -132,7 +363,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_get_coupon() {
-        let repository = Arc::new(Mutex::new(MemoryRepository::new()));
+        let repository = Arc::new(MemoryRepository::new());
         let coupon = Coupon::new(
             "TEST123".to_string(),
             vec!["challenge1".to_string()],
@@ -140,7 +371,7 @@ mod tests {
             Utc::now() + Duration::days(7),
         );
 
-        CouponRepository::save(&mut *repository.lock().await, coupon.clone())
+        CouponRepository::save(&*repository, coupon.clone())
             .await
             .unwrap();
 
@@ -158,7 +389,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_list_coupons() {
-        let repository = Arc::new(Mutex::new(MemoryRepository::new()));
+        let repository = Arc::new(MemoryRepository::new());
 
         // Create and save multiple coupons
         let coupon1 = Coupon::new(
@@ -174,12 +405,8 @@ mod tests {
             Utc::now() + Duration::days(7),
         );
 
-        CouponRepository::save(&mut *repository.lock().await, coupon1)
-            .await
-            .unwrap();
-        CouponRepository::save(&mut *repository.lock().await, coupon2)
-            .await
-            .unwrap();
+        CouponRepository::save(&*repository, coupon1).await.unwrap();
+        CouponRepository::save(&*repository, coupon2).await.unwrap();
 
         let result = list_coupons(repository).await;
         assert!(result.is_ok());
@@ -191,7 +418,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_validate_coupon() {
-        let repository = Arc::new(Mutex::new(MemoryRepository::new()));
+        let repository = Arc::new(MemoryRepository::new());
         let coupon = Coupon::new(
             "TEST123".to_string(),
             vec!["challenge1".to_string()],
@@ -199,9 +426,7 @@ mod tests {
             Utc::now() + Duration::days(7),
         );
 
-        CouponRepository::save(&mut *repository.lock().await, coupon)
-            .await
-            .unwrap();
+        CouponRepository::save(&*repository, coupon).await.unwrap();
 
         // Test valid coupon and challenge
         let result = validate_coupon(
@@ -236,7 +461,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_redeem_coupon_success() {
-        let repository = Arc::new(Mutex::new(MemoryRepository::new()));
+        let repository = Arc::new(MemoryRepository::new());
         let coupon = Coupon::new(
             "TEST123".to_string(),
             vec!["challenge1".to_string()],
@@ -244,29 +469,60 @@ mod tests {
             Utc::now() + Duration::days(7),
         );
 
-        CouponRepository::save(&mut *repository.lock().await, coupon)
-            .await
-            .unwrap();
+        CouponRepository::save(&*repository, coupon).await.unwrap();
 
         // First redemption should succeed
         let result = redeem_coupon(
             "TEST123".to_string(),
             "challenge1".to_string(),
+            "user1".to_string(),
             repository.clone(),
         )
         .await;
         assert!(result.is_ok());
         assert!(result.unwrap());
 
-        // Second redemption should fail due to trace ID
-        let result =
-            redeem_coupon("TEST123".to_string(), "challenge1".to_string(), repository).await;
+        // The same user cannot redeem twice
+        let result = redeem_coupon(
+            "TEST123".to_string(),
+            "challenge1".to_string(),
+            "user1".to_string(),
+            repository.clone(),
+        )
+        .await;
         assert!(result.is_err());
+
+        // Another user can redeem the second use
+        let result = redeem_coupon(
+            "TEST123".to_string(),
+            "challenge1".to_string(),
+            "user2".to_string(),
+            repository.clone(),
+        )
+        .await;
+        assert!(result.is_ok());
+        assert!(result.unwrap());
+
+        let redemptions = list_redemptions("TEST123".to_string(), repository)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(redemptions.len(), 2);
+        assert_eq!(redemptions[0].redeemed_by, "user1");
+        assert_eq!(redemptions[1].redeemed_by, "user2");
+    }
+
+    #[tokio::test]
+    async fn test_list_redemptions_of_nonexistent_coupon() {
+        let repository = Arc::new(MemoryRepository::new());
+
+        let result = list_redemptions("NONEXISTENT".to_string(), repository).await;
+        assert!(result.unwrap().is_none());
     }
 
     #[tokio::test]
     async fn test_redeem_coupon_invalid_challenge() {
-        let repository = Arc::new(Mutex::new(MemoryRepository::new()));
+        let repository = Arc::new(MemoryRepository::new());
         let coupon = Coupon::new(
             "TEST123".to_string(),
             vec!["challenge1".to_string()],
@@ -274,13 +530,12 @@ mod tests {
             Utc::now() + Duration::days(7),
         );
 
-        CouponRepository::save(&mut *repository.lock().await, coupon)
-            .await
-            .unwrap();
+        CouponRepository::save(&*repository, coupon).await.unwrap();
 
         let result = redeem_coupon(
             "TEST123".to_string(),
             "invalid_challenge".to_string(),
+            "user1".to_string(),
             repository,
         )
         .await;
@@ -290,15 +545,263 @@ mod tests {
 
     #[tokio::test]
     async fn test_redeem_nonexistent_coupon() {
-        let repository = Arc::new(Mutex::new(MemoryRepository::new()));
+        let repository = Arc::new(MemoryRepository::new());
 
         let result = redeem_coupon(
             "NONEXISTENT".to_string(),
             "challenge1".to_string(),
+            "user1".to_string(),
             repository,
         )
         .await;
         assert!(result.is_ok());
         assert!(!result.unwrap());
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_redemptions_respect_uses_remaining() {
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let coupon = Coupon::new(
+            "TEST123".to_string(),
+            vec!["challenge1".to_string()],
+            3,
+            Utc::now() + Duration::days(7),
+        );
+        CouponRepository::save(&*repository, coupon).await.unwrap();
+
+        let handles: Vec<_> = (0..20)
+            .map(|i| {
+                let repository = repository.clone();
+                tokio::spawn(async move {
+                    repository
+                        .redeem("TEST123", "challenge1", &format!("trace-{}", i))
+                        .await
+                        .unwrap()
+                })
+            })
+            .collect();
+
+        let mut redeemed = 0;
+        for handle in handles {
+            if let Redemption::Redeemed(..) = handle.await.unwrap() {
+                redeemed += 1;
+            }
+        }
+        assert_eq!(redeemed, 3);
+
+        let coupon = CouponRepository::fetch(&*repository, "TEST123")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(coupon.uses_remaining, 0);
+    }
+
+    fn campaign_spec(name: &str, count: usize) -> CampaignSpec {
+        CampaignSpec {
+            name: name.to_string(),
+            prefix: "CLASS-".to_string(),
+            count,
+            challenge_ids: vec!["challenge1".to_string()],
+            uses: 1,
+            expiration_date: Utc::now() + Duration::days(30),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_campaign() {
+        let repository = Arc::new(MemoryRepository::new());
+
+        let (campaign, coupons) =
+            create_campaign(campaign_spec("class-5a", 50), repository.clone())
+                .await
+                .unwrap();
+
+        assert_eq!(campaign.name, "class-5a");
+        assert_eq!(coupons.len(), 50);
+        let codes: HashSet<_> = coupons.iter().map(|coupon| coupon.code.clone()).collect();
+        assert_eq!(codes.len(), 50);
+        assert!(codes.iter().all(|code| code.starts_with("CLASS-")));
+        assert_eq!(list_coupons(repository.clone()).await.unwrap().len(), 50);
+
+        let (fetched, fetched_coupons) = get_campaign("class-5a".to_string(), repository.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetched, campaign);
+        assert_eq!(fetched_coupons, coupons);
+
+        let result = create_campaign(campaign_spec("class-5a", 1), repository).await;
+        assert!(matches!(result, Err(CouponError::CampaignExists(_))));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_campaigns_with_the_same_name() {
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+
+        let handles: Vec<_> = (0..10)
+            .map(|_| {
+                let repository = repository.clone();
+                tokio::spawn(create_campaign(campaign_spec("class-5a", 5), repository))
+            })
+            .collect();
+
+        let mut created = Vec::new();
+        for handle in handles {
+            match handle.await.unwrap() {
+                Ok((campaign, _)) => created.push(campaign),
+                Err(err) => assert!(matches!(err, CouponError::CampaignExists(_))),
+            }
+        }
+        assert_eq!(created.len(), 1);
+        assert_eq!(list_coupons(repository.clone()).await.unwrap().len(), 5);
+        let (fetched, _) = get_campaign("class-5a".to_string(), repository)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetched, created[0]);
+    }
+
+    #[test]
+    fn test_random_codes_use_the_whole_alphabet() {
+        let mut seen = vec![HashSet::new(); CODE_LENGTH];
+        for _ in 0..2000 {
+            for (position, c) in random_code().chars().enumerate() {
+                seen[position].insert(c);
+            }
+        }
+        assert!(seen.iter().all(|chars| chars.len() == CODE_ALPHABET.len()));
+    }
+
+    #[tokio::test]
+    async fn test_create_invalid_campaign() {
+        let repository = Arc::new(MemoryRepository::new());
+
+        let result = create_campaign(campaign_spec("class-5a", 0), repository.clone()).await;
+        assert!(matches!(result, Err(CouponError::InvalidCampaign(_))));
+
+        let result = create_campaign(
+            campaign_spec("class-5a", MAX_CAMPAIGN_SIZE + 1),
+            repository.clone(),
+        )
+        .await;
+        assert!(matches!(result, Err(CouponError::InvalidCampaign(_))));
+
+        let mut spec = campaign_spec("class-5a", 1);
+        spec.prefix = "a,b".to_string();
+        let result = create_campaign(spec, repository.clone()).await;
+        assert!(matches!(result, Err(CouponError::InvalidCampaign(_))));
+
+        assert!(list_coupons(repository).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_create_duplicate_coupon() {
+        let repository = Arc::new(MemoryRepository::new());
+        let coupon = Coupon::new(
+            "TEST123".to_string(),
+            vec!["challenge1".to_string()],
+            1,
+            Utc::now() + Duration::days(7),
+        );
+
+        create_coupon(coupon.clone(), repository.clone())
+            .await
+            .unwrap();
+        let result = create_coupon(coupon, repository).await;
+        assert!(matches!(
+            result,
+            Err(CouponError::Repository(RepositoryError::AlreadyExists(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_update_and_deactivate_coupon() {
+        let repository = Arc::new(MemoryRepository::new());
+        let coupon = Coupon::new(
+            "TEST123".to_string(),
+            vec!["challenge1".to_string()],
+            1,
+            Utc::now() + Duration::days(7),
+        );
+        CouponRepository::save(&*repository, coupon).await.unwrap();
+
+        let expiration_date = Utc::now() + Duration::days(30);
+        let update = CouponUpdate {
+            expiration_date: Some(expiration_date),
+            uses_remaining: Some(5),
+            challenge_ids: Some(vec!["challenge1".to_string(), "challenge2".to_string()]),
+            active: None,
+        };
+        let updated = update_coupon("TEST123".to_string(), update, repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(updated.expiration_date, expiration_date);
+        assert_eq!(updated.uses_remaining, 5);
+        assert!(validate_coupon(
+            "TEST123".to_string(),
+            "challenge2".to_string(),
+            repository.clone()
+        )
+        .await
+        .unwrap());
+
+        let deactivate = CouponUpdate {
+            active: Some(false),
+            ..Default::default()
+        };
+        update_coupon("TEST123".to_string(), deactivate, repository.clone())
+            .await
+            .unwrap();
+        assert!(!is_coupon_active("TEST123".to_string(), repository.clone())
+            .await
+            .unwrap());
+        assert!(!validate_coupon(
+            "TEST123".to_string(),
+            "challenge1".to_string(),
+            repository.clone()
+        )
+        .await
+        .unwrap());
+        let result = redeem_coupon(
+            "TEST123".to_string(),
+            "challenge1".to_string(),
+            "user1".to_string(),
+            repository.clone(),
+        )
+        .await;
+        assert!(!result.unwrap());
+
+        let invalid = CouponUpdate {
+            challenge_ids: Some(vec![]),
+            ..Default::default()
+        };
+        let result = update_coupon("TEST123".to_string(), invalid, repository).await;
+        assert!(matches!(result, Err(CouponError::InvalidUpdate(_))));
+    }
+
+    #[tokio::test]
+    async fn test_delete_coupon() {
+        let repository = Arc::new(MemoryRepository::new());
+        let coupon = Coupon::new(
+            "TEST123".to_string(),
+            vec!["challenge1".to_string()],
+            1,
+            Utc::now() + Duration::days(7),
+        );
+        CouponRepository::save(&*repository, coupon).await.unwrap();
+
+        delete_coupon("TEST123".to_string(), repository.clone())
+            .await
+            .unwrap();
+        assert!(get_coupon("TEST123".to_string(), repository.clone())
+            .await
+            .unwrap()
+            .is_none());
+
+        let result = delete_coupon("TEST123".to_string(), repository).await;
+        assert!(matches!(
+            result,
+            Err(CouponError::Repository(RepositoryError::NotFound(_)))
+        ));
+    }
 }
@@ -1,72 +1,419 @@
-use crate::storage::{RepositoryError, Storage};
+use crate::config::{setting, ConfigError};
+use crate::routes::caller::PROFILE_ID_HEADER;
+use crate::routes::v1::leaderboard::{LeaderboardEntry, PlayerRankQuery};
+use crate::services::v1::profile::{authorize_profile, ProfileError};
+use crate::storage::{ProfileRepository, RankedRecord, RepositoryError, Storage};
+use axum::http::StatusCode;
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, Utc};
 use konnektoren_core::challenges::PerformanceRecord;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use thiserror::Error;
+use utoipa::ToSchema;
 
-const PERFORMANCE_RECORDS_LIMIT: usize = 10;
+pub const CAPACITY_VAR: &str = "LEADERBOARD_CAPACITY";
+pub const NAMESPACE_CAPACITIES_VAR: &str = "LEADERBOARD_NAMESPACE_CAPACITIES";
+pub const UTC_OFFSET_VAR: &str = "LEADERBOARD_UTC_OFFSET";
+pub const PLAYER_KEY_VAR: &str = "LEADERBOARD_PLAYER_KEY";
+
+/// Records a leaderboard keeps unless configured otherwise.
+pub const DEFAULT_CAPACITY: usize = 10;
+
+/// Namespace of the global leaderboard, challenge leaderboards use the challenge id.
+pub const LEADERBOARD_NAMESPACE: &str = "leaderboard";
+
+#[derive(Debug, Error)]
+pub enum PlayerRankError {
+    #[error("Repository error: {0}")]
+    Repository(#[from] RepositoryError),
+    #[error("Either profile_name or profile_id is required")]
+    MissingPlayer,
+    #[error("Players are known by profile id, profile_id is required")]
+    MissingProfileId,
+    #[error("Player {0} is not registered")]
+    UnknownPlayer(String),
+    #[error("{0} has no records on leaderboard {1}")]
+    NotRanked(String, String),
+}
+
+#[derive(Debug, Error)]
+pub enum SubmissionError {
+    #[error("Repository error: {0}")]
+    Repository(#[from] RepositoryError),
+    #[error("Records are kept per profile, send the {0} header")]
+    MissingProfile(&'static str),
+    #[error("{0}")]
+    Profile(#[from] ProfileError),
+}
+
+/// What identifies the player of a record, a board keeps one record per player.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlayerKey {
+    /// The profile name on the record, as sent by the client.
+    #[default]
+    ProfileName,
+    /// The profile id of the request, authorized with a token of the profile.
+    ProfileId,
+}
+
+impl std::str::FromStr for PlayerKey {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "profile_name" => Ok(Self::ProfileName),
+            "profile_id" => Ok(Self::ProfileId),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Time span a leaderboard covers. Every submission feeds the boards of all periods.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum LeaderboardPeriod {
+    Daily,
+    Weekly,
+    Monthly,
+    #[default]
+    AllTime,
+}
+
+impl LeaderboardPeriod {
+    /// Namespace of the board of `namespace` for the period containing the local `day`.
+    /// Boards of past periods are kept under their own namespaces.
+    pub fn namespace(self, namespace: &str, day: NaiveDate) -> String {
+        match self {
+            Self::Daily => format!("{}:daily:{}", namespace, day.format("%Y-%m-%d")),
+            Self::Weekly => {
+                let week = day.iso_week();
+                format!("{}:weekly:{}-W{:02}", namespace, week.year(), week.week())
+            }
+            Self::Monthly => format!("{}:monthly:{}", namespace, day.format("%Y-%m")),
+            Self::AllTime => namespace.to_string(),
+        }
+    }
+}
+
+/// How many records the leaderboards keep and where their periods start.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderboardConfig {
+    /// Capacity of every namespace without its own.
+    pub capacity: usize,
+    /// Capacities of single namespaces, their period boards included.
+    pub namespace_capacities: HashMap<String, usize>,
+    /// Offset of the time zone days, weeks and months are counted in.
+    pub utc_offset: FixedOffset,
+    pub player_key: PlayerKey,
+}
+
+impl Default for LeaderboardConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_CAPACITY,
+            namespace_capacities: HashMap::new(),
+            utc_offset: FixedOffset::east_opt(0).expect("zero is a valid offset"),
+            player_key: PlayerKey::default(),
+        }
+    }
+}
+
+impl LeaderboardConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    /// Builds the config from a variable lookup, namespace capacities are
+    /// given as `namespace=capacity` pairs separated by commas. A capacity of
+    /// zero would empty the board on every submission, so it is rejected.
+    pub fn from_vars<F>(var: F) -> Result<Self, ConfigError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut namespace_capacities = HashMap::new();
+        for entry in var(NAMESPACE_CAPACITIES_VAR)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let invalid =
+                || ConfigError::InvalidSetting(NAMESPACE_CAPACITIES_VAR, entry.to_string());
+            let (namespace, capacity) = entry.split_once('=').ok_or_else(invalid)?;
+            let capacity = capacity
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|capacity| *capacity > 0)
+                .ok_or_else(invalid)?;
+            namespace_capacities.insert(namespace.trim().to_string(), capacity);
+        }
+        let capacity = setting(&var, CAPACITY_VAR, DEFAULT_CAPACITY)?;
+        if capacity == 0 {
+            return Err(ConfigError::InvalidSetting(
+                CAPACITY_VAR,
+                capacity.to_string(),
+            ));
+        }
+        Ok(Self {
+            capacity,
+            namespace_capacities,
+            utc_offset: setting(&var, UTC_OFFSET_VAR, Self::default().utc_offset)?,
+            player_key: setting(&var, PLAYER_KEY_VAR, PlayerKey::default())?,
+        })
+    }
+
+    /// The day `date` falls on in the configured time zone.
+    pub fn local_day(&self, date: DateTime<Utc>) -> NaiveDate {
+        date.with_timezone(&self.utc_offset).date_naive()
+    }
+
+    pub fn capacity(&self, namespace: &str) -> usize {
+        self.namespace_capacities
+            .get(namespace)
+            .copied()
+            .unwrap_or(self.capacity)
+    }
+}
 
 pub enum AddPerformanceRecordResult {
     Success(PerformanceRecord),
     LimitReached,
+    /// The player has a record on the board at least as good.
+    NotImproved,
+}
+
+/// Who submitted a record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Submitter {
+    /// The player the record is kept for on the boards.
+    pub player: String,
+    /// The profile the submission was authorized for, if any.
+    pub profile_id: Option<String>,
+}
+
+/// The submitter of a record, its player under the configured key.
+///
+/// Profile ids come from the request and need the owner token or a session
+/// token of the profile, names on the record are taken as they are.
+pub async fn submission_player(
+    performance_record: &PerformanceRecord,
+    profile_id: Option<&str>,
+    token: Option<&str>,
+    config: &LeaderboardConfig,
+    repository: Arc<dyn Storage>,
+) -> Result<Submitter, SubmissionError> {
+    if let Some(profile_id) = profile_id {
+        authorize_profile(profile_id, token, repository).await?;
+    }
+    let player = match config.player_key {
+        PlayerKey::ProfileName => performance_record.profile_name.clone(),
+        PlayerKey::ProfileId => profile_id
+            .ok_or(SubmissionError::MissingProfile(PROFILE_ID_HEADER))?
+            .to_string(),
+    };
+    Ok(Submitter {
+        player,
+        profile_id: profile_id.map(str::to_string),
+    })
 }
 
+/// Adds a record of `submitter` to the leaderboard `namespace` and to its boards of
+/// the periods the record's date falls in. Each board keeps the best record of a
+/// player, and a full board drops its worst record if the new one is better.
+/// Every submission is kept for ranks, and for the profile of `submitter` if it has one.
+///
+/// The result is the one of the all-time board.
 pub async fn add_performance_record(
     namespace: &str,
+    submitter: &Submitter,
     performance_record: PerformanceRecord,
-    repository: Arc<Mutex<dyn Storage>>,
+    config: &LeaderboardConfig,
+    repository: Arc<dyn Storage>,
 ) -> Result<AddPerformanceRecordResult, RepositoryError> {
-    let mut storage = repository.lock().await;
+    repository
+        .record_submission(
+            namespace,
+            &submitter.player,
+            submitter.profile_id.as_deref(),
+            performance_record.clone(),
+        )
+        .await?;
+    let player = submitter.player.as_str();
+
+    let capacity = config.capacity(namespace);
+    let day = config.local_day(performance_record.date);
+    for period in [
+        LeaderboardPeriod::Daily,
+        LeaderboardPeriod::Weekly,
+        LeaderboardPeriod::Monthly,
+    ] {
+        match repository
+            .add_performance_record(
+                &period.namespace(namespace, day),
+                player,
+                performance_record.clone(),
+                capacity,
+            )
+            .await
+        {
+            Ok(_) | Err(RepositoryError::LimitReached(_) | RepositoryError::AlreadyExists(_)) => {}
+            Err(e) => return Err(e),
+        }
+    }
 
-    // First try to add directly
-    match storage
-        .add_performance_record(namespace, performance_record.clone())
+    match repository
+        .add_performance_record(namespace, player, performance_record, capacity)
         .await
     {
-        Ok(record) => return Ok(AddPerformanceRecordResult::Success(record)),
-        Err(RepositoryError::LimitReached(_)) => {
-            // If limit reached, get all records to compare
-            let mut leaderboard = storage.fetch_performance_records(namespace).await?;
-            leaderboard.sort();
-
-            // If there are records and the new record is better than the worst one
-            if let Some(worst_record) = leaderboard.last() {
-                if worst_record > &performance_record {
-                    // Remove the worst record
-                    storage
-                        .remove_performance_record(namespace, worst_record.clone())
-                        .await?;
-
-                    // Try adding the new record again
-                    storage
-                        .add_performance_record(namespace, performance_record.clone())
-                        .await?;
-
-                    return Ok(AddPerformanceRecordResult::Success(performance_record));
-                }
-            }
-            Ok(AddPerformanceRecordResult::LimitReached)
-        }
+        Ok(record) => Ok(AddPerformanceRecordResult::Success(record)),
+        Err(RepositoryError::LimitReached(_)) => Ok(AddPerformanceRecordResult::LimitReached),
+        Err(RepositoryError::AlreadyExists(_)) => Ok(AddPerformanceRecordResult::NotImproved),
         Err(e) => Err(e),
     }
 }
 
-pub async fn fetch_all_performance_records(
+/// A page of a leaderboard, best records first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderboardPage {
+    pub entries: Vec<LeaderboardEntry>,
+    /// Records on the whole leaderboard.
+    pub total: usize,
+}
+
+/// Up to `limit` records of the leaderboard `namespace` after the best `offset`,
+/// all of them without a limit. Each entry carries its rank on the whole leaderboard.
+pub async fn fetch_leaderboard_page(
     namespace: &str,
-    repository: Arc<Mutex<dyn Storage>>,
-) -> Result<Vec<PerformanceRecord>, RepositoryError> {
-    let performance_records = repository
-        .lock()
-        .await
-        .fetch_performance_records(namespace)
-        .await?;
-    Ok(performance_records)
+    offset: usize,
+    limit: Option<usize>,
+    repository: Arc<dyn Storage>,
+) -> Result<LeaderboardPage, RepositoryError> {
+    let mut performance_records = repository.fetch_performance_records(namespace).await?;
+    performance_records.sort();
+    let total = performance_records.len();
+    let entries = performance_records
+        .into_iter()
+        .enumerate()
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .map(|(index, record)| LeaderboardEntry {
+            rank: index + 1,
+            record,
+        })
+        .collect();
+    Ok(LeaderboardPage { entries, total })
+}
+
+/// Where a player stands among everybody who submitted to a leaderboard.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PlayerRank {
+    /// Best record of the player
+    pub record: PerformanceRecord,
+    /// Position among all participants, starting at 1, shared by equal records
+    #[schema(example = 42)]
+    pub rank: usize,
+    /// Players who submitted a record
+    #[schema(example = 180)]
+    pub total: usize,
+    /// Percentage of participants the player ranks at or above
+    #[schema(example = 77.2)]
+    pub percentile: f64,
+}
+
+/// Rank of a player's best record among the best records of every player who
+/// submitted to `namespace`, including records kept on the board from before
+/// submissions were recorded. Players are looked up under the configured key,
+/// a profile id stands for the name of its profile if players are known by name.
+pub async fn fetch_player_rank(
+    namespace: &str,
+    query: PlayerRankQuery,
+    config: &LeaderboardConfig,
+    repository: Arc<dyn Storage>,
+) -> Result<PlayerRank, PlayerRankError> {
+    let player = match (config.player_key, query.profile_name, query.profile_id) {
+        (PlayerKey::ProfileName, Some(profile_name), _) => profile_name,
+        (player_key, _, Some(profile_id)) => {
+            let profile = match ProfileRepository::fetch(&*repository, profile_id.clone()).await {
+                Ok(profile) => profile,
+                Err(RepositoryError::NotFound(_)) => {
+                    return Err(PlayerRankError::UnknownPlayer(profile_id))
+                }
+                Err(err) => return Err(err.into()),
+            };
+            match player_key {
+                PlayerKey::ProfileName => profile.name,
+                PlayerKey::ProfileId => profile_id,
+            }
+        }
+        (PlayerKey::ProfileId, Some(_), None) => return Err(PlayerRankError::MissingProfileId),
+        (_, None, None) => return Err(PlayerRankError::MissingPlayer),
+    };
+
+    let RankedRecord {
+        record,
+        rank,
+        total,
+    } = repository
+        .fetch_ranked_record(namespace, &player)
+        .await?
+        .ok_or_else(|| PlayerRankError::NotRanked(player, namespace.to_string()))?;
+    let percentile = ((total - rank + 1) as f64 * 1000.0 / total as f64).round() / 10.0;
+    Ok(PlayerRank {
+        record,
+        rank,
+        total,
+        percentile,
+    })
+}
+
+pub(crate) fn player_rank_error_response(err: PlayerRankError) -> (StatusCode, String) {
+    let status = match &err {
+        PlayerRankError::MissingPlayer | PlayerRankError::MissingProfileId => {
+            StatusCode::BAD_REQUEST
+        }
+        PlayerRankError::UnknownPlayer(_) | PlayerRankError::NotRanked(..) => StatusCode::NOT_FOUND,
+        PlayerRankError::Repository(_) => {
+            log::error!("Player rank failed: {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, err.to_string())
+}
+
+pub(crate) fn submission_error_response(err: SubmissionError) -> (StatusCode, String) {
+    let status = match &err {
+        SubmissionError::MissingProfile(_)
+        | SubmissionError::Profile(ProfileError::MissingToken(_)) => StatusCode::UNAUTHORIZED,
+        SubmissionError::Profile(ProfileError::InvalidToken(_)) => StatusCode::FORBIDDEN,
+        SubmissionError::Profile(ProfileError::Unowned(_)) => StatusCode::CONFLICT,
+        SubmissionError::Profile(ProfileError::Repository(RepositoryError::NotFound(_))) => {
+            StatusCode::NOT_FOUND
+        }
+        SubmissionError::Profile(ProfileError::Repository(_)) | SubmissionError::Repository(_) => {
+            log::error!("Performance record failed: {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, err.to_string())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::services::v1::profile::save_profile;
     use crate::storage::{LeaderboardRepository, MemoryRepository};
     use chrono::DateTime;
+    use konnektoren_core::prelude::PlayerProfile;
+
+    /// A submitter without a profile.
+    fn named(player: &str) -> Submitter {
+        Submitter {
+            player: player.to_string(),
+            profile_id: None,
+        }
+    }
 
     #[test]
     fn test_sort_performance() {
@@ -159,13 +506,14 @@ mod tests {
     #[tokio::test]
     async fn test_add_limit_reached() {
         let storage = MemoryRepository::new();
-        let repository = Arc::new(Mutex::new(storage));
+        let repository = Arc::new(storage);
 
         let namespace = "test";
+        let config = LeaderboardConfig::default();
         let mut records = vec![];
 
         // Fill up the leaderboard
-        for i in 0..PERFORMANCE_RECORDS_LIMIT {
+        for i in 0..DEFAULT_CAPACITY {
             let record = PerformanceRecord {
                 profile_name: i.to_string(),
                 challenges_performance: vec![("".to_string(), 100, 200)],
@@ -176,11 +524,8 @@ mod tests {
             };
             records.push(record.clone());
 
-            // Use repository.lock() to access storage methods
             repository
-                .lock()
-                .await
-                .add_performance_record(namespace, record)
+                .add_performance_record(namespace, &i.to_string(), record, DEFAULT_CAPACITY)
                 .await
                 .unwrap();
         }
@@ -194,7 +539,14 @@ mod tests {
             ..Default::default()
         };
 
-        let result = add_performance_record(namespace, new_record, repository.clone()).await;
+        let result = add_performance_record(
+            namespace,
+            &named("new"),
+            new_record,
+            &config,
+            repository.clone(),
+        )
+        .await;
         assert!(result.is_ok());
 
         let new_worse_record = PerformanceRecord {
@@ -206,7 +558,14 @@ mod tests {
             ..Default::default()
         };
 
-        let result = add_performance_record(namespace, new_worse_record, repository.clone()).await;
+        let result = add_performance_record(
+            namespace,
+            &named("new_worse"),
+            new_worse_record,
+            &config,
+            repository.clone(),
+        )
+        .await;
         assert!(matches!(
             result,
             Ok(AddPerformanceRecordResult::LimitReached)
@@ -221,7 +580,430 @@ mod tests {
             ..Default::default()
         };
 
-        let result = add_performance_record(namespace, new_best_record, repository).await;
+        let result = add_performance_record(
+            namespace,
+            &named("new_best"),
+            new_best_record,
+            &config,
+            repository,
+        )
+        .await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_capacity_per_namespace() {
+        let config = LeaderboardConfig::from_vars(|key| match key {
+            CAPACITY_VAR => Some("3".to_string()),
+            NAMESPACE_CAPACITIES_VAR => Some("leaderboard=1000, small=1".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(config.capacity(LEADERBOARD_NAMESPACE), 1000);
+        assert_eq!(config.capacity("challenge"), 3);
+
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        for i in 0..2u8 {
+            let record = PerformanceRecord {
+                profile_name: i.to_string(),
+                performance_percentage: i,
+                ..Default::default()
+            };
+            add_performance_record(
+                "small",
+                &named(&i.to_string()),
+                record,
+                &config,
+                repository.clone(),
+            )
+            .await
+            .unwrap();
+        }
+        let records = repository.fetch_performance_records("small").await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].profile_name, "1");
+
+        let result = LeaderboardConfig::from_vars(|key| {
+            (key == NAMESPACE_CAPACITIES_VAR).then(|| "leaderboard".to_string())
+        });
+        assert_eq!(
+            result,
+            Err(ConfigError::InvalidSetting(
+                NAMESPACE_CAPACITIES_VAR,
+                "leaderboard".to_string()
+            ))
+        );
+        let result = LeaderboardConfig::from_vars(|key| {
+            (key == NAMESPACE_CAPACITIES_VAR).then(|| "leaderboard=1000, small=0".to_string())
+        });
+        assert_eq!(
+            result,
+            Err(ConfigError::InvalidSetting(
+                NAMESPACE_CAPACITIES_VAR,
+                "small=0".to_string()
+            ))
+        );
+        let result =
+            LeaderboardConfig::from_vars(|key| (key == CAPACITY_VAR).then(|| "0".to_string()));
+        assert_eq!(
+            result,
+            Err(ConfigError::InvalidSetting(CAPACITY_VAR, "0".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_leaderboard_page() {
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        for percentage in [50, 90, 70, 80, 60] {
+            let record = PerformanceRecord {
+                profile_name: percentage.to_string(),
+                performance_percentage: percentage,
+                ..Default::default()
+            };
+            repository
+                .add_performance_record("test", &percentage.to_string(), record, DEFAULT_CAPACITY)
+                .await
+                .unwrap();
+        }
+
+        let page = fetch_leaderboard_page("test", 1, Some(2), repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(page.total, 5);
+        let ranks: Vec<(usize, &str)> = page
+            .entries
+            .iter()
+            .map(|entry| (entry.rank, entry.record.profile_name.as_str()))
+            .collect();
+        assert_eq!(ranks, vec![(2, "80"), (3, "70")]);
+
+        let page = fetch_leaderboard_page("test", 0, None, repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(page.entries.len(), 5);
+        assert_eq!(page.entries[0].record.profile_name, "90");
+
+        let page = fetch_leaderboard_page("test", 10, Some(2), repository)
+            .await
+            .unwrap();
+        assert!(page.entries.is_empty());
+        assert_eq!(page.total, 5);
+    }
+
+    #[tokio::test]
+    async fn test_player_rank_counts_every_submission() {
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let config = LeaderboardConfig {
+            capacity: 1,
+            ..Default::default()
+        };
+        for (name, percentage) in [("alice", 60), ("bob", 90), ("carol", 40), ("alice", 80)] {
+            let record = PerformanceRecord {
+                profile_name: name.to_string(),
+                performance_percentage: percentage,
+                ..Default::default()
+            };
+            add_performance_record("test", &named(name), record, &config, repository.clone())
+                .await
+                .unwrap();
+        }
+        let query = |profile_name: &str| PlayerRankQuery {
+            profile_name: Some(profile_name.to_string()),
+            profile_id: None,
+        };
+
+        // Only bob is on the board, alice is ranked by her best submission
+        let rank = fetch_player_rank("test", query("alice"), &config, repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(rank.record.performance_percentage, 80);
+        assert_eq!((rank.rank, rank.total), (2, 3));
+        assert_eq!(rank.percentile, 66.7);
+
+        let rank = fetch_player_rank("test", query("carol"), &config, repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(rank.rank, 3);
+
+        let result = fetch_player_rank("test", query("dave"), &config, repository.clone()).await;
+        assert!(matches!(result, Err(PlayerRankError::NotRanked(..))));
+        let result =
+            fetch_player_rank("test", PlayerRankQuery::default(), &config, repository).await;
+        assert!(matches!(result, Err(PlayerRankError::MissingPlayer)));
+    }
+
+    #[tokio::test]
+    async fn test_player_rank_by_profile_id() {
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let config = LeaderboardConfig {
+            player_key: PlayerKey::ProfileId,
+            ..Default::default()
+        };
+        for (profile_id, percentage) in [("first", 90), ("second", 60)] {
+            let mut profile = PlayerProfile::new(profile_id.to_string());
+            profile.name = "Alex".to_string();
+            ProfileRepository::save(&*repository, profile)
+                .await
+                .unwrap();
+            let submitter = Submitter {
+                player: profile_id.to_string(),
+                profile_id: Some(profile_id.to_string()),
+            };
+            let record = PerformanceRecord {
+                profile_name: "Alex".to_string(),
+                performance_percentage: percentage,
+                ..Default::default()
+            };
+            add_performance_record("test", &submitter, record, &config, repository.clone())
+                .await
+                .unwrap();
+        }
+        let query = |profile_id: &str| PlayerRankQuery {
+            profile_name: None,
+            profile_id: Some(profile_id.to_string()),
+        };
+
+        // Both profiles go by the same name, each is ranked by its own records
+        let rank = fetch_player_rank("test", query("second"), &config, repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(rank.record.performance_percentage, 60);
+        assert_eq!((rank.rank, rank.total), (2, 2));
+        let rank = fetch_player_rank("test", query("first"), &config, repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(rank.record.performance_percentage, 90);
+        assert_eq!(rank.rank, 1);
+
+        let by_name = PlayerRankQuery {
+            profile_name: Some("Alex".to_string()),
+            profile_id: None,
+        };
+        let result = fetch_player_rank("test", by_name, &config, repository.clone()).await;
+        assert!(matches!(result, Err(PlayerRankError::MissingProfileId)));
+        let result = fetch_player_rank("test", query("third"), &config, repository).await;
+        assert!(matches!(result, Err(PlayerRankError::UnknownPlayer(_))));
+    }
+
+    #[tokio::test]
+    async fn test_submissions_feed_period_boards() {
+        let config = LeaderboardConfig::from_vars(|key| {
+            (key == UTC_OFFSET_VAR).then(|| "+02:00".to_string())
+        })
+        .unwrap();
+        // Late on Sunday in UTC is already Monday two hours east
+        let date = DateTime::parse_from_rfc3339("2024-03-31T23:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let day = config.local_day(date);
+        assert_eq!(day, NaiveDate::from_ymd_opt(2024, 4, 1).unwrap());
+        assert_eq!(
+            LeaderboardPeriod::Weekly.namespace("test", day),
+            "test:weekly:2024-W14"
+        );
+
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let record = PerformanceRecord {
+            profile_name: "alice".to_string(),
+            date,
+            ..Default::default()
+        };
+        add_performance_record(
+            "test",
+            &named("alice"),
+            record.clone(),
+            &config,
+            repository.clone(),
+        )
+        .await
+        .unwrap();
+        for namespace in [
+            "test",
+            "test:daily:2024-04-01",
+            "test:weekly:2024-W14",
+            "test:monthly:2024-04",
+        ] {
+            let records = repository
+                .fetch_performance_records(namespace)
+                .await
+                .unwrap();
+            assert_eq!(records, vec![record.clone()], "{}", namespace);
+        }
+
+        let result = LeaderboardConfig::from_vars(|key| {
+            (key == UTC_OFFSET_VAR).then(|| "Europe/Berlin".to_string())
+        });
+        assert!(matches!(
+            result,
+            Err(ConfigError::InvalidSetting(UTC_OFFSET_VAR, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_boards_keep_the_best_record_of_a_player() {
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let config = LeaderboardConfig::default();
+        let record = |percentage: u8| PerformanceRecord {
+            profile_name: "alice".to_string(),
+            performance_percentage: percentage,
+            date: DateTime::from(DateTime::parse_from_rfc3339("2024-04-01T12:00:00Z").unwrap()),
+            ..Default::default()
+        };
+
+        for (percentage, improved) in [(50, true), (40, false), (80, true), (80, false)] {
+            let result = add_performance_record(
+                "test",
+                &named("alice"),
+                record(percentage),
+                &config,
+                repository.clone(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(
+                !matches!(result, AddPerformanceRecordResult::NotImproved),
+                improved,
+                "{}",
+                percentage
+            );
+        }
+        for namespace in ["test", "test:daily:2024-04-01", "test:monthly:2024-04"] {
+            let records = repository
+                .fetch_performance_records(namespace)
+                .await
+                .unwrap();
+            assert_eq!(records, vec![record(80)], "{}", namespace);
+        }
+        // Every submission still counts for ranks
+        assert_eq!(repository.fetch_submissions("test").await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_players_keyed_by_authorized_profile_id() {
+        let config = LeaderboardConfig::from_vars(|key| {
+            (key == PLAYER_KEY_VAR).then(|| "profile_id".to_string())
+        })
+        .unwrap();
+        assert_eq!(config.player_key, PlayerKey::ProfileId);
+
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let profile = PlayerProfile::new("alice-id".to_string());
+        let token = save_profile(profile, None, repository.clone())
+            .await
+            .unwrap()
+            .token
+            .unwrap();
+        let record = PerformanceRecord {
+            profile_name: "Alice".to_string(),
+            ..Default::default()
+        };
+
+        let submitter = submission_player(
+            &record,
+            Some("alice-id"),
+            Some(&token),
+            &config,
+            repository.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            submitter,
+            Submitter {
+                player: "alice-id".to_string(),
+                profile_id: Some("alice-id".to_string()),
+            }
+        );
+
+        let result =
+            submission_player(&record, None, Some(&token), &config, repository.clone()).await;
+        assert!(matches!(result, Err(SubmissionError::MissingProfile(_))));
+        let result = submission_player(
+            &record,
+            Some("alice-id"),
+            Some("wrong"),
+            &config,
+            repository.clone(),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(SubmissionError::Profile(ProfileError::InvalidToken(_)))
+        ));
+
+        let result = LeaderboardConfig::from_vars(|key| {
+            (key == PLAYER_KEY_VAR).then(|| "wallet".to_string())
+        });
+        assert_eq!(
+            result,
+            Err(ConfigError::InvalidSetting(
+                PLAYER_KEY_VAR,
+                "wallet".to_string()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_submissions_are_kept_for_the_authorized_profile() {
+        let config = LeaderboardConfig::default();
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let token = save_profile(
+            PlayerProfile::new("alice-id".to_string()),
+            None,
+            repository.clone(),
+        )
+        .await
+        .unwrap()
+        .token
+        .unwrap();
+        let record = PerformanceRecord {
+            profile_name: "Alice".to_string(),
+            ..Default::default()
+        };
+
+        let result =
+            submission_player(&record, Some("alice-id"), None, &config, repository.clone()).await;
+        assert!(matches!(
+            result,
+            Err(SubmissionError::Profile(ProfileError::MissingToken(_)))
+        ));
+
+        let submitter = submission_player(
+            &record,
+            Some("alice-id"),
+            Some(&token),
+            &config,
+            repository.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(submitter.player, "Alice");
+        add_performance_record(
+            "test",
+            &submitter,
+            record.clone(),
+            &config,
+            repository.clone(),
+        )
+        .await
+        .unwrap();
+        add_performance_record(
+            "test",
+            &named("Alice"),
+            record.clone(),
+            &config,
+            repository.clone(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            repository
+                .fetch_profile_submissions("alice-id")
+                .await
+                .unwrap(),
+            vec![record]
+        );
+        assert_eq!(repository.fetch_submissions("test").await.unwrap().len(), 2);
+    }
 }
@@ -3,3 +3,5 @@ pub mod coupon;
 pub mod leaderboard;
 pub mod profile;
 pub mod review;
+pub mod ton_proof;
+pub mod wallet;
@@ -1,15 +1,36 @@
-use crate::storage::{ProfileRepository, Storage};
+use crate::middleware::auth::constant_time_eq;
+use crate::storage::{ProfileCredentialRepository, ProfileRepository, RepositoryError, Storage};
 use anyhow::Error;
+use chrono::Utc;
 use konnektoren_core::prelude::PlayerProfile;
+use sha2::{Digest, Sha256};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ProfileError {
+    #[error("Repository error: {0}")]
+    Repository(#[from] RepositoryError),
+    #[error("Profile {0} is owned by another player, send its token")]
+    MissingToken(String),
+    #[error("Invalid token for profile {0}")]
+    InvalidToken(String),
+    #[error("Profile {0} was created before owner tokens, an admin has to issue its token")]
+    Unowned(String),
+}
+
+/// A saved profile, with the owner token when the save registered the profile.
+#[derive(Debug)]
+pub struct SavedProfile {
+    pub profile: PlayerProfile,
+    pub token: Option<String>,
+}
 
 pub async fn fetch_profile(
     profile_id: String,
-    repository: Arc<Mutex<dyn Storage>>,
+    repository: Arc<dyn Storage>,
 ) -> Result<PlayerProfile, Error> {
-    let mut storage = repository.lock().await;
-    let profile = ProfileRepository::fetch(&*storage, profile_id)
+    let profile = ProfileRepository::fetch(&*repository, profile_id)
         .await
         .map_err(|err| {
             log::error!("Error fetching profile: {:?}", err);
@@ -19,11 +40,8 @@ pub async fn fetch_profile(
     Ok(profile)
 }
 
-pub async fn fetch_all_profiles(
-    repository: Arc<Mutex<dyn Storage>>,
-) -> Result<Vec<PlayerProfile>, Error> {
-    let mut storage = repository.lock().await;
-    let profiles = ProfileRepository::fetch_all(&*storage)
+pub async fn fetch_all_profiles(repository: Arc<dyn Storage>) -> Result<Vec<PlayerProfile>, Error> {
+    let profiles = ProfileRepository::fetch_all(&*repository)
         .await
         .map_err(|err| {
             log::error!("Error fetching profiles: {:?}", err);
@@ -33,20 +51,125 @@ pub async fn fetch_all_profiles(
     Ok(profiles)
 }
 
+/// Saves a profile if `token` proves ownership of it.
+///
+/// The first save of a profile id registers it and returns a new owner token,
+/// later saves must present that token. Profiles stored before owner tokens
+/// existed are rejected until an admin issues their token with [`issue_owner_token`].
 pub async fn save_profile(
     profile: PlayerProfile,
-    repository: Arc<Mutex<dyn Storage>>,
-) -> Result<PlayerProfile, Error> {
-    let mut storage = repository.lock().await;
+    token: Option<&str>,
+    repository: Arc<dyn Storage>,
+) -> Result<SavedProfile, ProfileError> {
     log::info!("Received profile: {:?}", profile);
-    let saved_profile = ProfileRepository::save(&mut *storage, profile)
+    let token = match repository.fetch_credential(&profile.id).await? {
+        Some(secret_hash) => {
+            verify_token(&profile.id, token, &secret_hash, &*repository).await?;
+            None
+        }
+        None => {
+            match ProfileRepository::fetch(&*repository, profile.id.clone()).await {
+                Ok(_) => return Err(ProfileError::Unowned(profile.id)),
+                Err(RepositoryError::NotFound(_)) => {}
+                Err(err) => return Err(err.into()),
+            }
+            Some(register_owner(&profile.id, &*repository).await?)
+        }
+    };
+
+    let saved_profile = ProfileRepository::save(&*repository, profile)
         .await
         .map_err(|err| {
             log::error!("Error saving profile: {:?}", err);
             err
         })?;
     log::info!("Saved profile: {:?}", saved_profile);
-    Ok(saved_profile)
+    Ok(SavedProfile {
+        profile: saved_profile,
+        token,
+    })
+}
+
+/// Issues the owner token of a stored profile that has none yet, e.g. one
+/// saved before owner tokens existed. Fails with
+/// [`RepositoryError::AlreadyExists`] if the profile already has an owner.
+pub async fn issue_owner_token(
+    profile_id: &str,
+    repository: Arc<dyn Storage>,
+) -> Result<String, ProfileError> {
+    ProfileRepository::fetch(&*repository, profile_id.to_string()).await?;
+    let token = generate_token();
+    repository
+        .create_credential(profile_id, &hash_token(&token))
+        .await?;
+    log::info!("Issued owner token for profile {}", profile_id);
+    Ok(token)
+}
+
+/// Registers a new profile and returns its owner token.
+async fn register_owner(
+    profile_id: &str,
+    repository: &dyn Storage,
+) -> Result<String, ProfileError> {
+    let token = generate_token();
+    match repository
+        .create_credential(profile_id, &hash_token(&token))
+        .await
+    {
+        Ok(()) => Ok(token),
+        // Another request registered the profile first
+        Err(RepositoryError::AlreadyExists(_)) => {
+            Err(ProfileError::MissingToken(profile_id.to_string()))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Checks that `token` is the owner token of a registered profile.
+pub async fn authorize_profile(
+    profile_id: &str,
+    token: Option<&str>,
+    repository: Arc<dyn Storage>,
+) -> Result<(), ProfileError> {
+    match repository.fetch_credential(profile_id).await? {
+        Some(secret_hash) => verify_token(profile_id, token, &secret_hash, &*repository).await,
+        None => Err(ProfileError::Repository(RepositoryError::NotFound(
+            profile_id.to_string(),
+        ))),
+    }
+}
+
+/// Accepts the owner token or an unexpired session token of the profile.
+async fn verify_token(
+    profile_id: &str,
+    token: Option<&str>,
+    secret_hash: &str,
+    repository: &dyn Storage,
+) -> Result<(), ProfileError> {
+    let token = token.ok_or_else(|| ProfileError::MissingToken(profile_id.to_string()))?;
+    let token_hash = hash_token(token);
+    if constant_time_eq(token_hash.as_bytes(), secret_hash.as_bytes()) {
+        return Ok(());
+    }
+    match repository.fetch_session(&token_hash).await? {
+        Some(session) if session.profile_id == profile_id && session.expires_at > Utc::now() => {
+            Ok(())
+        }
+        _ => Err(ProfileError::InvalidToken(profile_id.to_string())),
+    }
+}
+
+pub(crate) fn generate_token() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// Only the hash of a token is stored, so a leaked database does not expose tokens.
+pub(crate) fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
 }
 
 #[cfg(test)]
@@ -57,25 +180,22 @@ mod tests {
 
     #[tokio::test]
     async fn test_fetch_profile() {
-        let mut repository = MemoryRepository::new();
+        let repository = MemoryRepository::new();
         let profile = PlayerProfile::new("example_user_id".to_string());
 
         // Save the profile first
         repository.save(profile.clone()).await.unwrap();
 
-        let fetched_profile = fetch_profile(
-            "example_user_id".to_string(),
-            Arc::new(Mutex::new(repository)),
-        )
-        .await
-        .unwrap();
+        let fetched_profile = fetch_profile("example_user_id".to_string(), Arc::new(repository))
+            .await
+            .unwrap();
 
         assert_eq!(fetched_profile.id, "example_user_id");
     }
 
     #[tokio::test]
     async fn test_fetch_all_profiles() {
-        let mut repository = MemoryRepository::new();
+        let repository = MemoryRepository::new();
 
         // Save multiple profiles
         repository
@@ -87,9 +207,7 @@ mod tests {
             .await
             .unwrap();
 
-        let profiles = fetch_all_profiles(Arc::new(Mutex::new(repository)))
-            .await
-            .unwrap();
+        let profiles = fetch_all_profiles(Arc::new(repository)).await.unwrap();
 
         assert_eq!(profiles.len(), 2);
         assert!(profiles.iter().any(|p| p.id == "user1"));
@@ -101,22 +219,112 @@ mod tests {
         let repository = MemoryRepository::new();
         let profile = PlayerProfile::new("example_user_id".to_string());
 
-        let saved_profile = save_profile(profile.clone(), Arc::new(Mutex::new(repository)))
+        let saved_profile = save_profile(profile.clone(), None, Arc::new(repository))
             .await
             .unwrap();
 
-        assert_eq!(saved_profile.id, "example_user_id");
+        assert_eq!(saved_profile.profile.id, "example_user_id");
+        assert!(saved_profile.token.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_only_owner_can_save_profile() {
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let mut profile = PlayerProfile::new("example_user_id".to_string());
+
+        let token = save_profile(profile.clone(), None, repository.clone())
+            .await
+            .unwrap()
+            .token
+            .unwrap();
+
+        profile.name = "hijacked".to_string();
+        let result = save_profile(profile.clone(), None, repository.clone()).await;
+        assert!(matches!(result, Err(ProfileError::MissingToken(_))));
+        let result = save_profile(profile.clone(), Some("wrong"), repository.clone()).await;
+        assert!(matches!(result, Err(ProfileError::InvalidToken(_))));
+        let stored = fetch_profile(profile.id.clone(), repository.clone())
+            .await
+            .unwrap();
+        assert_ne!(stored.name, "hijacked");
+
+        profile.name = "renamed".to_string();
+        let saved = save_profile(profile.clone(), Some(&token), repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(saved.token, None);
+        assert_eq!(saved.profile.name, "renamed");
+
+        assert!(
+            authorize_profile(&profile.id, Some(&token), repository.clone())
+                .await
+                .is_ok()
+        );
+        assert!(authorize_profile("unknown", Some(&token), repository)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_profiles_without_owner_need_an_issued_token() {
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let mut profile = PlayerProfile::new("legacy_user_id".to_string());
+        // Stored before owner tokens existed
+        ProfileRepository::save(&*repository, profile.clone())
+            .await
+            .unwrap();
+
+        profile.name = "hijacked".to_string();
+        let result = save_profile(profile.clone(), None, repository.clone()).await;
+        assert!(matches!(result, Err(ProfileError::Unowned(_))));
+        let result = save_profile(profile.clone(), Some("guess"), repository.clone()).await;
+        assert!(matches!(result, Err(ProfileError::Unowned(_))));
+        let stored = fetch_profile(profile.id.clone(), repository.clone())
+            .await
+            .unwrap();
+        assert_ne!(stored.name, "hijacked");
+
+        let token = issue_owner_token(&profile.id, repository.clone())
+            .await
+            .unwrap();
+        let result = issue_owner_token(&profile.id, repository.clone()).await;
+        assert!(matches!(
+            result,
+            Err(ProfileError::Repository(RepositoryError::AlreadyExists(_)))
+        ));
+        let result = issue_owner_token("unknown", repository.clone()).await;
+        assert!(matches!(
+            result,
+            Err(ProfileError::Repository(RepositoryError::NotFound(_)))
+        ));
+
+        profile.name = "renamed".to_string();
+        let saved = save_profile(profile, Some(&token), repository)
+            .await
+            .unwrap();
+        assert_eq!(saved.profile.name, "renamed");
+    }
+
+    #[tokio::test]
+    async fn test_stored_secret_is_not_the_token() {
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let profile = PlayerProfile::new("example_user_id".to_string());
+
+        let token = save_profile(profile.clone(), None, repository.clone())
+            .await
+            .unwrap()
+            .token
+            .unwrap();
+        let stored = repository.fetch_credential(&profile.id).await.unwrap();
+        assert_eq!(stored, Some(hash_token(&token)));
+        assert_ne!(stored, Some(token));
     }
 
     #[tokio::test]
     async fn test_fetch_profile_error() {
         let repository = MemoryRepository::new();
 
-        let result = fetch_profile(
-            "nonexistent_id".to_string(),
-            Arc::new(Mutex::new(repository)),
-        )
-        .await;
+        let result = fetch_profile("nonexistent_id".to_string(), Arc::new(repository)).await;
 
         assert!(result.is_err());
     }
@@ -2,15 +2,12 @@ use crate::storage::Storage;
 use anyhow::Error;
 use konnektoren_core::challenges::Review;
 use std::sync::Arc;
-use tokio::sync::Mutex;
 
 pub async fn fetch_reviews(
     challenge_id: String,
-    repository: Arc<Mutex<dyn Storage>>,
+    repository: Arc<dyn Storage>,
 ) -> Result<Vec<Review>, Error> {
     let reviews = repository
-        .lock()
-        .await
         .fetch_reviews(&challenge_id)
         .await
         .map_err(|err| {
@@ -29,27 +26,20 @@ pub async fn fetch_reviews(
     Ok(reviews)
 }
 
-pub async fn fetch_all_reviews(repository: Arc<Mutex<dyn Storage>>) -> Result<Vec<Review>, Error> {
-    let reviews = repository
-        .lock()
-        .await
-        .fetch_all_reviews()
-        .await
-        .map_err(|err| {
-            log::error!("Error fetching all reviews: {:?}", err);
-            err
-        })?;
+pub async fn fetch_all_reviews(repository: Arc<dyn Storage>) -> Result<Vec<Review>, Error> {
+    let reviews = repository.fetch_all_reviews().await.map_err(|err| {
+        log::error!("Error fetching all reviews: {:?}", err);
+        err
+    })?;
     log::debug!("Returning all reviews: {:?}", reviews);
     Ok(reviews)
 }
 
 pub async fn fetch_average_rating(
     challenge_id: String,
-    repository: Arc<Mutex<dyn Storage>>,
+    repository: Arc<dyn Storage>,
 ) -> Result<f64, Error> {
     let average_rating = repository
-        .lock()
-        .await
         .fetch_average_rating(&challenge_id)
         .await
         .map_err(|err| {
@@ -69,20 +59,12 @@ pub async fn fetch_average_rating(
     Ok(average_rating)
 }
 
-pub async fn store_review(
-    review: Review,
-    repository: Arc<Mutex<dyn Storage>>,
-) -> Result<(), Error> {
+pub async fn store_review(review: Review, repository: Arc<dyn Storage>) -> Result<(), Error> {
     log::debug!("Received review to store: {:?}", review);
-    let result = repository
-        .lock()
-        .await
-        .store_review(review)
-        .await
-        .map_err(|err| {
-            log::error!("Error storing review: {:?}", err);
-            err
-        })?;
+    let result = repository.store_review(review).await.map_err(|err| {
+        log::error!("Error storing review: {:?}", err);
+        err
+    })?;
     log::debug!("Stored review successfully");
     Ok(result)
 }
@@ -95,7 +77,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_store_review() {
-        let repository = Arc::new(Mutex::new(MemoryRepository::new()));
+        let repository = Arc::new(MemoryRepository::new());
         let review = Review {
             challenge_id: "example_challenge_id".to_string(),
             rating: 5,
@@ -116,7 +98,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_fetch_average_rating() {
-        let repository = Arc::new(Mutex::new(MemoryRepository::new()));
+        let repository = Arc::new(MemoryRepository::new());
         let review1 = Review {
             challenge_id: "example_challenge_id".to_string(),
             rating: 5,
@@ -0,0 +1,684 @@
+//! Verification of TON Connect `ton_proof` sign-in requests.
+//!
+//! A wallet signs a server issued payload together with the app domain and
+//! its own address. The proof is checked offline:
+//!
+//! 1. the payload was issued by this server and has not expired,
+//! 2. the domain is allowed and the proof is recent,
+//! 3. the wallet `state_init` hashes to the claimed address,
+//! 4. the public key is the one stored in the wallet data,
+//! 5. the ed25519 signature matches.
+//!
+//! Payloads are not stored when issued, so the sign-in records used ones
+//! until they expire to reject replayed proofs.
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use ed25519_dalek::{Signature, VerifyingKey};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::sync::Arc;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+const TON_PROOF_PREFIX: &[u8] = b"ton-proof-item-v2/";
+const TON_CONNECT_PREFIX: &[u8] = b"ton-connect";
+const BOC_MAGIC: [u8; 4] = [0xb5, 0xee, 0x9c, 0x72];
+const NONCE_LENGTH: usize = 16;
+/// Wallets v3 and v4 store `seqno:u32 wallet_id:u32 public_key:bits256`,
+/// v5 prefixes that with a one bit flag.
+const PUBLIC_KEY_OFFSETS: [usize; 2] = [64, 65];
+const DEFAULT_PAYLOAD_TTL_MINUTES: i64 = 15;
+const DEFAULT_PROOF_TTL_MINUTES: i64 = 15;
+/// Tolerated clock difference between wallet and server.
+const MAX_CLOCK_SKEW_SECONDS: i64 = 60;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum TonProofError {
+    #[error("Malformed proof: {0}")]
+    Malformed(String),
+    #[error("Invalid or expired payload")]
+    InvalidPayload,
+    #[error("Payload was already used")]
+    PayloadUsed,
+    #[error("Domain {0} is not allowed")]
+    DomainNotAllowed(String),
+    #[error("Proof is expired")]
+    Expired,
+    #[error("State init does not match the address")]
+    StateInitMismatch,
+    #[error("Public key does not belong to the wallet")]
+    PublicKeyMismatch,
+    #[error("Invalid signature")]
+    InvalidSignature,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct TonProofDomain {
+    #[serde(rename = "lengthBytes")]
+    #[schema(example = 16)]
+    pub length_bytes: u32,
+    #[schema(example = "konnektoren.help")]
+    pub value: String,
+}
+
+/// The `ton_proof` item returned by a TON Connect wallet.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct TonProof {
+    #[schema(example = 1700000000)]
+    pub timestamp: u64,
+    pub domain: TonProofDomain,
+    /// Base64 ed25519 signature
+    pub signature: String,
+    /// Payload issued by `POST /api/v1/wallet/challenge`
+    pub payload: String,
+    /// Base64 bag of cells with the wallet state init
+    pub state_init: String,
+}
+
+/// A proof that passed [`TonProofConfig::verify`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VerifiedProof {
+    pub address: WalletAddress,
+    /// Until then the signed payload is accepted.
+    pub payload_expires_at: DateTime<Utc>,
+}
+
+/// A wallet address in raw form, `workchain:hash`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalletAddress {
+    pub workchain: i32,
+    pub hash: [u8; 32],
+}
+
+impl WalletAddress {
+    pub fn parse_raw(address: &str) -> Result<Self, TonProofError> {
+        let malformed = || TonProofError::Malformed(format!("Invalid raw address {}", address));
+        let (workchain, hash) = address.split_once(':').ok_or_else(malformed)?;
+        let workchain = workchain.parse().map_err(|_| malformed())?;
+        let hash = hex::decode(hash)
+            .ok()
+            .and_then(|hash| hash.try_into().ok())
+            .ok_or_else(malformed)?;
+        Ok(Self { workchain, hash })
+    }
+}
+
+impl fmt::Display for WalletAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.workchain, hex::encode(self.hash))
+    }
+}
+
+/// Secret and limits for issuing and checking proofs.
+///
+/// Payloads are signed with the secret instead of being stored, so every
+/// instance sharing `TON_PROOF_SECRET` accepts them.
+#[derive(Clone)]
+pub struct TonProofConfig {
+    secret: Arc<Vec<u8>>,
+    domains: Arc<Vec<String>>,
+    payload_ttl: Duration,
+    proof_ttl: Duration,
+}
+
+impl TonProofConfig {
+    pub fn new<I, D>(secret: impl Into<Vec<u8>>, domains: I) -> Self
+    where
+        I: IntoIterator<Item = D>,
+        D: Into<String>,
+    {
+        Self {
+            secret: Arc::new(secret.into()),
+            domains: Arc::new(domains.into_iter().map(Into::into).collect()),
+            payload_ttl: Duration::minutes(DEFAULT_PAYLOAD_TTL_MINUTES),
+            proof_ttl: Duration::minutes(DEFAULT_PROOF_TTL_MINUTES),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    /// Reads `TON_PROOF_SECRET` and the comma separated `TON_PROOF_DOMAINS`.
+    /// Without a secret a random one is used, so payloads do not survive a restart.
+    pub fn from_vars<F>(var: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let secret = var("TON_PROOF_SECRET")
+            .filter(|secret| !secret.is_empty())
+            .map(String::into_bytes)
+            .unwrap_or_else(random_secret);
+        let domains = var("TON_PROOF_DOMAINS").unwrap_or_default();
+        Self::new(
+            secret,
+            domains
+                .split(',')
+                .map(str::trim)
+                .filter(|domain| !domain.is_empty()),
+        )
+    }
+
+    pub fn domains(&self) -> &[String] {
+        &self.domains
+    }
+
+    /// Issues a payload for a wallet to sign, valid until the returned time.
+    pub fn create_payload(&self, now: DateTime<Utc>) -> (String, DateTime<Utc>) {
+        let expires_at = now + self.payload_ttl;
+        let mut data = uuid::Uuid::new_v4().as_bytes().to_vec();
+        data.extend_from_slice(&expires_at.timestamp().to_be_bytes());
+        let signature = self.mac(&data).finalize().into_bytes();
+        data.extend_from_slice(&signature);
+        (hex::encode(data), expires_at)
+    }
+
+    /// Checks that this server issued `payload` and returns when it expires.
+    pub fn verify_payload(
+        &self,
+        payload: &str,
+        now: DateTime<Utc>,
+    ) -> Result<DateTime<Utc>, TonProofError> {
+        let data = hex::decode(payload).map_err(|_| TonProofError::InvalidPayload)?;
+        if data.len() != NONCE_LENGTH + 8 + 32 {
+            return Err(TonProofError::InvalidPayload);
+        }
+        let (data, signature) = data.split_at(NONCE_LENGTH + 8);
+        self.mac(data)
+            .verify_slice(signature)
+            .map_err(|_| TonProofError::InvalidPayload)?;
+
+        let expires_at = i64::from_be_bytes(data[NONCE_LENGTH..].try_into().unwrap());
+        if now.timestamp() > expires_at {
+            return Err(TonProofError::InvalidPayload);
+        }
+        DateTime::from_timestamp(expires_at, 0).ok_or(TonProofError::InvalidPayload)
+    }
+
+    /// Checks a proof and returns the verified wallet address. It does not
+    /// know whether the payload was used before, see [`VerifiedProof`].
+    pub fn verify(
+        &self,
+        address: &str,
+        public_key: &str,
+        proof: &TonProof,
+        now: DateTime<Utc>,
+    ) -> Result<VerifiedProof, TonProofError> {
+        let domain = &proof.domain.value;
+        if !self.domains.iter().any(|allowed| allowed == domain)
+            || proof.domain.length_bytes as usize != domain.len()
+        {
+            return Err(TonProofError::DomainNotAllowed(domain.clone()));
+        }
+
+        let timestamp = i64::try_from(proof.timestamp).map_err(|_| TonProofError::Expired)?;
+        if timestamp < (now - self.proof_ttl).timestamp()
+            || timestamp > now.timestamp() + MAX_CLOCK_SKEW_SECONDS
+        {
+            return Err(TonProofError::Expired);
+        }
+
+        let payload_expires_at = self.verify_payload(&proof.payload, now)?;
+        Ok(VerifiedProof {
+            address: verify_signature(address, public_key, proof)?,
+            payload_expires_at,
+        })
+    }
+
+    fn mac(&self, data: &[u8]) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(data);
+        mac
+    }
+}
+
+impl fmt::Debug for TonProofConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TonProofConfig")
+            .field("domains", &self.domains)
+            .field("payload_ttl", &self.payload_ttl)
+            .field("proof_ttl", &self.proof_ttl)
+            .finish()
+    }
+}
+
+fn random_secret() -> Vec<u8> {
+    let mut secret = uuid::Uuid::new_v4().as_bytes().to_vec();
+    secret.extend_from_slice(uuid::Uuid::new_v4().as_bytes());
+    secret
+}
+
+/// The message a wallet signs for `proof`, see the TON Connect `ton_proof` spec.
+pub fn proof_message(address: &WalletAddress, proof: &TonProof) -> [u8; 32] {
+    let mut message = Vec::new();
+    message.extend_from_slice(TON_PROOF_PREFIX);
+    message.extend_from_slice(&address.workchain.to_be_bytes());
+    message.extend_from_slice(&address.hash);
+    message.extend_from_slice(&proof.domain.length_bytes.to_le_bytes());
+    message.extend_from_slice(proof.domain.value.as_bytes());
+    message.extend_from_slice(&proof.timestamp.to_le_bytes());
+    message.extend_from_slice(proof.payload.as_bytes());
+
+    let mut full_message = vec![0xff, 0xff];
+    full_message.extend_from_slice(TON_CONNECT_PREFIX);
+    full_message.extend_from_slice(&Sha256::digest(&message));
+    Sha256::digest(&full_message).into()
+}
+
+/// Checks that `public_key` owns the wallet at `address` and signed `proof`.
+///
+/// Payload, domain and age of the proof are checked by [`TonProofConfig::verify`].
+pub fn verify_signature(
+    address: &str,
+    public_key: &str,
+    proof: &TonProof,
+) -> Result<WalletAddress, TonProofError> {
+    let address = WalletAddress::parse_raw(address)?;
+    let public_key: [u8; 32] = hex::decode(public_key)
+        .ok()
+        .and_then(|key| key.try_into().ok())
+        .ok_or_else(|| TonProofError::Malformed("Public key must be 32 hex bytes".into()))?;
+
+    let state_init = BASE64_STANDARD
+        .decode(&proof.state_init)
+        .map_err(|err| TonProofError::Malformed(format!("Invalid state init: {}", err)))?;
+    let cells = parse_boc(&state_init)?;
+    if cells.hash(0) != address.hash {
+        return Err(TonProofError::StateInitMismatch);
+    }
+    let data = cells
+        .state_init_data()
+        .ok_or(TonProofError::PublicKeyMismatch)?;
+    if !PUBLIC_KEY_OFFSETS
+        .iter()
+        .any(|offset| data.read_bytes(*offset) == Some(public_key))
+    {
+        return Err(TonProofError::PublicKeyMismatch);
+    }
+
+    let signature: [u8; 64] = BASE64_STANDARD
+        .decode(&proof.signature)
+        .ok()
+        .and_then(|signature| signature.try_into().ok())
+        .ok_or_else(|| TonProofError::Malformed("Signature must be 64 base64 bytes".into()))?;
+    let verifying_key =
+        VerifyingKey::from_bytes(&public_key).map_err(|_| TonProofError::PublicKeyMismatch)?;
+    verifying_key
+        .verify_strict(
+            &proof_message(&address, proof),
+            &Signature::from_bytes(&signature),
+        )
+        .map_err(|_| TonProofError::InvalidSignature)?;
+
+    Ok(address)
+}
+
+/// An ordinary cell of a bag of cells.
+struct Cell {
+    d1: u8,
+    d2: u8,
+    data: Vec<u8>,
+    refs: Vec<usize>,
+}
+
+impl Cell {
+    fn bit_len(&self) -> usize {
+        let bytes = self.data.len();
+        if self.d2 % 2 == 0 {
+            return bytes * 8;
+        }
+        // Incomplete bytes end with a single 1 bit followed by zeros
+        let last = self.data[bytes - 1];
+        bytes * 8 - last.trailing_zeros() as usize - 1
+    }
+
+    fn bit(&self, index: usize) -> bool {
+        self.data[index / 8] & (0x80 >> (index % 8)) != 0
+    }
+
+    fn read_bytes(&self, offset: usize) -> Option<[u8; 32]> {
+        if offset + 256 > self.bit_len() {
+            return None;
+        }
+        let mut bytes = [0u8; 32];
+        for (index, byte) in bytes.iter_mut().enumerate() {
+            for bit in 0..8 {
+                if self.bit(offset + index * 8 + bit) {
+                    *byte |= 0x80 >> bit;
+                }
+            }
+        }
+        Some(bytes)
+    }
+}
+
+/// Cells of a bag of cells with a single root at index 0.
+struct Cells {
+    cells: Vec<Cell>,
+    hashes: Vec<[u8; 32]>,
+}
+
+impl Cells {
+    fn hash(&self, index: usize) -> [u8; 32] {
+        self.hashes[index]
+    }
+
+    /// The data cell of a `StateInit` root cell.
+    fn state_init_data(&self) -> Option<&Cell> {
+        let root = &self.cells[0];
+        let mut bit = 0;
+        let mut next = |bits: usize| {
+            let value = bit < root.bit_len() && root.bit(bit);
+            bit += bits;
+            value
+        };
+        if next(1) {
+            next(5); // split_depth
+        }
+        if next(1) {
+            next(2); // special
+        }
+        let has_code = next(1);
+        let has_data = next(1);
+        if !has_data {
+            return None;
+        }
+        let data = *root.refs.get(usize::from(has_code))?;
+        self.cells.get(data)
+    }
+}
+
+/// Parses a bag of cells and computes the representation hash of every cell.
+fn parse_boc(bytes: &[u8]) -> Result<Cells, TonProofError> {
+    let malformed =
+        |reason: &str| TonProofError::Malformed(format!("Invalid state init: {}", reason));
+    let mut reader = Reader { bytes, position: 0 };
+
+    if reader.take(4).ok_or_else(|| malformed("too short"))? != BOC_MAGIC {
+        return Err(malformed("unsupported bag of cells"));
+    }
+    let flags = reader.byte().ok_or_else(|| malformed("too short"))?;
+    let has_index = flags & 0x80 != 0;
+    let ref_size = usize::from(flags & 0x07);
+    let offset_size = usize::from(reader.byte().ok_or_else(|| malformed("too short"))?);
+    if !(1..=4).contains(&ref_size) || !(1..=8).contains(&offset_size) {
+        return Err(malformed("invalid sizes"));
+    }
+
+    let cell_count = reader
+        .uint(ref_size)
+        .ok_or_else(|| malformed("too short"))?;
+    let root_count = reader
+        .uint(ref_size)
+        .ok_or_else(|| malformed("too short"))?;
+    let _absent = reader
+        .uint(ref_size)
+        .ok_or_else(|| malformed("too short"))?;
+    let _total_size = reader
+        .uint(offset_size)
+        .ok_or_else(|| malformed("too short"))?;
+    if root_count != 1 {
+        return Err(malformed("expected a single root"));
+    }
+    if reader
+        .uint(ref_size)
+        .ok_or_else(|| malformed("too short"))?
+        != 0
+    {
+        return Err(malformed("root must be the first cell"));
+    }
+    if has_index {
+        reader
+            .take(cell_count * offset_size)
+            .ok_or_else(|| malformed("too short"))?;
+    }
+
+    let mut cells = Vec::with_capacity(cell_count.min(bytes.len()));
+    for index in 0..cell_count {
+        let d1 = reader.byte().ok_or_else(|| malformed("too short"))?;
+        let d2 = reader.byte().ok_or_else(|| malformed("too short"))?;
+        if d1 & 0xf8 != 0 {
+            return Err(malformed(
+                "exotic cells and stored hashes are not supported",
+            ));
+        }
+        let ref_count = usize::from(d1 & 0x07);
+        if ref_count > 4 {
+            return Err(malformed("too many references"));
+        }
+        let data = reader
+            .take(usize::from(d2).div_ceil(2))
+            .ok_or_else(|| malformed("too short"))?
+            .to_vec();
+        if d2 % 2 == 1 && data.last() == Some(&0) {
+            return Err(malformed("invalid cell padding"));
+        }
+        let mut refs = Vec::with_capacity(ref_count);
+        for _ in 0..ref_count {
+            let child = reader
+                .uint(ref_size)
+                .ok_or_else(|| malformed("too short"))?;
+            // Children always follow their parent, which also rules out cycles
+            if child <= index || child >= cell_count {
+                return Err(malformed("invalid cell reference"));
+            }
+            refs.push(child);
+        }
+        cells.push(Cell { d1, d2, data, refs });
+    }
+
+    // Hash children first, they have higher indices than their parents
+    let mut hashes = vec![[0u8; 32]; cell_count];
+    let mut depths = vec![0u16; cell_count];
+    for index in (0..cell_count).rev() {
+        let cell = &cells[index];
+        let mut hasher = Sha256::new();
+        hasher.update([cell.d1, cell.d2]);
+        hasher.update(&cell.data);
+        for child in &cell.refs {
+            hasher.update(depths[*child].to_be_bytes());
+        }
+        for child in &cell.refs {
+            hasher.update(hashes[*child]);
+        }
+        hashes[index] = hasher.finalize().into();
+        depths[index] = cell
+            .refs
+            .iter()
+            .map(|child| depths[*child] + 1)
+            .max()
+            .unwrap_or(0);
+    }
+
+    if cells.is_empty() {
+        return Err(malformed("no cells"));
+    }
+    Ok(Cells { cells, hashes })
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.position.checked_add(len)?;
+        let bytes = self.bytes.get(self.position..end)?;
+        self.position = end;
+        Some(bytes)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn uint(&mut self, size: usize) -> Option<usize> {
+        self.take(size).map(|bytes| {
+            bytes
+                .iter()
+                .fold(0usize, |value, byte| (value << 8) | usize::from(*byte))
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    // Wallet of the ed25519 key with seed 0, 1, .., 31
+    pub const SEED: [u8; 32] = [
+        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
+        25, 26, 27, 28, 29, 30, 31,
+    ];
+    pub const PUBLIC_KEY: &str = "03a107bff3ce10be1d70dd18e74bc09967e4d6309ba50d5f1ddc8664125531b8";
+    pub const STATE_INIT: &str = "te6ccgEBAwEAPAACATQBAgAU/wD0pBP0vPLICwBRAAAAACmpoxcDoQe/884Qvh1w3RjnS8CZZ+TWMJulDV8d3IZkElUxuEA=";
+    pub const ADDRESS: &str = "0:e8956476d5fd9a57568164609c821ffbe090d73348dde70c0bfa0912afe426e9";
+    pub const DOMAIN: &str = "konnektoren.help";
+    const TIMESTAMP: u64 = 1700000000;
+    const SIGNATURE: &str =
+        "oEZ/eJYwFB+BOcdu9bumXsxgV7tpmA+LIDzU+k4x+ONo6AZ84YCMOqZXKEf6X8gHPIu5bKYNkMdgtRcm/gfgBQ==";
+
+    fn fixed_proof() -> TonProof {
+        TonProof {
+            timestamp: TIMESTAMP,
+            domain: TonProofDomain {
+                length_bytes: DOMAIN.len() as u32,
+                value: DOMAIN.to_string(),
+            },
+            signature: SIGNATURE.to_string(),
+            payload: "test-payload".to_string(),
+            state_init: STATE_INIT.to_string(),
+        }
+    }
+
+    /// A proof of the test wallet, signed now for `payload`.
+    pub fn signed_proof(payload: &str, now: DateTime<Utc>) -> TonProof {
+        let mut proof = fixed_proof();
+        proof.timestamp = now.timestamp() as u64;
+        proof.payload = payload.to_string();
+        let message = proof_message(&WalletAddress::parse_raw(ADDRESS).unwrap(), &proof);
+        let signature = SigningKey::from_bytes(&SEED).sign(&message);
+        proof.signature = BASE64_STANDARD.encode(signature.to_bytes());
+        proof
+    }
+
+    #[test]
+    fn test_fixed_vector_verifies() {
+        let address = verify_signature(ADDRESS, PUBLIC_KEY, &fixed_proof()).unwrap();
+        assert_eq!(address.workchain, 0);
+        assert_eq!(address.to_string(), ADDRESS);
+    }
+
+    #[test]
+    fn test_tampered_proofs_are_rejected() {
+        let mut proof = fixed_proof();
+        proof.payload = "other-payload".to_string();
+        assert_eq!(
+            verify_signature(ADDRESS, PUBLIC_KEY, &proof),
+            Err(TonProofError::InvalidSignature)
+        );
+
+        let mut proof = fixed_proof();
+        proof.timestamp += 1;
+        assert_eq!(
+            verify_signature(ADDRESS, PUBLIC_KEY, &proof),
+            Err(TonProofError::InvalidSignature)
+        );
+
+        let other_address = format!("0:{}", "ab".repeat(32));
+        assert_eq!(
+            verify_signature(&other_address, PUBLIC_KEY, &fixed_proof()),
+            Err(TonProofError::StateInitMismatch)
+        );
+
+        let other_key = hex::encode(
+            SigningKey::from_bytes(&[7u8; 32])
+                .verifying_key()
+                .to_bytes(),
+        );
+        assert_eq!(
+            verify_signature(ADDRESS, &other_key, &fixed_proof()),
+            Err(TonProofError::PublicKeyMismatch)
+        );
+
+        assert!(matches!(
+            verify_signature("not-an-address", PUBLIC_KEY, &fixed_proof()),
+            Err(TonProofError::Malformed(_))
+        ));
+        let mut proof = fixed_proof();
+        proof.state_init = BASE64_STANDARD.encode(b"garbage");
+        assert!(matches!(
+            verify_signature(ADDRESS, PUBLIC_KEY, &proof),
+            Err(TonProofError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_payloads_are_signed_and_expire() {
+        let config = TonProofConfig::new("secret", [DOMAIN]);
+        let now = Utc::now();
+        let (payload, expires_at) = config.create_payload(now);
+        assert_eq!(
+            config
+                .verify_payload(&payload, now)
+                .map(|expires_at| expires_at.timestamp()),
+            Ok(expires_at.timestamp())
+        );
+        assert_eq!(
+            config.verify_payload(&payload, expires_at + Duration::seconds(1)),
+            Err(TonProofError::InvalidPayload)
+        );
+
+        let other = TonProofConfig::new("other", [DOMAIN]);
+        assert_eq!(
+            other.verify_payload(&payload, now),
+            Err(TonProofError::InvalidPayload)
+        );
+        assert_eq!(
+            config.verify_payload("test-payload", now),
+            Err(TonProofError::InvalidPayload)
+        );
+    }
+
+    #[test]
+    fn test_verify_checks_domain_age_and_payload() {
+        let config = TonProofConfig::new("secret", [DOMAIN]);
+        let now = Utc::now();
+        let (payload, expires_at) = config.create_payload(now);
+
+        let proof = signed_proof(&payload, now);
+        let verified = config.verify(ADDRESS, PUBLIC_KEY, &proof, now).unwrap();
+        assert_eq!(verified.address.to_string(), ADDRESS);
+        assert_eq!(
+            verified.payload_expires_at.timestamp(),
+            expires_at.timestamp()
+        );
+
+        let other_domain = TonProofConfig::new("secret", ["example.com"]);
+        assert_eq!(
+            other_domain.verify(ADDRESS, PUBLIC_KEY, &proof, now),
+            Err(TonProofError::DomainNotAllowed(DOMAIN.to_string()))
+        );
+
+        assert_eq!(
+            config.verify(ADDRESS, PUBLIC_KEY, &proof, now + Duration::hours(1)),
+            Err(TonProofError::Expired)
+        );
+
+        // Signed correctly, but for a payload this server never issued
+        let proof = signed_proof("test-payload", now);
+        assert_eq!(
+            config.verify(ADDRESS, PUBLIC_KEY, &proof, now),
+            Err(TonProofError::InvalidPayload)
+        );
+    }
+}
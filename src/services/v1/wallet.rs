@@ -0,0 +1,286 @@
+use crate::services::v1::profile::{authorize_profile, generate_token, hash_token, ProfileError};
+use crate::services::v1::ton_proof::{TonProof, TonProofConfig, TonProofError};
+use crate::storage::{ProfileCredentialRepository, ProfileSession, RepositoryError, Storage};
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+use thiserror::Error;
+
+pub const SESSION_TTL_DAYS: i64 = 30;
+
+#[derive(Debug, Error)]
+pub enum WalletError {
+    #[error("{0}")]
+    Proof(#[from] TonProofError),
+    #[error("{0}")]
+    Profile(#[from] ProfileError),
+    #[error("Repository error: {0}")]
+    Repository(#[from] RepositoryError),
+    #[error("Wallet {0} is not linked to a profile yet, send the profile id and its owner token")]
+    ProfileRequired(String),
+    #[error("Wallet {0} is linked to another profile")]
+    LinkedToOtherProfile(String),
+}
+
+/// A wallet sign-in as sent by a TON Connect client.
+#[derive(Debug, Clone)]
+pub struct WalletSignIn {
+    /// Raw address, `workchain:hash`
+    pub address: String,
+    /// Hex ed25519 public key of the wallet
+    pub public_key: String,
+    pub proof: TonProof,
+    /// Profile to link, only needed on the first sign-in with a wallet
+    pub profile_id: Option<String>,
+}
+
+/// A session token for the profile linked to a verified wallet.
+#[derive(Debug, Clone)]
+pub struct WalletSession {
+    pub profile_id: String,
+    pub address: String,
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Verifies a `ton_proof` and issues a session token for the wallet's profile.
+///
+/// The first sign-in with a wallet links it to `profile_id`, which requires the
+/// owner token of that profile. Later sign-ins, e.g. from a new device, only
+/// need the proof. Every payload signs in once, rejected sign-ins do not
+/// use it up.
+pub async fn sign_in(
+    config: &TonProofConfig,
+    request: WalletSignIn,
+    owner_token: Option<&str>,
+    repository: Arc<dyn Storage>,
+    now: DateTime<Utc>,
+) -> Result<WalletSession, WalletError> {
+    let verified = config.verify(&request.address, &request.public_key, &request.proof, now)?;
+    let address = verified.address.to_string();
+
+    let linked_profile = repository.fetch_wallet_profile(&address).await?;
+    let profile_id = match &linked_profile {
+        Some(profile_id) => {
+            if request
+                .profile_id
+                .is_some_and(|requested| &requested != profile_id)
+            {
+                return Err(WalletError::LinkedToOtherProfile(address));
+            }
+            profile_id.clone()
+        }
+        None => {
+            let profile_id = request
+                .profile_id
+                .ok_or_else(|| WalletError::ProfileRequired(address.clone()))?;
+            authorize_profile(&profile_id, owner_token, repository.clone()).await?;
+            profile_id
+        }
+    };
+
+    // Payloads are not stored when issued, so used ones are recorded until
+    // they expire. Only a sign-in that gets this far uses up its payload.
+    match repository
+        .consume_payload(
+            &hash_token(&request.proof.payload),
+            verified.payload_expires_at,
+        )
+        .await
+    {
+        Ok(()) => {}
+        Err(RepositoryError::AlreadyExists(_)) => return Err(TonProofError::PayloadUsed.into()),
+        Err(err) => return Err(err.into()),
+    }
+
+    if linked_profile.is_none() {
+        match repository.link_wallet(&address, &profile_id).await {
+            Ok(()) => {}
+            // A concurrent sign-in linked the wallet first
+            Err(RepositoryError::AlreadyExists(_)) => {
+                if repository.fetch_wallet_profile(&address).await?.as_ref() != Some(&profile_id) {
+                    return Err(WalletError::LinkedToOtherProfile(address));
+                }
+            }
+            Err(err) => return Err(err.into()),
+        }
+        log::info!("Linked wallet {} to profile {}", address, profile_id);
+    }
+
+    let token = generate_token();
+    let expires_at = now + Duration::days(SESSION_TTL_DAYS);
+    repository
+        .create_session(
+            &hash_token(&token),
+            ProfileSession {
+                profile_id: profile_id.clone(),
+                expires_at,
+            },
+        )
+        .await?;
+
+    Ok(WalletSession {
+        profile_id,
+        address,
+        token,
+        expires_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::v1::profile::save_profile;
+    use crate::services::v1::ton_proof::tests::{signed_proof, ADDRESS, DOMAIN, PUBLIC_KEY};
+    use crate::storage::MemoryRepository;
+    use konnektoren_core::prelude::PlayerProfile;
+
+    fn request(config: &TonProofConfig, profile_id: Option<&str>) -> WalletSignIn {
+        let now = Utc::now();
+        let (payload, _) = config.create_payload(now);
+        WalletSignIn {
+            address: ADDRESS.to_string(),
+            public_key: PUBLIC_KEY.to_string(),
+            proof: signed_proof(&payload, now),
+            profile_id: profile_id.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sign_in_links_wallet_and_issues_session() {
+        let config = TonProofConfig::new("secret", [DOMAIN]);
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let mut profile = PlayerProfile::new("player1".to_string());
+        let owner_token = save_profile(profile.clone(), None, repository.clone())
+            .await
+            .unwrap()
+            .token
+            .unwrap();
+
+        // The first sign-in needs a profile and its owner token
+        let result = sign_in(
+            &config,
+            request(&config, None),
+            None,
+            repository.clone(),
+            Utc::now(),
+        )
+        .await;
+        assert!(matches!(result, Err(WalletError::ProfileRequired(_))));
+        let result = sign_in(
+            &config,
+            request(&config, Some("player1")),
+            Some("wrong"),
+            repository.clone(),
+            Utc::now(),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(WalletError::Profile(ProfileError::InvalidToken(_)))
+        ));
+
+        let session = sign_in(
+            &config,
+            request(&config, Some("player1")),
+            Some(&owner_token),
+            repository.clone(),
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(session.profile_id, "player1");
+        assert_eq!(session.address, ADDRESS);
+
+        // A new device signs in with the wallet alone
+        let session = sign_in(
+            &config,
+            request(&config, None),
+            None,
+            repository.clone(),
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(session.profile_id, "player1");
+
+        // and may update the profile with the session token
+        profile.name = "from another device".to_string();
+        let saved = save_profile(profile, Some(&session.token), repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(saved.profile.name, "from another device");
+
+        let result = sign_in(
+            &config,
+            request(&config, Some("player2")),
+            None,
+            repository,
+            Utc::now(),
+        )
+        .await;
+        assert!(matches!(result, Err(WalletError::LinkedToOtherProfile(_))));
+    }
+
+    #[tokio::test]
+    async fn test_payloads_sign_in_once() {
+        let config = TonProofConfig::new("secret", [DOMAIN]);
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        let profile = PlayerProfile::new("player1".to_string());
+        let owner_token = save_profile(profile, None, repository.clone())
+            .await
+            .unwrap()
+            .token
+            .unwrap();
+
+        let request = request(&config, Some("player1"));
+        sign_in(
+            &config,
+            request.clone(),
+            Some(&owner_token),
+            repository.clone(),
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+
+        // A captured proof cannot be replayed while its payload is valid
+        let replayed = WalletSignIn {
+            profile_id: None,
+            ..request
+        };
+        let result = sign_in(&config, replayed, None, repository, Utc::now()).await;
+        assert!(matches!(
+            result,
+            Err(WalletError::Proof(TonProofError::PayloadUsed))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sign_in_rejects_invalid_proofs() {
+        let config = TonProofConfig::new("secret", [DOMAIN]);
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+
+        let mut invalid = request(&config, Some("player1"));
+        invalid.proof.payload = config.create_payload(Utc::now()).0;
+        let result = sign_in(&config, invalid, None, repository.clone(), Utc::now()).await;
+        assert!(matches!(
+            result,
+            Err(WalletError::Proof(TonProofError::InvalidSignature))
+        ));
+
+        let expired = request(&config, Some("player1"));
+        let result = sign_in(
+            &config,
+            expired,
+            None,
+            repository.clone(),
+            Utc::now() + Duration::days(1),
+        )
+        .await;
+        assert!(matches!(result, Err(WalletError::Proof(_))));
+        assert_eq!(
+            repository.fetch_wallet_profile(ADDRESS).await.unwrap(),
+            None
+        );
+    }
+}
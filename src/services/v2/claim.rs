@@ -1,56 +1,209 @@
-#[cfg(feature = "ton")]
+use crate::issuer::TokenIssuerError;
 use crate::routes::v2::claim::{ClaimV2Request, ClaimV2Response};
-#[cfg(feature = "ton")]
-use crate::ton::{create_key_pair, create_testnet_client, generate_signed_message};
-#[cfg(feature = "ton")]
+use crate::services::claim_eligibility::ClaimPolicy;
+use crate::services::claim_error::ClaimError;
+use crate::services::claim_ledger::{
+    claim_ledger_error_response, fail_claim, get_claim, start_claim, ClaimStart,
+};
+use crate::services::claim_queue::ClaimQueue;
+use crate::storage::{ClaimKind, ClaimRecord, ClaimStatus, Storage};
 use axum::http::StatusCode;
 use axum::Json;
-use base64::prelude::BASE64_STANDARD;
-use base64::Engine;
-use std::env;
+use chrono::Utc;
+use std::sync::Arc;
 
-#[cfg(feature = "ton")]
+/// Signs a jetton transfer for the client to send, once per claim id and
+/// only if the player earned the tokens under `policy`. The message takes the
+/// seqno of the faucet wallet through `queue`, so it never competes with the
+/// transfers of the claim queue.
+///
+/// Repeated requests with the same id get the recorded message until it
+/// expires, then a new one if the wallet did not process it.
 pub async fn claim_tokens_service(
     payload: ClaimV2Request,
-) -> Result<Json<ClaimV2Response>, (StatusCode, String)> {
+    policy: &ClaimPolicy,
+    queue: &ClaimQueue,
+    repository: Arc<dyn Storage>,
+) -> Result<Json<ClaimV2Response>, ClaimError> {
     if payload.request_type != "claim" {
-        return Err((StatusCode::BAD_REQUEST, "Invalid request type".into()));
+        return Err(ClaimError::InvalidRequestType(payload.request_type));
     }
+    queue.issuer().validate_address(&payload.address)?;
 
     log::info!("Received claim request: {:?}", payload);
 
-    let client = match create_testnet_client().await {
-        Ok(client) => client,
-        Err(err) => return Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
+    let claim = ClaimRecord::new(
+        payload.id,
+        ClaimKind::SignedMessage,
+        payload.user,
+        payload.address,
+        payload.amount,
+    );
+    let claim = match start_claim(claim, policy, repository.clone(), Utc::now()).await? {
+        ClaimStart::New(claim) => claim,
+        ClaimStart::Existing(claim) => {
+            log::info!("Claim {} was already made: {:?}", claim.id, claim.status);
+            match claim.status {
+                ClaimStatus::Pending | ClaimStatus::Submitted => claim,
+                _ => {
+                    if let Some(err) = ClaimError::recorded(&claim) {
+                        return Err(err);
+                    }
+                    return Ok(Json(claim_response(claim)));
+                }
+            }
+        }
     };
 
-    let mnemonic = env::var("MNEMONIC").unwrap();
-    let key_pair = create_key_pair(&mnemonic).await.unwrap();
+    match queue
+        .sign_message(claim.clone(), repository.clone(), Utc::now())
+        .await
+    {
+        Ok(claim) => Ok(Json(claim_response(claim))),
+        // Requests with the same id sign the message again
+        Err(err @ (ClaimError::WalletBusy | ClaimError::Issuer(TokenIssuerError::Network(_)))) => {
+            Err(err)
+        }
+        Err(ClaimError::Issuer(err)) => {
+            fail_claim(claim, err.to_string(), repository).await?;
+            Err(err.into())
+        }
+        Err(err) => Err(err),
+    }
+}
 
-    let contract_address = env::var("CONTRACT_ADDRESS").unwrap();
-    let faucet_address = env::var("FAUCET_ADDRESS").unwrap();
+fn claim_response(claim: ClaimRecord) -> ClaimV2Response {
+    ClaimV2Response {
+        success: true,
+        raw_transaction: claim.raw_transaction.unwrap_or_default(),
+        destination: claim.address,
+    }
+}
 
-    match generate_signed_message(
-        &client,
-        &key_pair,
-        &contract_address,
-        &payload.address,
-        payload.amount as u128,
-    )
-    .await
+pub async fn get_claim_service(
+    id: i64,
+    repository: Arc<dyn Storage>,
+) -> Result<Json<ClaimRecord>, (StatusCode, String)> {
+    match get_claim(id, repository)
+        .await
+        .map_err(claim_ledger_error_response)?
     {
-        Ok(tx) => {
-            let b64_tx = BASE64_STANDARD.encode(&tx);
-            log::info!("Generated signed message: {:?}", tx);
+        Some(claim) => Ok(Json(claim)),
+        None => Err((StatusCode::NOT_FOUND, format!("Claim {} not found", id))),
+    }
+}
 
-            let response = ClaimV2Response {
-                success: true,
-                raw_transaction: b64_tx,
-                destination: payload.address.clone(),
-            };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::issuer::{IssuedTransfer, MemoryTokenIssuer, TokenIssuer};
+    use crate::services::claim_eligibility::tests::{eligible_player, policy};
+    use crate::services::claim_queue::ClaimQueueConfig;
+    use crate::storage::{ClaimRepository, MemoryRepository};
+    use base64::prelude::BASE64_STANDARD;
+    use base64::Engine;
 
-            Ok(Json(response))
+    fn request(id: i64, amount: u32) -> ClaimV2Request {
+        ClaimV2Request {
+            id,
+            user: "alice".to_string(),
+            request_type: "claim".to_string(),
+            address: "alice-wallet".to_string(),
+            amount,
         }
-        Err(err) => Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
+    }
+
+    async fn setup() -> (Arc<MemoryTokenIssuer>, ClaimQueue, Arc<dyn Storage>) {
+        let repository: Arc<dyn Storage> = Arc::new(MemoryRepository::new());
+        eligible_player(&repository, "alice", 5).await;
+        let issuer = Arc::new(MemoryTokenIssuer::new());
+        let queue = ClaimQueue::new(issuer.clone(), ClaimQueueConfig::default());
+        (issuer, queue, repository)
+    }
+
+    fn transfer(response: &ClaimV2Response) -> IssuedTransfer {
+        let message = BASE64_STANDARD.decode(&response.raw_transaction).unwrap();
+        serde_json::from_slice(&message).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_claim_returns_the_same_signed_message() {
+        let (issuer, queue, repository) = setup().await;
+        let policy = policy();
+
+        let Json(first) = claim_tokens_service(request(1, 2), &policy, &queue, repository.clone())
+            .await
+            .unwrap();
+        let Json(second) = claim_tokens_service(request(1, 2), &policy, &queue, repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(first.raw_transaction, second.raw_transaction);
+        assert_eq!(first.destination, "alice-wallet");
+
+        // The message is signed for the client and not sent by the server
+        assert!(issuer.sent_transfers().is_empty());
+        assert_eq!(transfer(&first).amount, 2);
+
+        let Json(claim) = get_claim_service(1, repository).await.unwrap();
+        assert_eq!(claim.raw_transaction, Some(first.raw_transaction));
+        assert_eq!(claim.status, ClaimStatus::Submitted);
+    }
+
+    #[tokio::test]
+    async fn test_signed_messages_take_the_seqno_in_turn() {
+        let (issuer, queue, repository) = setup().await;
+        let worker = queue.worker(repository.clone());
+        let policy = policy();
+
+        let Json(first) = claim_tokens_service(request(1, 1), &policy, &queue, repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(transfer(&first).seqno, 0);
+
+        // The first message holds the seqno until the wallet processed it
+        let result = claim_tokens_service(request(2, 1), &policy, &queue, repository.clone()).await;
+        assert!(matches!(result, Err(ClaimError::WalletBusy)));
+        let message = BASE64_STANDARD.decode(&first.raw_transaction).unwrap();
+        issuer.send_message(&message).await.unwrap();
+
+        let Json(second) = claim_tokens_service(request(2, 1), &policy, &queue, repository.clone())
+            .await
+            .unwrap();
+        assert_eq!(transfer(&second).seqno, 1);
+
+        worker.process(Utc::now()).await.unwrap();
+        let claim = repository.fetch_claim(1).await.unwrap().unwrap();
+        assert_eq!(claim.status, ClaimStatus::Sent);
+        let claim = repository.fetch_claim(2).await.unwrap().unwrap();
+        assert_eq!(claim.status, ClaimStatus::Submitted);
+    }
+
+    #[tokio::test]
+    async fn test_expired_messages_are_signed_anew() {
+        let (issuer, queue, repository) = setup().await;
+        let worker = queue.worker(repository.clone());
+        let policy = policy();
+        let config = ClaimQueueConfig::default();
+
+        claim_tokens_service(request(1, 1), &policy, &queue, repository.clone())
+            .await
+            .unwrap();
+        // Another message of the wallet takes the seqno of the unsent message
+        let other = issuer.signed_transfer("bob-wallet", 1).await.unwrap();
+        issuer.send_message(&other).await.unwrap();
+
+        let later = Utc::now() + config.confirm_timeout + chrono::Duration::seconds(1);
+        worker.process(later).await.unwrap();
+        let claim = repository.fetch_claim(1).await.unwrap().unwrap();
+        assert_eq!(claim.status, ClaimStatus::Pending);
+
+        let Json(renewed) =
+            claim_tokens_service(request(1, 1), &policy, &queue, repository.clone())
+                .await
+                .unwrap();
+        assert_eq!(transfer(&renewed).seqno, 1);
+        let claim = repository.fetch_claim(1).await.unwrap().unwrap();
+        assert_eq!(claim.status, ClaimStatus::Submitted);
+        assert_eq!(claim.attempts, 2);
     }
 }
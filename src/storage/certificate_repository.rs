@@ -0,0 +1,94 @@
+use crate::storage::error::RepositoryError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CertificateStatus {
+    /// The mint message was signed for the client by earlier versions, it is
+    /// queued again when the certificate is requested.
+    Prepared,
+    /// The mint waits in the claim queue.
+    Queued,
+    /// The wallet processed the mint message.
+    Sent,
+    /// The mint failed, the error is on its claim.
+    Failed,
+}
+
+/// An NFT certificate for a completed game path, keyed by player and game path.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CertificateRecord {
+    #[schema(example = "3f2b8c1d0e5a4f6b7c8d9e0f1a2b3c4d")]
+    pub id: String,
+    #[schema(example = "example_user")]
+    pub profile_id: String,
+    #[schema(example = "Example User")]
+    pub profile_name: String,
+    #[schema(example = "konnektoren")]
+    pub game_path_id: String,
+    /// Address owning the NFT
+    #[schema(example = "0QB-_k5Rule-nKr6HWPIlkDyHb1xhDdbI77q7uwAFqmUmKjP")]
+    pub owner: String,
+    #[schema(example = 95)]
+    pub performance_percentage: u8,
+    #[schema(example = 12)]
+    pub total_challenges: usize,
+    /// Date of the performance record the certificate is for
+    #[schema(value_type = String, format = DateTime)]
+    pub completed_at: DateTime<Utc>,
+    /// Address of the NFT collection and index of the item in it, the index
+    /// is reserved when the claim queue first signs the mint
+    pub collection: String,
+    #[schema(example = 7)]
+    pub item_index: Option<u64>,
+    /// Where the metadata of the NFT is served, stored in the item on-chain
+    #[schema(
+        example = "https://api.konnektoren.help/api/v1/certificates/3f2b8c1d0e5a4f6b7c8d9e0f1a2b3c4d"
+    )]
+    pub metadata_url: String,
+    /// Image shown for the NFT
+    pub image: Option<String>,
+    pub status: CertificateStatus,
+    /// Claim of the mint in the claim queue
+    #[serde(default)]
+    pub claim_id: Option<i64>,
+    /// Base64 signed mint message
+    pub raw_transaction: Option<String>,
+    /// Hash of the sent mint message
+    pub tx_hash: Option<String>,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    #[schema(value_type = String, format = DateTime)]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[async_trait]
+pub trait CertificateRepository: Send + Sync {
+    /// Records a new certificate, fails with [`RepositoryError::AlreadyExists`] if the id is taken.
+    async fn create_certificate(
+        &self,
+        certificate: CertificateRecord,
+    ) -> Result<CertificateRecord, RepositoryError>;
+
+    async fn fetch_certificate(
+        &self,
+        id: &str,
+    ) -> Result<Option<CertificateRecord>, RepositoryError>;
+
+    /// Replaces a recorded certificate, fails with [`RepositoryError::NotFound`] if it does not exist.
+    async fn update_certificate(
+        &self,
+        certificate: CertificateRecord,
+    ) -> Result<CertificateRecord, RepositoryError>;
+
+    /// Reserves an item index of `collection` that was never reserved before,
+    /// at least `next_index`, the next index of the collection on-chain.
+    async fn reserve_item_index(
+        &self,
+        collection: &str,
+        next_index: u64,
+    ) -> Result<u64, RepositoryError>;
+}
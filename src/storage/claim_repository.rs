@@ -0,0 +1,126 @@
+use crate::storage::error::RepositoryError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ClaimStatus {
+    /// The claim is recorded and the tokens are being sent.
+    Pending,
+    /// The claim waits in the claim queue for the server to send the tokens.
+    Queued,
+    /// The transfer was sent, or the message of a signed message claim was
+    /// signed, the faucet wallet has not processed it yet.
+    Submitted,
+    /// The faucet wallet processed the transfer.
+    Sent,
+    Failed,
+}
+
+/// How the tokens of a claim are delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimKind {
+    /// The server sends the jettons, `POST /api/v1/claim`.
+    Transfer,
+    /// The server returns a signed message for the client to send, `POST /api/v2/claim`.
+    SignedMessage,
+    /// The server mints the NFT of a certificate, `POST /api/v1/certificates`.
+    CertificateMint,
+}
+
+/// A token claim in the claims ledger, keyed by the id of the claim request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ClaimRecord {
+    #[schema(example = 123456)]
+    pub id: i64,
+    pub kind: ClaimKind,
+    #[schema(example = "example_user")]
+    pub user: String,
+    #[schema(example = "0QB-_k5Rule-nKr6HWPIlkDyHb1xhDdbI77q7uwAFqmUmKjP")]
+    pub address: String,
+    #[schema(example = 1)]
+    pub amount: u32,
+    pub status: ClaimStatus,
+    /// Hash of the sent transfer
+    pub tx_hash: Option<String>,
+    /// Base64 signed message of a signed message claim, or the last message
+    /// the claim queue sent
+    pub raw_transaction: Option<String>,
+    /// Why the claim failed, or why the last attempt of a queued claim failed
+    pub error: Option<String>,
+    /// Attempts of the claim queue to send the tokens
+    #[serde(default)]
+    pub attempts: u32,
+    /// Seqno of the faucet wallet the last message was signed with
+    #[serde(default)]
+    pub seqno: Option<u32>,
+    /// When the claim queue retries a queued claim
+    #[serde(default)]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    /// Certificate a certificate mint claim mints
+    #[serde(default)]
+    pub certificate_id: Option<String>,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    #[schema(value_type = String, format = DateTime)]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ClaimRecord {
+    /// A new pending claim.
+    pub fn new(id: i64, kind: ClaimKind, user: String, address: String, amount: u32) -> Self {
+        let now = Utc::now();
+        Self {
+            id,
+            kind,
+            user,
+            address,
+            amount,
+            status: ClaimStatus::Pending,
+            tx_hash: None,
+            raw_transaction: None,
+            error: None,
+            attempts: 0,
+            seqno: None,
+            next_attempt_at: None,
+            certificate_id: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Whether the claim waits in the claim queue.
+    pub fn is_queued(&self) -> bool {
+        matches!(self.status, ClaimStatus::Queued | ClaimStatus::Submitted)
+    }
+
+    /// Whether `other` asks for the same tokens as this claim.
+    pub fn is_same_request(&self, other: &ClaimRecord) -> bool {
+        self.id == other.id
+            && self.kind == other.kind
+            && self.user == other.user
+            && self.address == other.address
+            && self.amount == other.amount
+    }
+}
+
+#[async_trait]
+pub trait ClaimRepository: Send + Sync {
+    /// Records a new claim, fails with [`RepositoryError::AlreadyExists`] if the id is taken.
+    async fn create_claim(&self, claim: ClaimRecord) -> Result<ClaimRecord, RepositoryError>;
+
+    async fn fetch_claim(&self, id: i64) -> Result<Option<ClaimRecord>, RepositoryError>;
+
+    /// All claims of a user, in any status.
+    async fn fetch_user_claims(&self, user: &str) -> Result<Vec<ClaimRecord>, RepositoryError>;
+
+    /// Claims in the claim queue, queued or submitted, in any order.
+    async fn fetch_queued_claims(&self) -> Result<Vec<ClaimRecord>, RepositoryError>;
+
+    /// Replaces a recorded claim, fails with [`RepositoryError::NotFound`] if it does not exist.
+    async fn update_claim(&self, claim: ClaimRecord) -> Result<ClaimRecord, RepositoryError>;
+}
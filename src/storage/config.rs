@@ -0,0 +1,182 @@
+use crate::storage::{MemoryRepository, RepositoryError, Storage};
+use std::sync::Arc;
+use thiserror::Error;
+
+const DEFAULT_SQLITE_URL: &str = "sqlite://konnektoren.db";
+
+#[derive(Debug, Error, PartialEq)]
+pub enum StorageConfigError {
+    #[error("Unknown storage backend '{0}', expected one of: memory, redis, sqlite, postgres")]
+    UnknownBackend(String),
+    #[error("{0} must be set for the selected storage backend")]
+    MissingSetting(&'static str),
+    #[error("Storage backend '{0}' is not enabled in this build, rebuild with --features {0}")]
+    FeatureDisabled(&'static str),
+    #[error("Failed to connect to {backend} storage: {source}")]
+    Connection {
+        backend: &'static str,
+        source: RepositoryError,
+    },
+}
+
+/// Storage backend selected with `STORAGE_BACKEND`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StorageConfig {
+    Memory,
+    Redis { url: String },
+    Sqlite { url: String },
+    Postgres { url: String },
+}
+
+impl StorageConfig {
+    pub fn from_env() -> Result<Self, StorageConfigError> {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    /// Builds the config from a variable lookup.
+    /// Without `STORAGE_BACKEND`, Redis is used when `REDIS_URL` is set and memory otherwise.
+    pub fn from_vars<F>(var: F) -> Result<Self, StorageConfigError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let backend = match var("STORAGE_BACKEND").filter(|value| !value.is_empty()) {
+            Some(backend) => backend.to_lowercase(),
+            None if var("REDIS_URL").is_some() => "redis".to_string(),
+            None => "memory".to_string(),
+        };
+
+        match backend.as_str() {
+            "memory" => Ok(StorageConfig::Memory),
+            "redis" => Ok(StorageConfig::Redis {
+                url: var("REDIS_URL").ok_or(StorageConfigError::MissingSetting("REDIS_URL"))?,
+            }),
+            "sqlite" => Ok(StorageConfig::Sqlite {
+                url: var("SQLITE_URL").unwrap_or_else(|| DEFAULT_SQLITE_URL.to_string()),
+            }),
+            "postgres" => Ok(StorageConfig::Postgres {
+                url: var("DATABASE_URL")
+                    .ok_or(StorageConfigError::MissingSetting("DATABASE_URL"))?,
+            }),
+            _ => Err(StorageConfigError::UnknownBackend(backend)),
+        }
+    }
+
+    pub fn backend(&self) -> &'static str {
+        match self {
+            StorageConfig::Memory => "memory",
+            StorageConfig::Redis { .. } => "redis",
+            StorageConfig::Sqlite { .. } => "sqlite",
+            StorageConfig::Postgres { .. } => "postgres",
+        }
+    }
+}
+
+/// Creates the configured storage, failing if the backend is unreachable.
+pub async fn create_storage(
+    config: &StorageConfig,
+) -> Result<Arc<dyn Storage>, StorageConfigError> {
+    let backend = config.backend();
+
+    match config {
+        StorageConfig::Memory => Ok(Arc::new(MemoryRepository::new())),
+        #[cfg(feature = "redis")]
+        StorageConfig::Redis { url } => {
+            let storage = crate::storage::RedisStorage::new(url)
+                .await
+                .map_err(|source| StorageConfigError::Connection { backend, source })?;
+            Ok(Arc::new(storage))
+        }
+        #[cfg(feature = "sqlite")]
+        StorageConfig::Sqlite { url } => {
+            let storage = crate::storage::SqliteStorage::new(url)
+                .await
+                .map_err(|source| StorageConfigError::Connection { backend, source })?;
+            Ok(Arc::new(storage))
+        }
+        #[cfg(feature = "postgres")]
+        StorageConfig::Postgres { url } => {
+            let storage = crate::storage::PostgresStorage::new(url)
+                .await
+                .map_err(|source| StorageConfigError::Connection { backend, source })?;
+            Ok(Arc::new(storage))
+        }
+        #[allow(unreachable_patterns)]
+        _ => Err(StorageConfigError::FeatureDisabled(backend)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config_from(vars: &[(&str, &str)]) -> Result<StorageConfig, StorageConfigError> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        StorageConfig::from_vars(|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn test_default_backend() {
+        assert_eq!(config_from(&[]), Ok(StorageConfig::Memory));
+        assert_eq!(
+            config_from(&[("REDIS_URL", "redis://localhost")]),
+            Ok(StorageConfig::Redis {
+                url: "redis://localhost".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_explicit_backend() {
+        assert_eq!(
+            config_from(&[("STORAGE_BACKEND", "memory"), ("REDIS_URL", "redis://x")]),
+            Ok(StorageConfig::Memory)
+        );
+        assert_eq!(
+            config_from(&[("STORAGE_BACKEND", "SQLite")]),
+            Ok(StorageConfig::Sqlite {
+                url: DEFAULT_SQLITE_URL.to_string()
+            })
+        );
+        assert_eq!(
+            config_from(&[
+                ("STORAGE_BACKEND", "postgres"),
+                ("DATABASE_URL", "postgres://localhost/db")
+            ]),
+            Ok(StorageConfig::Postgres {
+                url: "postgres://localhost/db".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert_eq!(
+            config_from(&[("STORAGE_BACKEND", "redis")]),
+            Err(StorageConfigError::MissingSetting("REDIS_URL"))
+        );
+        assert_eq!(
+            config_from(&[("STORAGE_BACKEND", "mongo")]),
+            Err(StorageConfigError::UnknownBackend("mongo".to_string()))
+        );
+    }
+
+    #[cfg(feature = "redis")]
+    #[tokio::test]
+    async fn test_invalid_redis_url() {
+        let config = StorageConfig::Redis {
+            url: "not a url".to_string(),
+        };
+        let result = create_storage(&config).await;
+        assert!(matches!(
+            result,
+            Err(StorageConfigError::Connection {
+                backend: "redis",
+                ..
+            })
+        ));
+    }
+}
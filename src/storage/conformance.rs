@@ -0,0 +1,1078 @@
+//! Behaviour every [`Storage`] implementation has to provide.
+//!
+//! Backends run the suite with [`storage_conformance_tests`], passing an async
+//! function that returns `None` when the backend is not available.
+//! Keys are randomized so the suite can run against shared servers.
+
+use crate::storage::{
+    CertificateRecord, CertificateRepository, CertificateStatus, ClaimKind, ClaimRecord,
+    ClaimRepository, ClaimStatus, CouponCampaign, CouponRepository, CouponUpdate,
+    LeaderboardRepository, ProfileCredentialRepository, ProfileRepository, ProfileSession,
+    RankedRecord, Redemption, RepositoryError, ReviewRepository, Storage,
+    WindowedCounterRepository,
+};
+use chrono::{Duration, Timelike, Utc};
+use konnektoren_core::challenges::{PerformanceRecord, Review};
+use konnektoren_core::prelude::{Coupon, PlayerProfile};
+#[cfg(feature = "chat")]
+use yew_chat::prelude::{Message, MessageReceiver, MessageSender};
+
+pub const PERFORMANCE_RECORDS_LIMIT: usize = 10;
+
+fn unique(prefix: &str) -> String {
+    format!("{}_{}", prefix, uuid::Uuid::new_v4().simple())
+}
+
+fn unique_id() -> i64 {
+    (uuid::Uuid::new_v4().as_u64_pair().0 >> 1) as i64
+}
+
+pub async fn profiles<S: Storage>(storage: &S) {
+    let mut profile1 = PlayerProfile::new(unique("profile"));
+    let profile2 = PlayerProfile::new(unique("profile"));
+
+    ProfileRepository::save(storage, profile1.clone())
+        .await
+        .unwrap();
+    ProfileRepository::save(storage, profile2.clone())
+        .await
+        .unwrap();
+
+    let fetched = ProfileRepository::fetch(storage, profile1.id.clone())
+        .await
+        .unwrap();
+    assert_eq!(fetched, profile1);
+
+    let profiles = ProfileRepository::fetch_all(storage).await.unwrap();
+    assert!(profiles.contains(&profile1));
+    assert!(profiles.contains(&profile2));
+
+    // Saving again replaces the stored profile
+    profile1.name = "updated".to_string();
+    ProfileRepository::save(storage, profile1.clone())
+        .await
+        .unwrap();
+    let fetched = ProfileRepository::fetch(storage, profile1.id.clone())
+        .await
+        .unwrap();
+    assert_eq!(fetched.name, "updated");
+
+    let missing = ProfileRepository::fetch(storage, unique("missing")).await;
+    assert!(matches!(missing, Err(RepositoryError::NotFound(_))));
+}
+
+pub async fn profile_credentials<S: Storage>(storage: &S) {
+    let profile_id = unique("profile");
+
+    assert_eq!(storage.fetch_credential(&profile_id).await.unwrap(), None);
+    storage
+        .create_credential(&profile_id, "hash1")
+        .await
+        .unwrap();
+    assert_eq!(
+        storage.fetch_credential(&profile_id).await.unwrap(),
+        Some("hash1".to_string())
+    );
+
+    // The first owner keeps the profile
+    assert_eq!(
+        storage.create_credential(&profile_id, "hash2").await,
+        Err(RepositoryError::AlreadyExists(profile_id.clone()))
+    );
+    assert_eq!(
+        storage.fetch_credential(&profile_id).await.unwrap(),
+        Some("hash1".to_string())
+    );
+
+    let token_hash = unique("session");
+    assert_eq!(storage.fetch_session(&token_hash).await.unwrap(), None);
+    let session = ProfileSession {
+        profile_id: profile_id.clone(),
+        // Whole seconds, the SQL backends store unix timestamps
+        expires_at: Utc::now().with_nanosecond(0).unwrap() + Duration::days(1),
+    };
+    storage
+        .create_session(&token_hash, session.clone())
+        .await
+        .unwrap();
+    assert_eq!(
+        storage.fetch_session(&token_hash).await.unwrap(),
+        Some(session)
+    );
+
+    let address = unique("0:wallet");
+    assert_eq!(storage.fetch_wallet_profile(&address).await.unwrap(), None);
+    storage.link_wallet(&address, &profile_id).await.unwrap();
+    assert_eq!(
+        storage.link_wallet(&address, "other").await,
+        Err(RepositoryError::AlreadyExists(address.clone()))
+    );
+    assert_eq!(
+        storage.fetch_wallet_profile(&address).await.unwrap(),
+        Some(profile_id)
+    );
+
+    let payload_hash = unique("payload");
+    let expires_at = Utc::now() + Duration::minutes(15);
+    storage
+        .consume_payload(&payload_hash, expires_at)
+        .await
+        .unwrap();
+    assert_eq!(
+        storage.consume_payload(&payload_hash, expires_at).await,
+        Err(RepositoryError::AlreadyExists(payload_hash))
+    );
+    storage
+        .consume_payload(&unique("payload"), expires_at)
+        .await
+        .unwrap();
+}
+
+pub async fn leaderboard<S: Storage>(storage: &S) {
+    let namespace = unique("leaderboard");
+    let other_namespace = unique("leaderboard");
+    let record = |name: &str, percentage: u8| PerformanceRecord {
+        profile_name: name.to_string(),
+        performance_percentage: percentage,
+        ..Default::default()
+    };
+
+    for i in 0..PERFORMANCE_RECORDS_LIMIT {
+        storage
+            .add_performance_record(
+                &namespace,
+                &i.to_string(),
+                record(&i.to_string(), i as u8 + 1),
+                PERFORMANCE_RECORDS_LIMIT,
+            )
+            .await
+            .unwrap();
+    }
+
+    // A record worse than all others does not make it onto a full board
+    let overflow = record("overflow", 0);
+    let result = storage
+        .add_performance_record(
+            &namespace,
+            "overflow",
+            overflow.clone(),
+            PERFORMANCE_RECORDS_LIMIT,
+        )
+        .await;
+    assert_eq!(
+        result,
+        Err(RepositoryError::LimitReached(PERFORMANCE_RECORDS_LIMIT))
+    );
+
+    // A full board does not affect other namespaces
+    storage
+        .add_performance_record(
+            &other_namespace,
+            "overflow",
+            overflow.clone(),
+            PERFORMANCE_RECORDS_LIMIT,
+        )
+        .await
+        .unwrap();
+    let records = storage
+        .fetch_performance_records(&other_namespace)
+        .await
+        .unwrap();
+    assert_eq!(records, vec![overflow.clone()]);
+
+    // The capacity is given per call, better records replace the worst
+    let best = record("best", 100);
+    storage
+        .add_performance_record(&other_namespace, "best", best.clone(), 1)
+        .await
+        .unwrap();
+    let records = storage
+        .fetch_performance_records(&other_namespace)
+        .await
+        .unwrap();
+    assert_eq!(records, vec![best.clone()]);
+    let result = storage
+        .add_performance_record(&other_namespace, "overflow", overflow.clone(), 1)
+        .await;
+    assert_eq!(result, Err(RepositoryError::LimitReached(1)));
+
+    storage
+        .add_performance_record(&namespace, "best", best.clone(), PERFORMANCE_RECORDS_LIMIT)
+        .await
+        .unwrap();
+    let records = storage.fetch_performance_records(&namespace).await.unwrap();
+    assert_eq!(records.len(), PERFORMANCE_RECORDS_LIMIT);
+    assert!(records.contains(&best));
+    assert!(!records.iter().any(|record| record.profile_name == "0"));
+
+    let removed = records[0].clone();
+    storage
+        .remove_performance_record(&namespace, removed.clone())
+        .await
+        .unwrap();
+    let records = storage.fetch_performance_records(&namespace).await.unwrap();
+    assert_eq!(records.len(), PERFORMANCE_RECORDS_LIMIT - 1);
+    assert!(!records.contains(&removed));
+
+    let result = storage.remove_performance_record(&namespace, removed).await;
+    assert!(matches!(result, Err(RepositoryError::NotFound(_))));
+
+    // The freed slot can be used again
+    storage
+        .add_performance_record(&namespace, "overflow", overflow, PERFORMANCE_RECORDS_LIMIT)
+        .await
+        .unwrap();
+
+    // A lowered capacity trims the board to the best records
+    let result = storage
+        .add_performance_record(&namespace, "middle", record("middle", 5), 3)
+        .await;
+    assert_eq!(result, Err(RepositoryError::LimitReached(3)));
+    let records = storage.fetch_performance_records(&namespace).await.unwrap();
+    assert_eq!(records.len(), 3);
+    assert!(records
+        .iter()
+        .all(|record| record.performance_percentage > 5));
+}
+
+pub async fn player_records<S: Storage>(storage: &S) {
+    let namespace = unique("players");
+    let record = |name: &str, percentage: u8| PerformanceRecord {
+        profile_name: name.to_string(),
+        performance_percentage: percentage,
+        ..Default::default()
+    };
+
+    let first = record("Alice", 50);
+    storage
+        .add_performance_record(
+            &namespace,
+            "alice",
+            first.clone(),
+            PERFORMANCE_RECORDS_LIMIT,
+        )
+        .await
+        .unwrap();
+
+    // Records not better than the one of the player are rejected
+    for rejected in [record("Alice", 40), first.clone()] {
+        let result = storage
+            .add_performance_record(&namespace, "alice", rejected, PERFORMANCE_RECORDS_LIMIT)
+            .await;
+        assert_eq!(
+            result,
+            Err(RepositoryError::AlreadyExists("alice".to_string()))
+        );
+    }
+
+    // A better record replaces it, whatever the name on the record
+    let better = record("Alice Renamed", 80);
+    storage
+        .add_performance_record(
+            &namespace,
+            "alice",
+            better.clone(),
+            PERFORMANCE_RECORDS_LIMIT,
+        )
+        .await
+        .unwrap();
+    let other = record("Alice", 60);
+    storage
+        .add_performance_record(&namespace, "bob", other.clone(), PERFORMANCE_RECORDS_LIMIT)
+        .await
+        .unwrap();
+
+    let mut records = storage.fetch_performance_records(&namespace).await.unwrap();
+    records.sort();
+    assert_eq!(records, vec![better, other]);
+}
+
+pub async fn submissions<S: Storage>(storage: &S) {
+    let namespace = unique("submissions");
+    let other_namespace = unique("submissions");
+    assert!(storage
+        .fetch_submissions(&namespace)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let submissions: Vec<PerformanceRecord> =
+        [("alice", 50), ("bob", 70), ("alice", 90), ("alice", 60)]
+            .iter()
+            .map(|(name, percentage)| PerformanceRecord {
+                profile_name: name.to_string(),
+                performance_percentage: *percentage,
+                ..Default::default()
+            })
+            .collect();
+    let alice = unique("alice");
+    let profile_ids = [Some(alice.as_str()), None, Some(alice.as_str()), None];
+    for (submission, profile_id) in submissions.iter().zip(profile_ids) {
+        storage
+            .record_submission(
+                &namespace,
+                &submission.profile_name,
+                profile_id,
+                submission.clone(),
+            )
+            .await
+            .unwrap();
+    }
+    storage
+        .record_submission(
+            &other_namespace,
+            "alice",
+            Some(&alice),
+            submissions[1].clone(),
+        )
+        .await
+        .unwrap();
+    // Submissions are kept beside the board, whatever its capacity
+    storage
+        .add_performance_record(&namespace, "alice", submissions[0].clone(), 0)
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        storage.fetch_submissions(&namespace).await.unwrap(),
+        submissions
+    );
+    assert_eq!(
+        storage.fetch_profile_submissions(&alice).await.unwrap(),
+        vec![
+            submissions[0].clone(),
+            submissions[2].clone(),
+            submissions[1].clone()
+        ]
+    );
+    assert!(storage
+        .fetch_profile_submissions(&unique("bob"))
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Only better records replace the best one of a player
+    assert_eq!(
+        storage
+            .fetch_ranked_record(&namespace, "alice")
+            .await
+            .unwrap(),
+        Some(RankedRecord {
+            record: submissions[2].clone(),
+            rank: 1,
+            total: 2
+        })
+    );
+    assert_eq!(
+        storage
+            .fetch_ranked_record(&namespace, "bob")
+            .await
+            .unwrap(),
+        Some(RankedRecord {
+            record: submissions[1].clone(),
+            rank: 2,
+            total: 2
+        })
+    );
+    // Equal records share their rank
+    storage
+        .record_submission(&other_namespace, "bob", None, submissions[1].clone())
+        .await
+        .unwrap();
+    assert_eq!(
+        storage
+            .fetch_ranked_record(&other_namespace, "alice")
+            .await
+            .unwrap()
+            .map(|ranked| (ranked.rank, ranked.total)),
+        Some((1, 2))
+    );
+    assert_eq!(
+        storage
+            .fetch_ranked_record(&namespace, "carol")
+            .await
+            .unwrap(),
+        None
+    );
+}
+
+pub async fn reviews<S: Storage>(storage: &S) {
+    let challenge_id = unique("challenge");
+    let review1 = Review {
+        challenge_id: challenge_id.clone(),
+        rating: 5,
+        comment: Some("Great challenge!".to_string()),
+    };
+    let review2 = Review {
+        challenge_id: challenge_id.clone(),
+        rating: 3,
+        comment: None,
+    };
+    let other_review = Review {
+        challenge_id: unique("challenge"),
+        rating: 1,
+        comment: None,
+    };
+
+    storage.store_review(review1.clone()).await.unwrap();
+    storage.store_review(review2.clone()).await.unwrap();
+    storage.store_review(other_review.clone()).await.unwrap();
+
+    let reviews = storage.fetch_reviews(&challenge_id).await.unwrap();
+    assert_eq!(reviews.len(), 2);
+    assert!(reviews.contains(&review1));
+    assert!(reviews.contains(&review2));
+
+    let average_rating = storage.fetch_average_rating(&challenge_id).await.unwrap();
+    assert_eq!(average_rating, 4.0);
+
+    let all_reviews = storage.fetch_all_reviews().await.unwrap();
+    assert!(all_reviews.contains(&review1));
+    assert!(all_reviews.contains(&review2));
+    assert!(all_reviews.contains(&other_review));
+}
+
+pub async fn coupons<S: Storage>(storage: &S) {
+    let mut coupon1 = Coupon::new(
+        unique("COUPON"),
+        vec!["challenge1".to_string()],
+        1,
+        Utc::now() + Duration::days(7),
+    );
+    let coupon2 = Coupon::new(
+        unique("COUPON"),
+        vec!["challenge2".to_string()],
+        2,
+        Utc::now() + Duration::days(7),
+    );
+
+    CouponRepository::save(storage, coupon1.clone())
+        .await
+        .unwrap();
+    CouponRepository::save(storage, coupon2.clone())
+        .await
+        .unwrap();
+
+    let fetched = CouponRepository::fetch(storage, &coupon1.code)
+        .await
+        .unwrap();
+    assert_eq!(fetched, Some(coupon1.clone()));
+
+    let coupons = CouponRepository::fetch_all(storage).await.unwrap();
+    assert!(coupons.contains(&coupon1));
+    assert!(coupons.contains(&coupon2));
+
+    // Saving again replaces the stored coupon
+    coupon1.uses_remaining = 5;
+    CouponRepository::save(storage, coupon1.clone())
+        .await
+        .unwrap();
+    let fetched = CouponRepository::fetch(storage, &coupon1.code)
+        .await
+        .unwrap();
+    assert_eq!(fetched, Some(coupon1));
+
+    let missing = CouponRepository::fetch(storage, &unique("MISSING"))
+        .await
+        .unwrap();
+    assert_eq!(missing, None);
+    let campaign = CouponCampaign {
+        name: unique("campaign"),
+        codes: vec![coupon1.code.clone(), coupon2.code.clone()],
+        created_at: Utc::now(),
+    };
+    storage.save_campaign(campaign.clone()).await.unwrap();
+    let fetched = storage.fetch_campaign(&campaign.name).await.unwrap();
+    assert_eq!(fetched, Some(campaign));
+    let missing = storage.fetch_campaign(&unique("campaign")).await.unwrap();
+    assert_eq!(missing, None);
+
+    // Creating reserves the name, deleting releases it
+    let result = storage.create_campaign(campaign.clone()).await;
+    assert_eq!(
+        result,
+        Err(RepositoryError::AlreadyExists(campaign.name.clone()))
+    );
+    let reserved = CouponCampaign {
+        name: unique("campaign"),
+        codes: Vec::new(),
+        created_at: Utc::now(),
+    };
+    storage.create_campaign(reserved.clone()).await.unwrap();
+    storage.delete_campaign(&reserved.name).await.unwrap();
+    let missing = storage.fetch_campaign(&reserved.name).await.unwrap();
+    assert_eq!(missing, None);
+    let result = storage.delete_campaign(&reserved.name).await;
+    assert_eq!(
+        result,
+        Err(RepositoryError::NotFound(reserved.name.clone()))
+    );
+    storage.create_campaign(reserved).await.unwrap();
+}
+
+pub async fn coupon_lifecycle<S: Storage>(storage: &S) {
+    let coupon = Coupon::new(
+        unique("COUPON"),
+        vec!["challenge1".to_string()],
+        1,
+        Utc::now() + Duration::days(7),
+    );
+
+    storage.create(coupon.clone()).await.unwrap();
+    let duplicate = storage.create(coupon.clone()).await;
+    assert_eq!(
+        duplicate,
+        Err(RepositoryError::AlreadyExists(coupon.code.clone()))
+    );
+    assert!(storage.is_active(&coupon.code).await.unwrap());
+
+    let update = CouponUpdate {
+        uses_remaining: Some(3),
+        challenge_ids: Some(vec!["challenge1".to_string(), "challenge2".to_string()]),
+        ..Default::default()
+    };
+    let updated = storage.update(&coupon.code, &update).await.unwrap();
+    assert_eq!(updated.uses_remaining, 3);
+    let fetched = CouponRepository::fetch(storage, &coupon.code)
+        .await
+        .unwrap();
+    assert_eq!(fetched, Some(updated));
+
+    // Inactive coupons cannot be redeemed until they are activated again
+    let deactivate = CouponUpdate {
+        active: Some(false),
+        ..Default::default()
+    };
+    storage.update(&coupon.code, &deactivate).await.unwrap();
+    assert!(!storage.is_active(&coupon.code).await.unwrap());
+    let redemption = storage
+        .redeem(&coupon.code, "challenge2", "alice")
+        .await
+        .unwrap();
+    assert!(matches!(redemption, Redemption::Rejected));
+
+    let activate = CouponUpdate {
+        active: Some(true),
+        ..Default::default()
+    };
+    storage.update(&coupon.code, &activate).await.unwrap();
+    let redemption = storage
+        .redeem(&coupon.code, "challenge2", "alice")
+        .await
+        .unwrap();
+    assert!(matches!(redemption, Redemption::Redeemed(..)));
+
+    storage.delete(&coupon.code).await.unwrap();
+    let fetched = CouponRepository::fetch(storage, &coupon.code)
+        .await
+        .unwrap();
+    assert_eq!(fetched, None);
+    assert!(storage
+        .fetch_redemptions(&coupon.code)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(matches!(
+        storage.delete(&coupon.code).await,
+        Err(RepositoryError::NotFound(_))
+    ));
+    assert!(matches!(
+        storage.update(&coupon.code, &activate).await,
+        Err(RepositoryError::NotFound(_))
+    ));
+    assert!(matches!(
+        storage.is_active(&coupon.code).await,
+        Err(RepositoryError::NotFound(_))
+    ));
+}
+
+pub async fn coupon_redemption<S: Storage>(storage: &S) {
+    let coupon = Coupon::new(
+        unique("COUPON"),
+        vec!["challenge1".to_string()],
+        3,
+        Utc::now() + Duration::days(7),
+    );
+    let expired = Coupon::new(
+        unique("COUPON"),
+        vec!["challenge1".to_string()],
+        1,
+        Utc::now() - Duration::days(1),
+    );
+    CouponRepository::save(storage, coupon.clone())
+        .await
+        .unwrap();
+    CouponRepository::save(storage, expired.clone())
+        .await
+        .unwrap();
+
+    let redemption = storage
+        .redeem(&coupon.code, "challenge1", "alice")
+        .await
+        .unwrap();
+    assert!(matches!(redemption, Redemption::Redeemed(..)));
+
+    // The same caller cannot redeem twice
+    let redemption = storage
+        .redeem(&coupon.code, "challenge1", "alice")
+        .await
+        .unwrap();
+    assert!(matches!(redemption, Redemption::Failed(_)));
+
+    let redemption = storage
+        .redeem(&coupon.code, "challenge2", "bob")
+        .await
+        .unwrap();
+    assert!(matches!(redemption, Redemption::Rejected));
+
+    let redemption = storage
+        .redeem(&coupon.code, "challenge1", "bob")
+        .await
+        .unwrap();
+    assert!(matches!(redemption, Redemption::Redeemed(..)));
+
+    let Redemption::Redeemed(redeemed, _) = storage
+        .redeem(&coupon.code, "challenge1", "carol")
+        .await
+        .unwrap()
+    else {
+        panic!("last use should be redeemed");
+    };
+    assert_eq!(redeemed.uses_remaining, 0);
+    let fetched = CouponRepository::fetch(storage, &coupon.code)
+        .await
+        .unwrap();
+    assert_eq!(fetched, Some(redeemed));
+
+    // Used up
+    let redemption = storage
+        .redeem(&coupon.code, "challenge1", "dave")
+        .await
+        .unwrap();
+    assert!(matches!(redemption, Redemption::Rejected));
+
+    let redemptions = storage.fetch_redemptions(&coupon.code).await.unwrap();
+    let redeemed_by: Vec<&str> = redemptions
+        .iter()
+        .map(|redemption| redemption.redeemed_by.as_str())
+        .collect();
+    assert_eq!(redeemed_by, vec!["alice", "bob", "carol"]);
+    assert!(redemptions
+        .iter()
+        .all(|redemption| redemption.challenge_id == "challenge1"));
+
+    let redemption = storage
+        .redeem(&expired.code, "challenge1", "alice")
+        .await
+        .unwrap();
+    assert!(matches!(redemption, Redemption::Rejected));
+    assert!(storage
+        .fetch_redemptions(&expired.code)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let redemption = storage
+        .redeem(&unique("MISSING"), "challenge1", "alice")
+        .await
+        .unwrap();
+    assert!(matches!(redemption, Redemption::Rejected));
+}
+
+pub async fn user_redemptions<S: Storage>(storage: &S) {
+    let alice = unique("alice");
+    let first = Coupon::new(
+        unique("COUPON"),
+        vec!["challenge1".to_string()],
+        3,
+        Utc::now() + Duration::days(7),
+    );
+    let second = Coupon::new(
+        unique("COUPON"),
+        vec!["challenge2".to_string()],
+        3,
+        Utc::now() + Duration::days(7),
+    );
+    CouponRepository::save(storage, first.clone())
+        .await
+        .unwrap();
+    CouponRepository::save(storage, second.clone())
+        .await
+        .unwrap();
+
+    assert!(storage
+        .fetch_user_redemptions(&alice)
+        .await
+        .unwrap()
+        .is_empty());
+    storage
+        .redeem(&first.code, "challenge1", &alice)
+        .await
+        .unwrap();
+    storage
+        .redeem(&second.code, "challenge2", &alice)
+        .await
+        .unwrap();
+    storage
+        .redeem(&first.code, "challenge1", &unique("bob"))
+        .await
+        .unwrap();
+
+    let mut challenge_ids: Vec<String> = storage
+        .fetch_user_redemptions(&alice)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|redemption| redemption.challenge_id)
+        .collect();
+    challenge_ids.sort();
+    assert_eq!(challenge_ids, vec!["challenge1", "challenge2"]);
+
+    // Deleting a coupon removes its redemptions
+    storage.delete(&first.code).await.unwrap();
+    let redemptions = storage.fetch_user_redemptions(&alice).await.unwrap();
+    assert_eq!(redemptions.len(), 1);
+    assert_eq!(redemptions[0].challenge_id, "challenge2");
+}
+
+#[cfg(feature = "chat")]
+pub async fn chat<S: Storage>(storage: &S) {
+    let channel = unique("channel");
+    let message1 = Message {
+        sender: "alice".to_string(),
+        content: "Hello".to_string(),
+        timestamp: 1,
+    };
+    let message2 = Message {
+        sender: "bob".to_string(),
+        content: "Hi".to_string(),
+        timestamp: 2,
+    };
+
+    storage
+        .send_message(&channel, message1.clone())
+        .await
+        .unwrap();
+    storage
+        .send_message(&channel, message2.clone())
+        .await
+        .unwrap();
+
+    let messages = storage.receive_messages(&channel).await.unwrap();
+    assert_eq!(messages.len(), 2);
+    assert!(messages.contains(&message1));
+    assert!(messages.contains(&message2));
+
+    let messages = storage.receive_messages(&unique("channel")).await.unwrap();
+    assert!(messages.is_empty());
+}
+
+pub async fn claims<S: Storage>(storage: &S) {
+    let id = unique_id();
+    let alice = unique("alice");
+    assert_eq!(storage.fetch_claim(id).await.unwrap(), None);
+    assert!(storage.fetch_user_claims(&alice).await.unwrap().is_empty());
+
+    let claim = ClaimRecord::new(
+        id,
+        ClaimKind::Transfer,
+        alice.clone(),
+        "address".to_string(),
+        5,
+    );
+    let missing = storage.update_claim(claim.clone()).await;
+    assert!(matches!(missing, Err(RepositoryError::NotFound(_))));
+
+    storage.create_claim(claim.clone()).await.unwrap();
+    assert_eq!(storage.fetch_claim(id).await.unwrap(), Some(claim.clone()));
+
+    let duplicate = storage.create_claim(claim.clone()).await;
+    assert!(matches!(duplicate, Err(RepositoryError::AlreadyExists(_))));
+
+    let sent = ClaimRecord {
+        status: ClaimStatus::Sent,
+        tx_hash: Some("hash".to_string()),
+        ..claim
+    };
+    storage.update_claim(sent.clone()).await.unwrap();
+    assert_eq!(storage.fetch_claim(id).await.unwrap(), Some(sent.clone()));
+
+    let other = ClaimRecord::new(
+        unique_id(),
+        ClaimKind::SignedMessage,
+        unique("bob"),
+        "address".to_string(),
+        1,
+    );
+    storage.create_claim(other).await.unwrap();
+    assert_eq!(storage.fetch_user_claims(&alice).await.unwrap(), vec![sent]);
+}
+
+/// Ids of `ids` in the claim queue, other tests may queue claims concurrently.
+async fn queued_claim_ids<S: Storage>(storage: &S, ids: &[i64]) -> Vec<i64> {
+    let mut queued: Vec<i64> = storage
+        .fetch_queued_claims()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|claim| claim.id)
+        .filter(|id| ids.contains(id))
+        .collect();
+    queued.sort();
+    queued
+}
+
+pub async fn claim_queue<S: Storage>(storage: &S) {
+    let user = unique("alice");
+    let queued = ClaimRecord {
+        status: ClaimStatus::Queued,
+        ..ClaimRecord::new(
+            unique_id(),
+            ClaimKind::Transfer,
+            user.clone(),
+            "address".to_string(),
+            1,
+        )
+    };
+    let pending = ClaimRecord::new(
+        unique_id(),
+        ClaimKind::SignedMessage,
+        user,
+        "address".to_string(),
+        1,
+    );
+    let ids = [queued.id, pending.id];
+    storage.create_claim(queued.clone()).await.unwrap();
+    storage.create_claim(pending.clone()).await.unwrap();
+    assert_eq!(queued_claim_ids(storage, &ids).await, vec![queued.id]);
+
+    let submitted = ClaimRecord {
+        status: ClaimStatus::Submitted,
+        attempts: 1,
+        seqno: Some(7),
+        tx_hash: Some("hash".to_string()),
+        ..queued
+    };
+    storage.update_claim(submitted.clone()).await.unwrap();
+    let stored = storage.fetch_queued_claims().await.unwrap();
+    assert!(stored.contains(&submitted));
+
+    storage
+        .update_claim(ClaimRecord {
+            status: ClaimStatus::Sent,
+            ..submitted.clone()
+        })
+        .await
+        .unwrap();
+    storage
+        .update_claim(ClaimRecord {
+            status: ClaimStatus::Queued,
+            ..pending.clone()
+        })
+        .await
+        .unwrap();
+    assert_eq!(queued_claim_ids(storage, &ids).await, vec![pending.id]);
+}
+
+pub async fn certificates<S: Storage>(storage: &S) {
+    let id = unique("certificate");
+    assert_eq!(storage.fetch_certificate(&id).await.unwrap(), None);
+
+    let now = Utc::now();
+    let certificate = CertificateRecord {
+        id: id.clone(),
+        profile_id: unique("alice"),
+        profile_name: "Alice".to_string(),
+        game_path_id: "konnektoren".to_string(),
+        owner: "address".to_string(),
+        performance_percentage: 90,
+        total_challenges: 3,
+        completed_at: now,
+        collection: "collection".to_string(),
+        item_index: None,
+        metadata_url: format!("https://example.com/api/v1/certificates/{}", id),
+        image: None,
+        status: CertificateStatus::Queued,
+        claim_id: Some(-1),
+        raw_transaction: None,
+        tx_hash: None,
+        created_at: now,
+        updated_at: now,
+    };
+    let missing = storage.update_certificate(certificate.clone()).await;
+    assert!(matches!(missing, Err(RepositoryError::NotFound(_))));
+
+    storage
+        .create_certificate(certificate.clone())
+        .await
+        .unwrap();
+    assert_eq!(
+        storage.fetch_certificate(&id).await.unwrap(),
+        Some(certificate.clone())
+    );
+    let duplicate = storage.create_certificate(certificate.clone()).await;
+    assert!(matches!(duplicate, Err(RepositoryError::AlreadyExists(_))));
+
+    let sent = CertificateRecord {
+        status: CertificateStatus::Sent,
+        item_index: Some(3),
+        raw_transaction: Some("message".to_string()),
+        tx_hash: Some("hash".to_string()),
+        ..certificate
+    };
+    storage.update_certificate(sent.clone()).await.unwrap();
+    assert_eq!(storage.fetch_certificate(&id).await.unwrap(), Some(sent));
+
+    let collection = unique("collection");
+    assert_eq!(storage.reserve_item_index(&collection, 0).await.unwrap(), 0);
+    assert_eq!(storage.reserve_item_index(&collection, 0).await.unwrap(), 1);
+    // Items minted elsewhere move the next index on, reserved ones are skipped
+    assert_eq!(storage.reserve_item_index(&collection, 5).await.unwrap(), 5);
+    assert_eq!(storage.reserve_item_index(&collection, 2).await.unwrap(), 6);
+    let other = unique("collection");
+    assert_eq!(storage.reserve_item_index(&other, 0).await.unwrap(), 0);
+}
+
+pub async fn windowed_counter<S: Storage>(storage: &S) {
+    let namespace = unique("presence");
+
+    assert_eq!(storage.get_active_count(&namespace).await.unwrap(), 0);
+    assert_eq!(storage.record_presence(&namespace).await.unwrap(), 1);
+    storage.record_presence(&namespace).await.unwrap();
+    assert_eq!(storage.record_presence(&namespace).await.unwrap(), 3);
+    assert_eq!(storage.get_active_count(&namespace).await.unwrap(), 3);
+
+    let other_namespace = unique("presence");
+    assert_eq!(storage.get_active_count(&other_namespace).await.unwrap(), 0);
+}
+
+/// Generates one test per conformance check for a storage backend.
+///
+/// `$create` is an async function returning `Option<impl Storage>`;
+/// the tests pass without checking anything when it returns `None`.
+macro_rules! storage_conformance_tests {
+    ($create:path) => {
+        #[tokio::test]
+        async fn conformance_profiles() {
+            let Some(storage) = $create().await else {
+                return;
+            };
+            $crate::storage::conformance::profiles(&storage).await;
+        }
+
+        #[tokio::test]
+        async fn conformance_profile_credentials() {
+            let Some(storage) = $create().await else {
+                return;
+            };
+            $crate::storage::conformance::profile_credentials(&storage).await;
+        }
+
+        #[tokio::test]
+        async fn conformance_leaderboard() {
+            let Some(storage) = $create().await else {
+                return;
+            };
+            $crate::storage::conformance::leaderboard(&storage).await;
+        }
+
+        #[tokio::test]
+        async fn conformance_player_records() {
+            let Some(storage) = $create().await else {
+                return;
+            };
+            $crate::storage::conformance::player_records(&storage).await;
+        }
+
+        #[tokio::test]
+        async fn conformance_submissions() {
+            let Some(storage) = $create().await else {
+                return;
+            };
+            $crate::storage::conformance::submissions(&storage).await;
+        }
+
+        #[tokio::test]
+        async fn conformance_reviews() {
+            let Some(storage) = $create().await else {
+                return;
+            };
+            $crate::storage::conformance::reviews(&storage).await;
+        }
+
+        #[tokio::test]
+        async fn conformance_coupons() {
+            let Some(storage) = $create().await else {
+                return;
+            };
+            $crate::storage::conformance::coupons(&storage).await;
+        }
+
+        #[tokio::test]
+        async fn conformance_coupon_lifecycle() {
+            let Some(storage) = $create().await else {
+                return;
+            };
+            $crate::storage::conformance::coupon_lifecycle(&storage).await;
+        }
+
+        #[tokio::test]
+        async fn conformance_coupon_redemption() {
+            let Some(storage) = $create().await else {
+                return;
+            };
+            $crate::storage::conformance::coupon_redemption(&storage).await;
+        }
+
+        #[cfg(feature = "chat")]
+        #[tokio::test]
+        async fn conformance_chat() {
+            let Some(storage) = $create().await else {
+                return;
+            };
+            $crate::storage::conformance::chat(&storage).await;
+        }
+
+        #[tokio::test]
+        async fn conformance_user_redemptions() {
+            let Some(storage) = $create().await else {
+                return;
+            };
+            $crate::storage::conformance::user_redemptions(&storage).await;
+        }
+
+        #[tokio::test]
+        async fn conformance_claims() {
+            let Some(storage) = $create().await else {
+                return;
+            };
+            $crate::storage::conformance::claims(&storage).await;
+        }
+
+        #[tokio::test]
+        async fn conformance_claim_queue() {
+            let Some(storage) = $create().await else {
+                return;
+            };
+            $crate::storage::conformance::claim_queue(&storage).await;
+        }
+
+        #[tokio::test]
+        async fn conformance_certificates() {
+            let Some(storage) = $create().await else {
+                return;
+            };
+            $crate::storage::conformance::certificates(&storage).await;
+        }
+
+        #[tokio::test]
+        async fn conformance_windowed_counter() {
+            let Some(storage) = $create().await else {
+                return;
+            };
+            $crate::storage::conformance::windowed_counter(&storage).await;
+        }
+    };
+}
+
+pub(crate) use storage_conformance_tests;
@@ -1,10 +1,150 @@
 use crate::storage::error::RepositoryError;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use konnektoren_core::marketplace::Coupon;
+use konnektoren_core::prelude::CouponRedemptionError;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A single use of a coupon.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CouponRedemption {
+    /// Profile or session id of the caller who redeemed the coupon.
+    pub redeemed_by: String,
+    pub challenge_id: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub redeemed_at: DateTime<Utc>,
+}
+
+/// Coupons generated together, e.g. for a classroom.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CouponCampaign {
+    pub name: String,
+    pub codes: Vec<String>,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Changes to a stored coupon, fields left `None` are kept.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CouponUpdate {
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub expiration_date: Option<DateTime<Utc>>,
+    pub uses_remaining: Option<u32>,
+    pub challenge_ids: Option<Vec<String>>,
+    /// Inactive coupons can neither be validated nor redeemed.
+    pub active: Option<bool>,
+}
+
+impl CouponUpdate {
+    pub fn apply(&self, mut coupon: Coupon) -> Coupon {
+        if let Some(expiration_date) = self.expiration_date {
+            coupon.expiration_date = expiration_date;
+        }
+        if let Some(uses_remaining) = self.uses_remaining {
+            coupon.uses_remaining = uses_remaining;
+        }
+        if let Some(challenge_ids) = &self.challenge_ids {
+            coupon.challenge_ids = challenge_ids.clone();
+        }
+        coupon
+    }
+}
+
+/// Outcome of [`CouponRepository::redeem`].
+#[derive(Debug)]
+pub enum Redemption {
+    /// One use was redeemed, holds the updated coupon and the recorded redemption.
+    Redeemed(Coupon, CouponRedemption),
+    /// The coupon is unknown, inactive, expired, used up or not valid for the challenge.
+    Rejected,
+    /// The coupon itself refused the redemption, e.g. the caller already redeemed it.
+    Failed(CouponRedemptionError),
+}
 
 #[async_trait]
 pub trait CouponRepository: Send + Sync {
     async fn fetch(&self, coupon_code: &str) -> Result<Option<Coupon>, RepositoryError>;
     async fn fetch_all(&self) -> Result<Vec<Coupon>, RepositoryError>;
-    async fn save(&mut self, coupon: Coupon) -> Result<Coupon, RepositoryError>;
+    async fn save(&self, coupon: Coupon) -> Result<Coupon, RepositoryError>;
+
+    /// Stores a new coupon, fails with [`RepositoryError::AlreadyExists`] if the code is taken.
+    async fn create(&self, coupon: Coupon) -> Result<Coupon, RepositoryError>;
+
+    /// Applies `update` atomically, so concurrent redemptions are not lost.
+    async fn update(
+        &self,
+        coupon_code: &str,
+        update: &CouponUpdate,
+    ) -> Result<Coupon, RepositoryError>;
+
+    /// Removes the coupon together with its redemption history.
+    async fn delete(&self, coupon_code: &str) -> Result<(), RepositoryError>;
+
+    async fn is_active(&self, coupon_code: &str) -> Result<bool, RepositoryError>;
+
+    /// Checks and redeems one use of a coupon for `redeemed_by` in a single
+    /// atomic step, so concurrent callers can never redeem more uses than available.
+    /// The redemption is added to the coupon's history in the same step.
+    async fn redeem(
+        &self,
+        coupon_code: &str,
+        challenge_id: &str,
+        redeemed_by: &str,
+    ) -> Result<Redemption, RepositoryError>;
+
+    /// Redemptions of a coupon, oldest first.
+    async fn fetch_redemptions(
+        &self,
+        coupon_code: &str,
+    ) -> Result<Vec<CouponRedemption>, RepositoryError>;
+
+    /// Redemptions made by a profile or session across all coupons.
+    async fn fetch_user_redemptions(
+        &self,
+        redeemed_by: &str,
+    ) -> Result<Vec<CouponRedemption>, RepositoryError>;
+
+    async fn fetch_campaign(&self, name: &str) -> Result<Option<CouponCampaign>, RepositoryError>;
+
+    /// Stores a new campaign, fails with [`RepositoryError::AlreadyExists`] if the name is taken.
+    async fn create_campaign(
+        &self,
+        campaign: CouponCampaign,
+    ) -> Result<CouponCampaign, RepositoryError>;
+
+    async fn save_campaign(
+        &self,
+        campaign: CouponCampaign,
+    ) -> Result<CouponCampaign, RepositoryError>;
+
+    /// Removes a campaign, its coupons are kept.
+    async fn delete_campaign(&self, name: &str) -> Result<(), RepositoryError>;
+}
+
+/// Applies a redemption to a coupon loaded by a backend.
+/// Backends store the coupon and redemption of a [`Redemption::Redeemed`] result.
+pub(crate) fn redeem_coupon(
+    mut coupon: Coupon,
+    challenge_id: &str,
+    redeemed_by: &str,
+) -> Redemption {
+    if !coupon.challenge_ids.iter().any(|id| id == challenge_id)
+        || coupon.expiration_date < Utc::now()
+        || coupon.uses_remaining == 0
+    {
+        return Redemption::Rejected;
+    }
+
+    match coupon.redeem(redeemed_by.to_string()) {
+        Ok(_) => Redemption::Redeemed(
+            coupon,
+            CouponRedemption {
+                redeemed_by: redeemed_by.to_string(),
+                challenge_id: challenge_id.to_string(),
+                redeemed_at: Utc::now(),
+            },
+        ),
+        Err(err) => Redemption::Failed(err),
+    }
 }
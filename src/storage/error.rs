@@ -5,6 +5,7 @@ pub enum RepositoryError {
     NotFound(String),
     InternalError(String),
     LimitReached(usize),
+    AlreadyExists(String),
 }
 
 impl fmt::Display for RepositoryError {
@@ -13,6 +14,7 @@ impl fmt::Display for RepositoryError {
             RepositoryError::NotFound(id) => write!(f, "Resource not found: {}", id),
             RepositoryError::InternalError(err) => write!(f, "Internal error: {}", err),
             RepositoryError::LimitReached(limit) => write!(f, "Limit reached: {}", limit),
+            RepositoryError::AlreadyExists(id) => write!(f, "Resource already exists: {}", id),
         }
     }
 }
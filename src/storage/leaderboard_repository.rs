@@ -2,21 +2,108 @@ use crate::storage::error::RepositoryError;
 use async_trait::async_trait;
 use konnektoren_core::challenges::PerformanceRecord;
 
+/// The best record of a player among the best records of every player of a leaderboard.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedRecord {
+    pub record: PerformanceRecord,
+    /// One more than the number of players with a higher [`performance_score`].
+    pub rank: usize,
+    /// Players who submitted a record.
+    pub total: usize,
+}
+
 #[async_trait]
 pub trait LeaderboardRepository: Send + Sync {
     async fn fetch_performance_records(
         &self,
         namespace: &str,
     ) -> Result<Vec<PerformanceRecord>, RepositoryError>;
+    /// Adds the record of `player` to the leaderboard `namespace`, which keeps one
+    /// record per player and the best `capacity` records.
+    ///
+    /// An earlier record of the player is replaced if the new one is better, else
+    /// fails with [`RepositoryError::AlreadyExists`]. Worse records of other players
+    /// are dropped to make room, fails with [`RepositoryError::LimitReached`] if the
+    /// new record is not better than any of them.
     async fn add_performance_record(
-        &mut self,
+        &self,
         namespace: &str,
+        player: &str,
         performance_record: PerformanceRecord,
+        capacity: usize,
     ) -> Result<PerformanceRecord, RepositoryError>;
 
     async fn remove_performance_record(
-        &mut self,
+        &self,
         namespace: &str,
         performance_record: PerformanceRecord,
     ) -> Result<PerformanceRecord, RepositoryError>;
+
+    /// Keeps a record submitted to the leaderboard `namespace` by `player`, whether or not it
+    /// made it onto the board, and the best record of every player for ranks. A record only
+    /// replaces the best one of its player with a higher [`performance_score`].
+    /// `profile_id` is the profile the submission was authorized for, if any.
+    async fn record_submission(
+        &self,
+        namespace: &str,
+        player: &str,
+        profile_id: Option<&str>,
+        performance_record: PerformanceRecord,
+    ) -> Result<(), RepositoryError>;
+
+    /// The best submitted record of `player` to the leaderboard `namespace` and its
+    /// rank among the best records of every player, `None` if the player submitted nothing.
+    async fn fetch_ranked_record(
+        &self,
+        namespace: &str,
+        player: &str,
+    ) -> Result<Option<RankedRecord>, RepositoryError>;
+
+    /// Every record submitted to the leaderboard `namespace`.
+    async fn fetch_submissions(
+        &self,
+        namespace: &str,
+    ) -> Result<Vec<PerformanceRecord>, RepositoryError>;
+
+    /// Every record submitted for the profile `profile_id` to any leaderboard, oldest first.
+    async fn fetch_profile_submissions(
+        &self,
+        profile_id: &str,
+    ) -> Result<Vec<PerformanceRecord>, RepositoryError>;
+}
+
+/// Bits of a record score taken by the inverted total time, the percentage
+/// is stored above them. The largest score stays below 2^53, exact as a double.
+const SCORE_TIME_BITS: u32 = 45;
+
+/// Score of a record, higher scores rank better: a higher percentage first,
+/// then a shorter total time.
+pub(crate) fn performance_score(record: &PerformanceRecord) -> u64 {
+    let max_time = (1u64 << SCORE_TIME_BITS) - 1;
+    let time: u64 = record
+        .challenges_performance
+        .iter()
+        .map(|(_, _, time)| time)
+        .sum();
+    (u64::from(record.performance_percentage) << SCORE_TIME_BITS) + (max_time - time.min(max_time))
+}
+
+/// Keys of the records dropped from a leaderboard of `capacity` records when
+/// `record` is added to `records`, and whether `record` makes it onto the board.
+/// Records sorting equal to `record` stay ahead of it.
+pub(crate) fn evict_performance_records<K>(
+    mut records: Vec<(K, PerformanceRecord)>,
+    record: &PerformanceRecord,
+    capacity: usize,
+) -> (Vec<K>, bool) {
+    records.sort_by(|(_, a), (_, b)| a.cmp(b));
+    let position = records.partition_point(|(_, existing)| existing <= record);
+    let kept = position < capacity;
+    let retained = if kept { capacity - 1 } else { capacity };
+    let dropped = records
+        .into_iter()
+        .skip(retained)
+        .map(|(key, _)| key)
+        .collect();
+    (dropped, kept)
 }
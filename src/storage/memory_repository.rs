@@ -1,109 +1,301 @@
+use crate::storage::coupon_repository::redeem_coupon;
 use crate::storage::{
-    CouponRepository, LeaderboardRepository, ProfileRepository, RepositoryError, ReviewRepository,
-    Storage, WindowedCounterRepository,
+    evict_performance_records, performance_score, CertificateRecord, CertificateRepository,
+    ClaimRecord, ClaimRepository, CouponCampaign, CouponRedemption, CouponRepository, CouponUpdate,
+    LeaderboardRepository, ProfileCredentialRepository, ProfileRepository, ProfileSession,
+    RankedRecord, Redemption, RepositoryError, ReviewRepository, Storage,
+    WindowedCounterRepository,
 };
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use konnektoren_core::challenges::{PerformanceRecord, Review};
 use konnektoren_core::prelude::{Coupon, PlayerProfile};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
 use std::time::{SystemTime, UNIX_EPOCH};
+#[cfg(test)]
+use tokio::sync::Notify;
 use yew_chat::prelude::{Message, MessageReceiver, MessageSender, ReceiveError, SendError};
 #[cfg(feature = "chat")]
 use yew_chat::server::MemoryMessageStorage;
 use yew_chat::server::MessageStorage;
 
-const PERFORMANCE_RECORDS_LIMIT: usize = 10;
-
+/// In-memory storage. Each collection has its own lock, so operations on
+/// different repositories never wait for each other.
 pub struct MemoryRepository {
-    profiles: HashMap<String, PlayerProfile>,
-    performance_records: Vec<PerformanceRecord>,
-    reviews: HashMap<String, Vec<Review>>,
-    coupons: HashMap<String, Coupon>,
+    profiles: RwLock<HashMap<String, PlayerProfile>>,
+    profile_credentials: RwLock<HashMap<String, String>>,
+    profile_sessions: RwLock<HashMap<String, ProfileSession>>,
+    wallet_profiles: RwLock<HashMap<String, String>>,
+    /// Expiry of used sign-in payloads, by payload hash.
+    consumed_payloads: RwLock<HashMap<String, DateTime<Utc>>>,
+    /// Records of every leaderboard with the player they belong to.
+    performance_records: RwLock<HashMap<String, Vec<(String, PerformanceRecord)>>>,
+    performance_submissions: RwLock<HashMap<String, Vec<PerformanceRecord>>>,
+    /// Submissions authorized for a profile, by profile id.
+    profile_submissions: RwLock<HashMap<String, Vec<PerformanceRecord>>>,
+    /// Best submission of every player, by namespace and player.
+    best_records: RwLock<HashMap<String, HashMap<String, PerformanceRecord>>>,
+    reviews: RwLock<HashMap<String, Vec<Review>>>,
+    coupons: RwLock<HashMap<String, Coupon>>,
+    inactive_coupons: RwLock<HashSet<String>>,
+    coupon_redemptions: RwLock<HashMap<String, Vec<CouponRedemption>>>,
+    coupon_campaigns: RwLock<HashMap<String, CouponCampaign>>,
+    claims: RwLock<HashMap<i64, ClaimRecord>>,
+    certificates: RwLock<HashMap<String, CertificateRecord>>,
+    /// Next free item index, by NFT collection.
+    nft_item_indexes: RwLock<HashMap<String, u64>>,
     #[cfg(feature = "chat")]
     message_storage: MemoryMessageStorage,
-    active_users: HashMap<String, Vec<u64>>,
+    active_users: RwLock<HashMap<String, Vec<u64>>>,
+    /// Holds leaderboard reads until released, see [`MemoryRepository::with_blocking_leaderboard`].
+    #[cfg(test)]
+    leaderboard_gate: Option<LeaderboardGate>,
+}
+
+#[cfg(test)]
+struct LeaderboardGate {
+    entered: Notify,
+    release: Notify,
 }
 
 impl MemoryRepository {
     pub fn new() -> Self {
         MemoryRepository {
-            profiles: HashMap::new(),
-            performance_records: Vec::new(),
-            reviews: HashMap::new(),
-            coupons: HashMap::new(),
+            profiles: RwLock::new(HashMap::new()),
+            profile_credentials: RwLock::new(HashMap::new()),
+            profile_sessions: RwLock::new(HashMap::new()),
+            wallet_profiles: RwLock::new(HashMap::new()),
+            consumed_payloads: RwLock::new(HashMap::new()),
+            performance_records: RwLock::new(HashMap::new()),
+            performance_submissions: RwLock::new(HashMap::new()),
+            profile_submissions: RwLock::new(HashMap::new()),
+            best_records: RwLock::new(HashMap::new()),
+            reviews: RwLock::new(HashMap::new()),
+            coupons: RwLock::new(HashMap::new()),
+            inactive_coupons: RwLock::new(HashSet::new()),
+            coupon_redemptions: RwLock::new(HashMap::new()),
+            coupon_campaigns: RwLock::new(HashMap::new()),
+            claims: RwLock::new(HashMap::new()),
+            certificates: RwLock::new(HashMap::new()),
+            nft_item_indexes: RwLock::new(HashMap::new()),
             #[cfg(feature = "chat")]
             message_storage: MemoryMessageStorage::new(),
-            active_users: HashMap::new(),
+            active_users: RwLock::new(HashMap::new()),
+            #[cfg(test)]
+            leaderboard_gate: None,
+        }
+    }
+
+    /// Storage whose leaderboard reads block until [`Self::release_leaderboard`] is called.
+    #[cfg(test)]
+    pub(crate) fn with_blocking_leaderboard() -> Self {
+        MemoryRepository {
+            leaderboard_gate: Some(LeaderboardGate {
+                entered: Notify::new(),
+                release: Notify::new(),
+            }),
+            ..Self::new()
+        }
+    }
+
+    /// Waits until a leaderboard read is blocked.
+    #[cfg(test)]
+    pub(crate) async fn leaderboard_entered(&self) {
+        if let Some(gate) = &self.leaderboard_gate {
+            gate.entered.notified().await;
         }
     }
 
-    fn cleanup_old_entries(&mut self, namespace: &str) {
-        if let Some(timestamps) = self.active_users.get_mut(namespace) {
-            let current_time = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
-            let window = 24 * 60 * 60;
-            timestamps.retain(|&timestamp| current_time - timestamp < window);
+    #[cfg(test)]
+    pub(crate) fn release_leaderboard(&self) {
+        if let Some(gate) = &self.leaderboard_gate {
+            gate.release.notify_one();
         }
     }
 }
 
+fn lock_error<T>(_: T) -> RepositoryError {
+    RepositoryError::InternalError("Memory storage lock poisoned".to_string())
+}
+
+fn current_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
 impl Storage for MemoryRepository {}
 
 #[async_trait]
 impl ProfileRepository for MemoryRepository {
     async fn fetch(&self, profile_id: String) -> Result<PlayerProfile, RepositoryError> {
-        match self.profiles.get(&profile_id) {
+        let profiles = self.profiles.read().map_err(lock_error)?;
+        match profiles.get(&profile_id) {
             Some(profile) => Ok(profile.clone()),
             None => Err(RepositoryError::NotFound(profile_id.clone())),
         }
     }
 
     async fn fetch_all(&self) -> Result<Vec<PlayerProfile>, RepositoryError> {
-        Ok(self.profiles.values().cloned().collect())
+        let profiles = self.profiles.read().map_err(lock_error)?;
+        Ok(profiles.values().cloned().collect())
     }
 
-    async fn save(&mut self, profile: PlayerProfile) -> Result<PlayerProfile, RepositoryError> {
-        self.profiles.insert(profile.id.clone(), profile.clone());
+    async fn save(&self, profile: PlayerProfile) -> Result<PlayerProfile, RepositoryError> {
+        let mut profiles = self.profiles.write().map_err(lock_error)?;
+        profiles.insert(profile.id.clone(), profile.clone());
         Ok(profile)
     }
 }
 
+#[async_trait]
+impl ProfileCredentialRepository for MemoryRepository {
+    async fn create_credential(
+        &self,
+        profile_id: &str,
+        secret_hash: &str,
+    ) -> Result<(), RepositoryError> {
+        let mut credentials = self.profile_credentials.write().map_err(lock_error)?;
+        if credentials.contains_key(profile_id) {
+            return Err(RepositoryError::AlreadyExists(profile_id.to_string()));
+        }
+        credentials.insert(profile_id.to_string(), secret_hash.to_string());
+        Ok(())
+    }
+
+    async fn fetch_credential(&self, profile_id: &str) -> Result<Option<String>, RepositoryError> {
+        let credentials = self.profile_credentials.read().map_err(lock_error)?;
+        Ok(credentials.get(profile_id).cloned())
+    }
+
+    async fn create_session(
+        &self,
+        token_hash: &str,
+        session: ProfileSession,
+    ) -> Result<(), RepositoryError> {
+        let mut sessions = self.profile_sessions.write().map_err(lock_error)?;
+        let now = Utc::now();
+        sessions.retain(|_, session| session.expires_at > now);
+        sessions.insert(token_hash.to_string(), session);
+        Ok(())
+    }
+
+    async fn fetch_session(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<ProfileSession>, RepositoryError> {
+        let sessions = self.profile_sessions.read().map_err(lock_error)?;
+        Ok(sessions.get(token_hash).cloned())
+    }
+
+    async fn link_wallet(&self, address: &str, profile_id: &str) -> Result<(), RepositoryError> {
+        let mut wallets = self.wallet_profiles.write().map_err(lock_error)?;
+        if wallets.contains_key(address) {
+            return Err(RepositoryError::AlreadyExists(address.to_string()));
+        }
+        wallets.insert(address.to_string(), profile_id.to_string());
+        Ok(())
+    }
+
+    async fn fetch_wallet_profile(&self, address: &str) -> Result<Option<String>, RepositoryError> {
+        let wallets = self.wallet_profiles.read().map_err(lock_error)?;
+        Ok(wallets.get(address).cloned())
+    }
+
+    async fn consume_payload(
+        &self,
+        payload_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        let mut payloads = self.consumed_payloads.write().map_err(lock_error)?;
+        let now = Utc::now();
+        payloads.retain(|_, expires_at| *expires_at > now);
+        if payloads.contains_key(payload_hash) {
+            return Err(RepositoryError::AlreadyExists(payload_hash.to_string()));
+        }
+        payloads.insert(payload_hash.to_string(), expires_at);
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl LeaderboardRepository for MemoryRepository {
     async fn fetch_performance_records(
         &self,
-        _namespace: &str,
+        namespace: &str,
     ) -> Result<Vec<PerformanceRecord>, RepositoryError> {
-        Ok(self.performance_records.clone())
+        #[cfg(test)]
+        if let Some(gate) = &self.leaderboard_gate {
+            gate.entered.notify_one();
+            gate.release.notified().await;
+        }
+        let performance_records = self.performance_records.read().map_err(lock_error)?;
+        Ok(performance_records
+            .get(namespace)
+            .map(|records| records.iter().map(|(_, record)| record.clone()).collect())
+            .unwrap_or_default())
     }
 
     async fn add_performance_record(
-        &mut self,
-        _namespace: &str,
+        &self,
+        namespace: &str,
+        player: &str,
         performance_record: PerformanceRecord,
+        capacity: usize,
     ) -> Result<PerformanceRecord, RepositoryError> {
-        if self.performance_records.len() < PERFORMANCE_RECORDS_LIMIT {
-            self.performance_records.push(performance_record.clone());
-        } else {
-            return Err(RepositoryError::LimitReached(PERFORMANCE_RECORDS_LIMIT));
+        let mut performance_records = self.performance_records.write().map_err(lock_error)?;
+        let performance_records = performance_records
+            .entry(namespace.to_string())
+            .or_insert_with(Vec::new);
+        if let Some(index) = performance_records
+            .iter()
+            .position(|(owner, _)| owner == player)
+        {
+            if performance_records[index].1 <= performance_record {
+                return Err(RepositoryError::AlreadyExists(player.to_string()));
+            }
+            performance_records.remove(index);
         }
+        if performance_records.len() >= capacity {
+            let (dropped, kept) = evict_performance_records(
+                performance_records
+                    .iter()
+                    .map(|(_, record)| record.clone())
+                    .enumerate()
+                    .collect(),
+                &performance_record,
+                capacity,
+            );
+            let mut index = 0;
+            performance_records.retain(|_| {
+                index += 1;
+                !dropped.contains(&(index - 1))
+            });
+            if !kept {
+                return Err(RepositoryError::LimitReached(capacity));
+            }
+        }
+        performance_records.push((player.to_string(), performance_record.clone()));
         Ok(performance_record)
     }
 
     async fn remove_performance_record(
-        &mut self,
-        _namespace: &str,
+        &self,
+        namespace: &str,
         performance_record: PerformanceRecord,
     ) -> Result<PerformanceRecord, RepositoryError> {
-        let index = self
-            .performance_records
+        let mut performance_records = self.performance_records.write().map_err(lock_error)?;
+        let performance_records = performance_records
+            .get_mut(namespace)
+            .ok_or(RepositoryError::NotFound(namespace.to_string()))?;
+        let index = performance_records
             .iter()
-            .position(|r| r == &performance_record);
+            .position(|(_, r)| r == &performance_record);
         match index {
             Some(i) => {
-                self.performance_records.remove(i);
+                performance_records.remove(i);
                 Ok(performance_record)
             }
             None => Err(RepositoryError::NotFound(
@@ -111,12 +303,85 @@ impl LeaderboardRepository for MemoryRepository {
             )),
         }
     }
+
+    async fn record_submission(
+        &self,
+        namespace: &str,
+        player: &str,
+        profile_id: Option<&str>,
+        performance_record: PerformanceRecord,
+    ) -> Result<(), RepositoryError> {
+        let mut submissions = self.performance_submissions.write().map_err(lock_error)?;
+        let mut best_records = self.best_records.write().map_err(lock_error)?;
+        let players = best_records.entry(namespace.to_string()).or_default();
+        match players.get(player) {
+            Some(best) if performance_score(best) >= performance_score(&performance_record) => {}
+            _ => {
+                players.insert(player.to_string(), performance_record.clone());
+            }
+        }
+        if let Some(profile_id) = profile_id {
+            let mut profile_submissions = self.profile_submissions.write().map_err(lock_error)?;
+            profile_submissions
+                .entry(profile_id.to_string())
+                .or_default()
+                .push(performance_record.clone());
+        }
+        submissions
+            .entry(namespace.to_string())
+            .or_default()
+            .push(performance_record);
+        Ok(())
+    }
+
+    async fn fetch_submissions(
+        &self,
+        namespace: &str,
+    ) -> Result<Vec<PerformanceRecord>, RepositoryError> {
+        let submissions = self.performance_submissions.read().map_err(lock_error)?;
+        Ok(submissions.get(namespace).cloned().unwrap_or_default())
+    }
+
+    async fn fetch_ranked_record(
+        &self,
+        namespace: &str,
+        player: &str,
+    ) -> Result<Option<RankedRecord>, RepositoryError> {
+        let best_records = self.best_records.read().map_err(lock_error)?;
+        let Some(players) = best_records.get(namespace) else {
+            return Ok(None);
+        };
+        Ok(players.get(player).map(|record| {
+            let score = performance_score(record);
+            RankedRecord {
+                record: record.clone(),
+                rank: players
+                    .values()
+                    .filter(|best| performance_score(best) > score)
+                    .count()
+                    + 1,
+                total: players.len(),
+            }
+        }))
+    }
+
+    async fn fetch_profile_submissions(
+        &self,
+        profile_id: &str,
+    ) -> Result<Vec<PerformanceRecord>, RepositoryError> {
+        let profile_submissions = self.profile_submissions.read().map_err(lock_error)?;
+        Ok(profile_submissions
+            .get(profile_id)
+            .cloned()
+            .unwrap_or_default())
+    }
 }
 
 #[async_trait]
 impl ReviewRepository for MemoryRepository {
-    async fn store_review(&mut self, review: Review) -> Result<(), RepositoryError> {
-        self.reviews
+    async fn store_review(&self, review: Review) -> Result<(), RepositoryError> {
+        let mut reviews = self.reviews.write().map_err(lock_error)?;
+        reviews
             .entry(review.challenge_id.clone())
             .or_insert_with(Vec::new)
             .push(review);
@@ -124,8 +389,8 @@ impl ReviewRepository for MemoryRepository {
     }
 
     async fn fetch_reviews(&self, namespace: &str) -> Result<Vec<Review>, RepositoryError> {
-        Ok(self
-            .reviews
+        let reviews = self.reviews.read().map_err(lock_error)?;
+        Ok(reviews
             .values()
             .flatten()
             .filter(|review| review.challenge_id == *namespace)
@@ -134,13 +399,12 @@ impl ReviewRepository for MemoryRepository {
     }
 
     async fn fetch_average_rating(&self, namespace: &str) -> Result<f64, RepositoryError> {
-        let reviews = self
-            .reviews
+        let reviews = self.reviews.read().map_err(lock_error)?;
+        let reviews = reviews
             .get(namespace)
             .ok_or(RepositoryError::NotFound(namespace.to_string()))?;
         let total: u32 = reviews.iter().map(|review| review.rating as u32).sum();
         let count = reviews.len() as f64;
-
         Ok(if count > 0.0 {
             total as f64 / count
         } else {
@@ -149,24 +413,269 @@ impl ReviewRepository for MemoryRepository {
     }
 
     async fn fetch_all_reviews(&self) -> Result<Vec<Review>, RepositoryError> {
-        Ok(self.reviews.values().flatten().cloned().collect())
+        let reviews = self.reviews.read().map_err(lock_error)?;
+        Ok(reviews.values().flatten().cloned().collect())
     }
 }
 
 #[async_trait]
 impl CouponRepository for MemoryRepository {
     async fn fetch(&self, coupon_code: &str) -> Result<Option<Coupon>, RepositoryError> {
-        Ok(self.coupons.get(coupon_code).cloned())
+        let coupons = self.coupons.read().map_err(lock_error)?;
+        Ok(coupons.get(coupon_code).cloned())
     }
 
     async fn fetch_all(&self) -> Result<Vec<Coupon>, RepositoryError> {
-        Ok(self.coupons.values().cloned().collect())
+        let coupons = self.coupons.read().map_err(lock_error)?;
+        Ok(coupons.values().cloned().collect())
+    }
+
+    async fn save(&self, coupon: Coupon) -> Result<Coupon, RepositoryError> {
+        let mut coupons = self.coupons.write().map_err(lock_error)?;
+        coupons.insert(coupon.code.clone(), coupon.clone());
+        Ok(coupon)
     }
 
-    async fn save(&mut self, mut coupon: Coupon) -> Result<Coupon, RepositoryError> {
-        self.coupons.insert(coupon.code.clone(), coupon.clone());
+    async fn create(&self, coupon: Coupon) -> Result<Coupon, RepositoryError> {
+        let mut coupons = self.coupons.write().map_err(lock_error)?;
+        if coupons.contains_key(&coupon.code) {
+            return Err(RepositoryError::AlreadyExists(coupon.code));
+        }
+        coupons.insert(coupon.code.clone(), coupon.clone());
         Ok(coupon)
     }
+
+    async fn update(
+        &self,
+        coupon_code: &str,
+        update: &CouponUpdate,
+    ) -> Result<Coupon, RepositoryError> {
+        let mut coupons = self.coupons.write().map_err(lock_error)?;
+        let coupon = coupons
+            .get_mut(coupon_code)
+            .ok_or(RepositoryError::NotFound(coupon_code.to_string()))?;
+        *coupon = update.apply(coupon.clone());
+
+        if let Some(active) = update.active {
+            let mut inactive_coupons = self.inactive_coupons.write().map_err(lock_error)?;
+            if active {
+                inactive_coupons.remove(coupon_code);
+            } else {
+                inactive_coupons.insert(coupon_code.to_string());
+            }
+        }
+        Ok(coupon.clone())
+    }
+
+    async fn delete(&self, coupon_code: &str) -> Result<(), RepositoryError> {
+        let mut coupons = self.coupons.write().map_err(lock_error)?;
+        if coupons.remove(coupon_code).is_none() {
+            return Err(RepositoryError::NotFound(coupon_code.to_string()));
+        }
+        self.inactive_coupons
+            .write()
+            .map_err(lock_error)?
+            .remove(coupon_code);
+        self.coupon_redemptions
+            .write()
+            .map_err(lock_error)?
+            .remove(coupon_code);
+        Ok(())
+    }
+
+    async fn is_active(&self, coupon_code: &str) -> Result<bool, RepositoryError> {
+        let coupons = self.coupons.read().map_err(lock_error)?;
+        if !coupons.contains_key(coupon_code) {
+            return Err(RepositoryError::NotFound(coupon_code.to_string()));
+        }
+        let inactive_coupons = self.inactive_coupons.read().map_err(lock_error)?;
+        Ok(!inactive_coupons.contains(coupon_code))
+    }
+
+    async fn redeem(
+        &self,
+        coupon_code: &str,
+        challenge_id: &str,
+        redeemed_by: &str,
+    ) -> Result<Redemption, RepositoryError> {
+        // Holding the write lock makes check and update a single step
+        let mut coupons = self.coupons.write().map_err(lock_error)?;
+        let Some(coupon) = coupons.get(coupon_code).cloned() else {
+            return Ok(Redemption::Rejected);
+        };
+        if self
+            .inactive_coupons
+            .read()
+            .map_err(lock_error)?
+            .contains(coupon_code)
+        {
+            return Ok(Redemption::Rejected);
+        }
+
+        let redemption = redeem_coupon(coupon, challenge_id, redeemed_by);
+        if let Redemption::Redeemed(coupon, coupon_redemption) = &redemption {
+            let mut coupon_redemptions = self.coupon_redemptions.write().map_err(lock_error)?;
+            coupon_redemptions
+                .entry(coupon.code.clone())
+                .or_insert_with(Vec::new)
+                .push(coupon_redemption.clone());
+            coupons.insert(coupon.code.clone(), coupon.clone());
+        }
+        Ok(redemption)
+    }
+
+    async fn fetch_redemptions(
+        &self,
+        coupon_code: &str,
+    ) -> Result<Vec<CouponRedemption>, RepositoryError> {
+        let coupon_redemptions = self.coupon_redemptions.read().map_err(lock_error)?;
+        Ok(coupon_redemptions
+            .get(coupon_code)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn fetch_user_redemptions(
+        &self,
+        redeemed_by: &str,
+    ) -> Result<Vec<CouponRedemption>, RepositoryError> {
+        let coupon_redemptions = self.coupon_redemptions.read().map_err(lock_error)?;
+        Ok(coupon_redemptions
+            .values()
+            .flatten()
+            .filter(|redemption| redemption.redeemed_by == redeemed_by)
+            .cloned()
+            .collect())
+    }
+
+    async fn fetch_campaign(&self, name: &str) -> Result<Option<CouponCampaign>, RepositoryError> {
+        let coupon_campaigns = self.coupon_campaigns.read().map_err(lock_error)?;
+        Ok(coupon_campaigns.get(name).cloned())
+    }
+
+    async fn create_campaign(
+        &self,
+        campaign: CouponCampaign,
+    ) -> Result<CouponCampaign, RepositoryError> {
+        let mut coupon_campaigns = self.coupon_campaigns.write().map_err(lock_error)?;
+        if coupon_campaigns.contains_key(&campaign.name) {
+            return Err(RepositoryError::AlreadyExists(campaign.name));
+        }
+        coupon_campaigns.insert(campaign.name.clone(), campaign.clone());
+        Ok(campaign)
+    }
+
+    async fn save_campaign(
+        &self,
+        campaign: CouponCampaign,
+    ) -> Result<CouponCampaign, RepositoryError> {
+        let mut coupon_campaigns = self.coupon_campaigns.write().map_err(lock_error)?;
+        coupon_campaigns.insert(campaign.name.clone(), campaign.clone());
+        Ok(campaign)
+    }
+
+    async fn delete_campaign(&self, name: &str) -> Result<(), RepositoryError> {
+        let mut coupon_campaigns = self.coupon_campaigns.write().map_err(lock_error)?;
+        match coupon_campaigns.remove(name) {
+            Some(_) => Ok(()),
+            None => Err(RepositoryError::NotFound(name.to_string())),
+        }
+    }
+}
+
+#[async_trait]
+impl ClaimRepository for MemoryRepository {
+    async fn create_claim(&self, claim: ClaimRecord) -> Result<ClaimRecord, RepositoryError> {
+        let mut claims = self.claims.write().map_err(lock_error)?;
+        if claims.contains_key(&claim.id) {
+            return Err(RepositoryError::AlreadyExists(claim.id.to_string()));
+        }
+        claims.insert(claim.id, claim.clone());
+        Ok(claim)
+    }
+
+    async fn fetch_claim(&self, id: i64) -> Result<Option<ClaimRecord>, RepositoryError> {
+        let claims = self.claims.read().map_err(lock_error)?;
+        Ok(claims.get(&id).cloned())
+    }
+
+    async fn fetch_user_claims(&self, user: &str) -> Result<Vec<ClaimRecord>, RepositoryError> {
+        let claims = self.claims.read().map_err(lock_error)?;
+        Ok(claims
+            .values()
+            .filter(|claim| claim.user == user)
+            .cloned()
+            .collect())
+    }
+
+    async fn fetch_queued_claims(&self) -> Result<Vec<ClaimRecord>, RepositoryError> {
+        let claims = self.claims.read().map_err(lock_error)?;
+        Ok(claims
+            .values()
+            .filter(|claim| claim.is_queued())
+            .cloned()
+            .collect())
+    }
+
+    async fn update_claim(&self, claim: ClaimRecord) -> Result<ClaimRecord, RepositoryError> {
+        let mut claims = self.claims.write().map_err(lock_error)?;
+        match claims.get_mut(&claim.id) {
+            Some(stored) => {
+                *stored = claim.clone();
+                Ok(claim)
+            }
+            None => Err(RepositoryError::NotFound(claim.id.to_string())),
+        }
+    }
+}
+
+#[async_trait]
+impl CertificateRepository for MemoryRepository {
+    async fn create_certificate(
+        &self,
+        certificate: CertificateRecord,
+    ) -> Result<CertificateRecord, RepositoryError> {
+        let mut certificates = self.certificates.write().map_err(lock_error)?;
+        if certificates.contains_key(&certificate.id) {
+            return Err(RepositoryError::AlreadyExists(certificate.id));
+        }
+        certificates.insert(certificate.id.clone(), certificate.clone());
+        Ok(certificate)
+    }
+
+    async fn fetch_certificate(
+        &self,
+        id: &str,
+    ) -> Result<Option<CertificateRecord>, RepositoryError> {
+        let certificates = self.certificates.read().map_err(lock_error)?;
+        Ok(certificates.get(id).cloned())
+    }
+
+    async fn update_certificate(
+        &self,
+        certificate: CertificateRecord,
+    ) -> Result<CertificateRecord, RepositoryError> {
+        let mut certificates = self.certificates.write().map_err(lock_error)?;
+        match certificates.get_mut(&certificate.id) {
+            Some(stored) => {
+                *stored = certificate.clone();
+                Ok(certificate)
+            }
+            None => Err(RepositoryError::NotFound(certificate.id)),
+        }
+    }
+
+    async fn reserve_item_index(
+        &self,
+        collection: &str,
+        next_index: u64,
+    ) -> Result<u64, RepositoryError> {
+        let mut indexes = self.nft_item_indexes.write().map_err(lock_error)?;
+        let free = indexes.entry(collection.to_string()).or_insert(0);
+        let index = (*free).max(next_index);
+        *free = index + 1;
+        Ok(index)
+    }
 }
 
 #[cfg(feature = "chat")]
@@ -191,14 +700,11 @@ impl MessageStorage for MemoryRepository {}
 #[async_trait]
 impl WindowedCounterRepository for MemoryRepository {
     async fn get_active_count(&self, namespace: &str) -> Result<u32, RepositoryError> {
-        let current_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let current_time = current_time();
         let window = 24 * 60 * 60; // 24 hours in seconds
 
-        let count = self
-            .active_users
+        let active_users = self.active_users.read().map_err(lock_error)?;
+        let count = active_users
             .get(namespace)
             .map(|timestamps| {
                 timestamps
@@ -211,35 +717,42 @@ impl WindowedCounterRepository for MemoryRepository {
         Ok(count)
     }
 
-    async fn record_presence(&mut self, namespace: &str) -> Result<u32, RepositoryError> {
-        let current_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+    async fn record_presence(&self, namespace: &str) -> Result<u32, RepositoryError> {
+        let current_time = current_time();
+        let window = 24 * 60 * 60;
+
+        let mut active_users = self.active_users.write().map_err(lock_error)?;
+        let timestamps = active_users
+            .entry(namespace.to_string())
+            .or_insert_with(Vec::new);
 
         // Clean up old entries first
-        self.cleanup_old_entries(namespace);
+        timestamps.retain(|&timestamp| current_time - timestamp < window);
 
         // Record new presence
-        self.active_users
-            .entry(namespace.to_string())
-            .or_insert_with(Vec::new)
-            .push(current_time);
+        timestamps.push(current_time);
 
-        self.get_active_count(namespace).await
+        Ok(timestamps.len() as u32)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::conformance::storage_conformance_tests;
     use chrono::{Duration, Utc};
 
+    async fn create_storage() -> Option<MemoryRepository> {
+        Some(MemoryRepository::new())
+    }
+
+    storage_conformance_tests!(create_storage);
+
     #[tokio::test]
     async fn test_fetch_profile() {
-        let mut repo = MemoryRepository::new();
+        let repo = MemoryRepository::new();
         let profile = PlayerProfile::new("example_user_id".to_string());
-        ProfileRepository::save(&mut repo, profile.clone())
+        ProfileRepository::save(&repo, profile.clone())
             .await
             .unwrap();
         let fetched_profile = ProfileRepository::fetch(&repo, "example_user_id".to_string())
@@ -250,13 +763,13 @@ mod tests {
 
     #[tokio::test]
     async fn test_fetch_all_profiles() {
-        let mut repo = MemoryRepository::new();
+        let repo = MemoryRepository::new();
         let profile1 = PlayerProfile::new("example_user_id1".to_string());
         let profile2 = PlayerProfile::new("example_user_id2".to_string());
-        ProfileRepository::save(&mut repo, profile1.clone())
+        ProfileRepository::save(&repo, profile1.clone())
             .await
             .unwrap();
-        ProfileRepository::save(&mut repo, profile2.clone())
+        ProfileRepository::save(&repo, profile2.clone())
             .await
             .unwrap();
         let profiles = ProfileRepository::fetch_all(&repo).await.unwrap();
@@ -267,7 +780,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_fetch_reviews() {
-        let mut repo = MemoryRepository::new();
+        let repo = MemoryRepository::new();
         let review1 = Review {
             challenge_id: "example_challenge_id".to_string(),
             rating: 5,
@@ -290,7 +803,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_fetch_average_rating() {
-        let mut repo = MemoryRepository::new();
+        let repo = MemoryRepository::new();
         let review1 = Review {
             challenge_id: "example_challenge_id".to_string(),
             rating: 5,
@@ -311,7 +824,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_windowed_counter() {
-        let mut repo = MemoryRepository::new();
+        let repo = MemoryRepository::new();
         let namespace = "test_challenge";
 
         // Test initial count
@@ -334,32 +847,28 @@ mod tests {
 
     #[tokio::test]
     async fn test_save_coupon() {
-        let mut repo = MemoryRepository::new();
+        let repo = MemoryRepository::new();
         let coupon = Coupon::new(
             "TESTCOUPON".to_string(),
             vec!["challenge1".to_string()],
             1,
             Utc::now() + Duration::days(7),
         );
-        let saved_coupon = CouponRepository::save(&mut repo, coupon.clone())
-            .await
-            .unwrap();
+        let saved_coupon = CouponRepository::save(&repo, coupon.clone()).await.unwrap();
         assert_eq!(saved_coupon, coupon);
-        assert_eq!(repo.coupons.len(), 1);
+        assert_eq!(repo.coupons.read().unwrap().len(), 1);
     }
 
     #[tokio::test]
     async fn test_fetch_coupon() {
-        let mut repo = MemoryRepository::new();
+        let repo = MemoryRepository::new();
         let coupon = Coupon::new(
             "TESTCOUPON".to_string(),
             vec!["challenge1".to_string()],
             1,
             Utc::now() + Duration::days(7),
         );
-        CouponRepository::save(&mut repo, coupon.clone())
-            .await
-            .unwrap();
+        CouponRepository::save(&repo, coupon.clone()).await.unwrap();
         let fetched_coupon = CouponRepository::fetch(&repo, "TESTCOUPON")
             .await
             .unwrap()
@@ -369,7 +878,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_fetch_all_coupons() {
-        let mut repo = MemoryRepository::new();
+        let repo = MemoryRepository::new();
         let coupon1 = Coupon::new(
             "TESTCOUPON1".to_string(),
             vec!["challenge1".to_string()],
@@ -382,10 +891,10 @@ mod tests {
             1,
             Utc::now() + Duration::days(7),
         );
-        CouponRepository::save(&mut repo, coupon1.clone())
+        CouponRepository::save(&repo, coupon1.clone())
             .await
             .unwrap();
-        CouponRepository::save(&mut repo, coupon2.clone())
+        CouponRepository::save(&repo, coupon2.clone())
             .await
             .unwrap();
         let coupons = CouponRepository::fetch_all(&repo).await.unwrap();
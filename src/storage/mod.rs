@@ -1,23 +1,39 @@
+mod certificate_repository;
+mod claim_repository;
+mod config;
+#[cfg(test)]
+mod conformance;
 mod coupon_repository;
 mod error;
 mod leaderboard_repository;
 mod memory_repository;
+mod profile_credential_repository;
 mod profile_repository;
 mod review_repository;
 mod windowed_counter_repository;
 
 #[cfg(not(feature = "chat"))]
 pub trait Storage:
-    ProfileRepository + LeaderboardRepository + ReviewRepository + CouponRepository
+    ProfileRepository
+    + ProfileCredentialRepository
+    + LeaderboardRepository
+    + ReviewRepository
+    + CouponRepository
+    + ClaimRepository
+    + CertificateRepository
+    + WindowedCounterRepository
 {
 }
 
 #[cfg(feature = "chat")]
 pub trait Storage:
     ProfileRepository
+    + ProfileCredentialRepository
     + LeaderboardRepository
     + ReviewRepository
     + CouponRepository
+    + ClaimRepository
+    + CertificateRepository
     + MessageStorage
     + WindowedCounterRepository
 {
@@ -26,10 +42,23 @@ pub trait Storage:
 #[cfg(feature = "redis")]
 mod redis_storage;
 
-pub use coupon_repository::CouponRepository;
+#[cfg(feature = "sqlite")]
+mod sqlite_storage;
+
+#[cfg(feature = "postgres")]
+mod postgres_storage;
+
+pub use certificate_repository::{CertificateRecord, CertificateRepository, CertificateStatus};
+pub use claim_repository::{ClaimKind, ClaimRecord, ClaimRepository, ClaimStatus};
+pub use config::{create_storage, StorageConfig, StorageConfigError};
+pub use coupon_repository::{
+    CouponCampaign, CouponRedemption, CouponRepository, CouponUpdate, Redemption,
+};
 pub use error::RepositoryError;
-pub use leaderboard_repository::LeaderboardRepository;
+pub(crate) use leaderboard_repository::{evict_performance_records, performance_score};
+pub use leaderboard_repository::{LeaderboardRepository, RankedRecord};
 pub use memory_repository::MemoryRepository;
+pub use profile_credential_repository::{ProfileCredentialRepository, ProfileSession};
 pub use profile_repository::ProfileRepository;
 pub use review_repository::ReviewRepository;
 pub use windowed_counter_repository::WindowedCounterRepository;
@@ -37,3 +66,9 @@ use yew_chat::server::MessageStorage;
 
 #[cfg(feature = "redis")]
 pub use redis_storage::RedisStorage;
+
+#[cfg(feature = "sqlite")]
+pub use sqlite_storage::SqliteStorage;
+
+#[cfg(feature = "postgres")]
+pub use postgres_storage::PostgresStorage;
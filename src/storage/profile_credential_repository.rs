@@ -0,0 +1,52 @@
+use crate::storage::error::RepositoryError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A session token issued to a player, e.g. after signing in with a wallet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileSession {
+    pub profile_id: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Hashed secrets proving ownership of a profile.
+#[async_trait]
+pub trait ProfileCredentialRepository: Send + Sync {
+    /// Stores the owner secret of a profile, fails with
+    /// [`RepositoryError::AlreadyExists`] if the profile already has an owner.
+    async fn create_credential(
+        &self,
+        profile_id: &str,
+        secret_hash: &str,
+    ) -> Result<(), RepositoryError>;
+
+    async fn fetch_credential(&self, profile_id: &str) -> Result<Option<String>, RepositoryError>;
+
+    /// Stores a session under the hash of its token.
+    async fn create_session(
+        &self,
+        token_hash: &str,
+        session: ProfileSession,
+    ) -> Result<(), RepositoryError>;
+
+    /// Returns the session of a token hash, backends may drop expired sessions.
+    async fn fetch_session(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<ProfileSession>, RepositoryError>;
+
+    /// Links a verified wallet address to a profile, fails with
+    /// [`RepositoryError::AlreadyExists`] if the address is already linked.
+    async fn link_wallet(&self, address: &str, profile_id: &str) -> Result<(), RepositoryError>;
+
+    async fn fetch_wallet_profile(&self, address: &str) -> Result<Option<String>, RepositoryError>;
+
+    /// Marks a sign-in payload as used until it expires, fails with
+    /// [`RepositoryError::AlreadyExists`] if it was used before.
+    async fn consume_payload(
+        &self,
+        payload_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), RepositoryError>;
+}
@@ -6,5 +6,5 @@ use konnektoren_core::prelude::PlayerProfile;
 pub trait ProfileRepository: Send + Sync {
     async fn fetch(&self, profile_id: String) -> Result<PlayerProfile, RepositoryError>;
     async fn fetch_all(&self) -> Result<Vec<PlayerProfile>, RepositoryError>;
-    async fn save(&mut self, profile: PlayerProfile) -> Result<PlayerProfile, RepositoryError>;
+    async fn save(&self, profile: PlayerProfile) -> Result<PlayerProfile, RepositoryError>;
 }
@@ -1,13 +1,15 @@
 use crate::compatibility::LegacyPerformanceRecord;
+use crate::storage::coupon_repository::redeem_coupon;
 use crate::storage::{
-    CouponRepository, LeaderboardRepository, ProfileRepository, RepositoryError, ReviewRepository,
-    Storage, WindowedCounterRepository,
+    performance_score, CertificateRecord, CertificateRepository, ClaimRecord, ClaimRepository,
+    CouponCampaign, CouponRedemption, CouponRepository, CouponUpdate, LeaderboardRepository,
+    ProfileCredentialRepository, ProfileRepository, ProfileSession, RankedRecord, Redemption,
+    RepositoryError, ReviewRepository, Storage, WindowedCounterRepository,
 };
 use async_trait::async_trait;
 use konnektoren_core::challenges::{PerformanceRecord, Review};
 use konnektoren_core::prelude::{Coupon, PlayerProfile};
 use redis::AsyncCommands;
-use std::hash::{DefaultHasher, Hash, Hasher};
 use yew_chat::prelude::{Message, MessageReceiver, MessageSender, ReceiveError, SendError};
 use yew_chat::server::MessageStorage;
 
@@ -16,8 +18,23 @@ pub struct RedisStorage {
 }
 
 const PROFILES_HSET: &str = "profiles";
+const PROFILE_CREDENTIALS_HSET: &str = "profile_credentials";
+const PROFILE_SESSION_KEY_PREFIX: &str = "profile_session";
+const WALLET_PROFILES_HSET: &str = "wallet_profiles";
+const CONSUMED_PAYLOAD_KEY_PREFIX: &str = "consumed_payload";
+/// Leaderboards stored as hashes before they moved to sorted sets, migrated on startup.
 const PERFORMANCE_RECORDS_HSET: &str = "performance_records";
-const PERFORMANCE_RECORDS_LIMIT: usize = 10;
+const PERFORMANCE_RECORDS_ZSET: &str = "ranked_performance_records";
+const PERFORMANCE_PLAYERS_HSET: &str = "performance_record_players";
+/// Set once the leaderboards kept only the best record of every player.
+const PERFORMANCE_RECORDS_DEDUPLICATED_KEY: &str = "ranked_performance_records_deduplicated";
+const PERFORMANCE_SUBMISSIONS_LIST: &str = "performance_submissions";
+const PROFILE_SUBMISSIONS_LIST: &str = "profile_performance_submissions";
+/// Score of the best submission of every player, and the record it belongs to.
+const PERFORMANCE_RANK_SCORES_ZSET: &str = "performance_rank_scores";
+const PERFORMANCE_RANK_RECORDS_HSET: &str = "performance_rank_records";
+/// Set once the best records of players were taken from earlier submissions and the boards.
+const PERFORMANCE_RANKS_BACKFILLED_KEY: &str = "performance_ranks_backfilled";
 
 const REVIEWS_HSET: &str = "reviews";
 
@@ -28,11 +45,470 @@ const WINDOW_SECONDS: i64 = 24 * 60 * 60;
 
 const COUPONS_HSET: &str = "coupons";
 
+const COUPON_REDEMPTIONS_LIST: &str = "coupon_redemptions";
+const USER_REDEMPTIONS_LIST: &str = "user_coupon_redemptions";
+const COUPON_CAMPAIGNS_HSET: &str = "coupon_campaigns";
+
+const INACTIVE_COUPONS_SET: &str = "inactive_coupons";
+
+const CLAIMS_HSET: &str = "claims";
+const USER_CLAIMS_SET: &str = "user_claims";
+const QUEUED_CLAIMS_SET: &str = "queued_claims";
+
+const CERTIFICATES_HSET: &str = "certificates";
+const NFT_ITEM_INDEXES_HSET: &str = "nft_item_indexes";
+
+/// Adds the member ARGV[2] of player ARGV[4] to the leaderboard KEYS[1] and drops
+/// the lowest ranked members beyond the capacity ARGV[3]. The hash KEYS[2] holds
+/// the member of each player, entries of dropped members are deleted with them.
+/// Boards only hold members of the hash, see [`DEDUPLICATE_PERFORMANCE_RECORDS_SCRIPT`].
+/// Returns 2 if the earlier member of the player ranks at least as high,
+/// else 1 if the new member is still on the board and 0 if not.
+const ADD_PERFORMANCE_RECORD_SCRIPT: &str = r#"
+local previous = redis.call('HGET', KEYS[2], ARGV[4])
+if previous then
+    local previous_score = redis.call('ZSCORE', KEYS[1], previous)
+    if previous_score then
+        local score = tonumber(ARGV[1])
+        previous_score = tonumber(previous_score)
+        if previous_score > score or (previous_score == score and previous >= ARGV[2]) then
+            return 2
+        end
+        redis.call('ZREM', KEYS[1], previous)
+    end
+end
+redis.call('ZADD', KEYS[1], ARGV[1], ARGV[2])
+local excess = redis.call('ZCARD', KEYS[1]) - tonumber(ARGV[3])
+if excess > 0 then
+    local dropped = {}
+    for _, member in ipairs(redis.call('ZRANGE', KEYS[1], 0, excess - 1)) do
+        dropped[member] = true
+    end
+    redis.call('ZREMRANGEBYRANK', KEYS[1], 0, excess - 1)
+    local entries = redis.call('HGETALL', KEYS[2])
+    for i = 1, #entries, 2 do
+        if dropped[entries[i + 1]] then
+            redis.call('HDEL', KEYS[2], entries[i])
+        end
+    end
+end
+if redis.call('ZSCORE', KEYS[1], ARGV[2]) then
+    redis.call('HSET', KEYS[2], ARGV[4], ARGV[2])
+    return 1
+end
+redis.call('HDEL', KEYS[2], ARGV[4])
+return 0
+"#;
+
+/// Keeps the best member of every player on the leaderboard KEYS[1] and points
+/// the entry of the player in the hash KEYS[2] to it. Members without an entry
+/// belong to the player of their profile name. Returns the removed members.
+const DEDUPLICATE_PERFORMANCE_RECORDS_SCRIPT: &str = r#"
+local owners = {}
+local entries = redis.call('HGETALL', KEYS[2])
+for i = 1, #entries, 2 do
+    owners[entries[i + 1]] = entries[i]
+end
+local kept = {}
+local removed = 0
+local members = redis.call('ZRANGE', KEYS[1], 0, -1, 'REV')
+for _, member in ipairs(members) do
+    local player = owners[member]
+    if not player then
+        local json = string.sub(member, string.find(member, ':', 1, true) + 1)
+        player = cjson.decode(json)['profile_name']
+    end
+    if kept[player] then
+        redis.call('ZREM', KEYS[1], member)
+        removed = removed + 1
+    else
+        kept[player] = true
+        redis.call('HSET', KEYS[2], player, member)
+    end
+end
+return removed
+"#;
+
+/// Keeps the record ARGV[3] with the score ARGV[1] as the best one of player ARGV[2]
+/// unless the sorted set KEYS[1] holds a score at least as high for the player.
+/// The hash KEYS[2] holds the best record of each player.
+const KEEP_BEST_RECORD_SCRIPT: &str = r#"
+local best = redis.call('ZSCORE', KEYS[1], ARGV[2])
+if best and tonumber(best) >= tonumber(ARGV[1]) then
+    return 0
+end
+redis.call('ZADD', KEYS[1], ARGV[1], ARGV[2])
+redis.call('HSET', KEYS[2], ARGV[2], ARGV[3])
+return 1
+"#;
+
+/// The best record of player ARGV[1] from the hash KEYS[2], the number of players
+/// with a higher score in the sorted set KEYS[1] and the number of all players.
+/// Returns nil if the player has no record.
+const RANK_BEST_RECORD_SCRIPT: &str = r#"
+local record = redis.call('HGET', KEYS[2], ARGV[1])
+if not record then
+    return false
+end
+local score = redis.call('ZSCORE', KEYS[1], ARGV[1])
+local higher = redis.call('ZCOUNT', KEYS[1], '(' .. score, '+inf')
+return {record, higher, redis.call('ZCARD', KEYS[1])}
+"#;
+
+/// Stores a redeemed coupon and appends the redemption to its history and
+/// the history of the caller, but only if the coupon still holds the value it was redeemed from.
+/// Returns -1 if the coupon was deactivated in the meantime.
+const REDEEM_COUPON_SCRIPT: &str = r#"
+if redis.call('SISMEMBER', KEYS[3], ARGV[1]) == 1 then
+    return -1
+end
+if redis.call('HGET', KEYS[1], ARGV[1]) == ARGV[2] then
+    redis.call('HSET', KEYS[1], ARGV[1], ARGV[3])
+    redis.call('RPUSH', KEYS[2], ARGV[4])
+    redis.call('RPUSH', KEYS[4], ARGV[4])
+    return 1
+end
+return 0
+"#;
+
+/// Stores an updated coupon if it still holds the value it was updated from,
+/// and (de)activates it when ARGV[4] is '1' or '0'.
+const UPDATE_COUPON_SCRIPT: &str = r#"
+if redis.call('HGET', KEYS[1], ARGV[1]) == ARGV[2] then
+    redis.call('HSET', KEYS[1], ARGV[1], ARGV[3])
+    if ARGV[4] == '1' then
+        redis.call('SREM', KEYS[2], ARGV[1])
+    elseif ARGV[4] == '0' then
+        redis.call('SADD', KEYS[2], ARGV[1])
+    end
+    return 1
+end
+return 0
+"#;
+
+/// Removes a coupon with its redemption history and deactivation flag.
+/// Its redemptions are also removed from the histories of the callers, `ARGV[2]:<redeemed_by>`.
+const DELETE_COUPON_SCRIPT: &str = r#"
+if redis.call('HDEL', KEYS[1], ARGV[1]) == 1 then
+    for _, redemption in ipairs(redis.call('LRANGE', KEYS[2], 0, -1)) do
+        local redeemed_by = cjson.decode(redemption)['redeemed_by']
+        redis.call('LREM', ARGV[2] .. ':' .. redeemed_by, 1, redemption)
+    end
+    redis.call('DEL', KEYS[2])
+    redis.call('SREM', KEYS[3], ARGV[1])
+    return 1
+end
+return 0
+"#;
+
+/// Stores a new claim and indexes it by user and, if ARGV[3] is 1, in the
+/// claim queue, returns 0 if the id is taken.
+const CREATE_CLAIM_SCRIPT: &str = r#"
+if redis.call('HSETNX', KEYS[1], ARGV[1], ARGV[2]) == 0 then
+    return 0
+end
+redis.call('SADD', KEYS[2], ARGV[1])
+if ARGV[3] == '1' then
+    redis.call('SADD', KEYS[3], ARGV[1])
+end
+return 1
+"#;
+
+/// Replaces a claim only if it exists and adds it to or removes it from the
+/// claim queue by ARGV[3], returns 1 if it was replaced.
+const UPDATE_CLAIM_SCRIPT: &str = r#"
+if redis.call('HEXISTS', KEYS[1], ARGV[1]) == 0 then
+    return 0
+end
+redis.call('HSET', KEYS[1], ARGV[1], ARGV[2])
+if ARGV[3] == '1' then
+    redis.call('SADD', KEYS[2], ARGV[1])
+else
+    redis.call('SREM', KEYS[2], ARGV[1])
+end
+return 1
+"#;
+
+/// Replaces a certificate only if it exists, returns 1 if it was replaced.
+const UPDATE_CERTIFICATE_SCRIPT: &str = r#"
+if redis.call('HEXISTS', KEYS[1], ARGV[1]) == 0 then
+    return 0
+end
+redis.call('HSET', KEYS[1], ARGV[1], ARGV[2])
+return 1
+"#;
+
+/// Reserves the next free item index of a collection, at least ARGV[2], and returns it.
+const RESERVE_ITEM_INDEX_SCRIPT: &str = r#"
+local free = tonumber(redis.call('HGET', KEYS[1], ARGV[1]) or '0')
+local index = math.max(free, tonumber(ARGV[2]))
+redis.call('HSET', KEYS[1], ARGV[1], index + 1)
+return index
+"#;
+
+/// Member of a record on a leaderboard sorted set. Members with the same score
+/// are ordered by their text, the leading date ranks later records better.
+fn performance_member(record: &PerformanceRecord) -> Result<String, RepositoryError> {
+    let json = serde_json::to_string(record)
+        .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
+    Ok(format!(
+        "{:015}:{}",
+        record.date.timestamp_millis().max(0),
+        json
+    ))
+}
+
+/// Keys matching `pattern` that hold a value of type `key_type`.
+async fn scan_keys(
+    conn: &mut redis::aio::MultiplexedConnection,
+    pattern: &str,
+    key_type: &str,
+) -> Result<Vec<String>, RepositoryError> {
+    let mut keys: Vec<String> = Vec::new();
+    let mut cursor: u64 = 0;
+    loop {
+        let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("MATCH")
+            .arg(pattern)
+            .arg("TYPE")
+            .arg(key_type)
+            .query_async(conn)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        keys.extend(batch);
+        if next == 0 {
+            return Ok(keys);
+        }
+        cursor = next;
+    }
+}
+
+fn parse_performance_member(member: &str) -> Result<PerformanceRecord, RepositoryError> {
+    let (_, json) = member.split_once(':').ok_or_else(|| {
+        RepositoryError::InternalError("Invalid Performance Record format".to_string())
+    })?;
+    serde_json::from_str(json).map_err(|err| RepositoryError::InternalError(err.to_string()))
+}
+
+/// A record stored in a leaderboard hash, in the current or the legacy format.
+fn parse_hash_performance_record(record_json: &str) -> Result<PerformanceRecord, RepositoryError> {
+    if let Ok(record) = serde_json::from_str::<PerformanceRecord>(record_json) {
+        return Ok(record);
+    }
+    serde_json::from_str::<LegacyPerformanceRecord>(record_json)
+        .map(Into::into)
+        .map_err(|_| {
+            RepositoryError::InternalError("Invalid Performance Record format".to_string())
+        })
+}
+
 impl RedisStorage {
-    pub fn new(url: &str) -> Self {
-        Self {
-            client: redis::Client::open(url).expect("Invalid Redis URL"),
+    /// Opens a client for `url` and checks that the server responds.
+    pub async fn new(url: &str) -> Result<Self, RepositoryError> {
+        let client =
+            redis::Client::open(url).map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let mut conn = client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        redis::cmd("PING")
+            .query_async::<_, String>(&mut conn)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let storage = Self { client };
+        storage.migrate_performance_records().await?;
+        Ok(storage)
+    }
+
+    /// Moves leaderboards from hashes into sorted sets, converting legacy
+    /// records and keying them by profile name, then drops all but the best
+    /// record of every player once. Each board is moved in one transaction, so
+    /// a restart resumes with the boards not moved yet.
+    async fn migrate_performance_records(&self) -> Result<(), RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let pattern = format!("{}:*", PERFORMANCE_RECORDS_HSET);
+        for hset in scan_keys(&mut conn, &pattern, "hash").await? {
+            let namespace = &hset[PERFORMANCE_RECORDS_HSET.len() + 1..];
+            let zset = format!("{}:{}", PERFORMANCE_RECORDS_ZSET, namespace);
+            let players = format!("{}:{}", PERFORMANCE_PLAYERS_HSET, namespace);
+            let records: Vec<(String, String)> = conn
+                .hgetall(&hset)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+            let mut ranked = Vec::with_capacity(records.len());
+            for (_, record_json) in &records {
+                let record = parse_hash_performance_record(record_json)?;
+                let member = performance_member(&record)?;
+                ranked.push((performance_score(&record), member, record.profile_name));
+            }
+            // Best first, like the board ranks them
+            ranked.sort_by(|a, b| (b.0, &b.1).cmp(&(a.0, &a.1)));
+
+            let mut pipe = redis::pipe();
+            pipe.atomic();
+            let mut seen = std::collections::HashSet::new();
+            for (score, member, profile_name) in &ranked {
+                // Players are known by name before, only their best record stays
+                if seen.insert(profile_name.as_str()) {
+                    pipe.zadd(&zset, member, *score).ignore();
+                    pipe.hset(&players, profile_name, member).ignore();
+                }
+            }
+            pipe.del(&hset).ignore();
+            pipe.query_async::<_, ()>(&mut conn)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            log::info!(
+                "Migrated {} performance records of {} players of {} to a sorted set",
+                records.len(),
+                seen.len(),
+                namespace
+            );
+        }
+
+        let deduplicated: bool = conn
+            .exists(PERFORMANCE_RECORDS_DEDUPLICATED_KEY)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        if !deduplicated {
+            self.deduplicate_performance_records().await?;
+            conn.set::<_, _, ()>(PERFORMANCE_RECORDS_DEDUPLICATED_KEY, 1)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        }
+
+        let backfilled: bool = conn
+            .exists(PERFORMANCE_RANKS_BACKFILLED_KEY)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        if !backfilled {
+            self.backfill_performance_ranks().await?;
+            conn.set::<_, _, ()>(PERFORMANCE_RANKS_BACKFILLED_KEY, 1)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Keeps the best record of every player from the submissions and boards
+    /// stored before best records were, submitters are known by profile name.
+    async fn backfill_performance_ranks(&self) -> Result<(), RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let mut ranked: Vec<(String, String, PerformanceRecord)> = Vec::new();
+        let pattern = format!("{}:*", PERFORMANCE_SUBMISSIONS_LIST);
+        for list in scan_keys(&mut conn, &pattern, "list").await? {
+            let namespace = &list[PERFORMANCE_SUBMISSIONS_LIST.len() + 1..];
+            for record in self.fetch_submission_list(&list).await? {
+                ranked.push((namespace.to_string(), record.profile_name.clone(), record));
+            }
+        }
+        let pattern = format!("{}:*", PERFORMANCE_RECORDS_ZSET);
+        for zset in scan_keys(&mut conn, &pattern, "zset").await? {
+            let namespace = &zset[PERFORMANCE_RECORDS_ZSET.len() + 1..];
+            let players = format!("{}:{}", PERFORMANCE_PLAYERS_HSET, namespace);
+            let members: std::collections::HashSet<String> = conn
+                .zrange(&zset, 0, -1)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            let entries: Vec<(String, String)> = conn
+                .hgetall(&players)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            for (player, member) in entries {
+                if members.contains(&member) {
+                    let record = parse_performance_member(&member)?;
+                    ranked.push((namespace.to_string(), player, record));
+                }
+            }
+        }
+
+        for (namespace, player, record) in &ranked {
+            let record_json = serde_json::to_string(record)
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            redis::Script::new(KEEP_BEST_RECORD_SCRIPT)
+                .key(format!("{}:{}", PERFORMANCE_RANK_SCORES_ZSET, namespace))
+                .key(format!("{}:{}", PERFORMANCE_RANK_RECORDS_HSET, namespace))
+                .arg(performance_score(record))
+                .arg(player)
+                .arg(record_json)
+                .invoke_async::<_, i64>(&mut conn)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        }
+        if !ranked.is_empty() {
+            log::info!(
+                "Kept the best records of players from {} earlier records",
+                ranked.len()
+            );
+        }
+        Ok(())
+    }
+
+    /// Drops all but the best record of every player from the leaderboards,
+    /// boards filled before players were tracked hold several of them.
+    async fn deduplicate_performance_records(&self) -> Result<(), RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let pattern = format!("{}:*", PERFORMANCE_RECORDS_ZSET);
+        for zset in scan_keys(&mut conn, &pattern, "zset").await? {
+            let namespace = &zset[PERFORMANCE_RECORDS_ZSET.len() + 1..];
+            let players = format!("{}:{}", PERFORMANCE_PLAYERS_HSET, namespace);
+            let removed: usize = redis::Script::new(DEDUPLICATE_PERFORMANCE_RECORDS_SCRIPT)
+                .key(&zset)
+                .key(&players)
+                .invoke_async(&mut conn)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            if removed > 0 {
+                log::info!(
+                    "Removed {} performance records of players with better ones from {}",
+                    removed,
+                    namespace
+                );
+            }
         }
+        Ok(())
+    }
+
+    /// Reads the submitted records kept in the list `list`.
+    async fn fetch_submission_list(
+        &self,
+        list: &str,
+    ) -> Result<Vec<PerformanceRecord>, RepositoryError> {
+        let mut connection = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
+        let submissions: Vec<String> = connection
+            .lrange(list, 0, -1)
+            .await
+            .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
+        submissions
+            .iter()
+            .map(|json| {
+                serde_json::from_str(json)
+                    .map_err(|err| RepositoryError::InternalError(err.to_string()))
+            })
+            .collect()
     }
 }
 
@@ -47,10 +523,11 @@ impl ProfileRepository for RedisStorage {
             .await
             .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
 
-        let profile_json: String = conn
+        let profile_json: Option<String> = conn
             .hget(PROFILES_HSET, &profile_id)
             .await
             .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        let profile_json = profile_json.ok_or(RepositoryError::NotFound(profile_id))?;
         serde_json::from_str(&profile_json)
             .map_err(|e| RepositoryError::InternalError(e.to_string()))
     }
@@ -74,7 +551,7 @@ impl ProfileRepository for RedisStorage {
             .collect()
     }
 
-    async fn save(&mut self, profile: PlayerProfile) -> Result<PlayerProfile, RepositoryError> {
+    async fn save(&self, profile: PlayerProfile) -> Result<PlayerProfile, RepositoryError> {
         let mut conn = self
             .client
             .get_multiplexed_async_connection()
@@ -90,165 +567,325 @@ impl ProfileRepository for RedisStorage {
 }
 
 #[async_trait]
-impl LeaderboardRepository for RedisStorage {
-    async fn fetch_performance_records(
+impl ProfileCredentialRepository for RedisStorage {
+    async fn create_credential(
         &self,
-        namespace: &str,
-    ) -> Result<Vec<PerformanceRecord>, RepositoryError> {
-        let hset = format!("{}:{}", PERFORMANCE_RECORDS_HSET, namespace);
-        let mut connection = self
+        profile_id: &str,
+        secret_hash: &str,
+    ) -> Result<(), RepositoryError> {
+        let mut conn = self
             .client
             .get_multiplexed_async_connection()
             .await
-            .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
 
-        // Get all keys and values
-        let records: Vec<(String, String)> = redis::cmd("HGETALL")
-            .arg(&hset)
-            .query_async(&mut connection)
+        let created: bool = conn
+            .hset_nx(PROFILE_CREDENTIALS_HSET, profile_id, secret_hash)
             .await
-            .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if !created {
+            return Err(RepositoryError::AlreadyExists(profile_id.to_string()));
+        }
+        Ok(())
+    }
 
-        let mut performance_records = Vec::new();
-        let mut updates_needed = Vec::new();
+    async fn fetch_credential(&self, profile_id: &str) -> Result<Option<String>, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
 
-        for (key, record_json) in records {
-            // Try to deserialize as PerformanceRecord first
-            if let Ok(record) = serde_json::from_str::<PerformanceRecord>(&record_json) {
-                performance_records.push(record);
-                continue;
-            }
+        conn.hget(PROFILE_CREDENTIALS_HSET, profile_id)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))
+    }
 
-            // If that fails, try as LegacyPerformanceRecord
-            if let Ok(legacy_record) = serde_json::from_str::<LegacyPerformanceRecord>(&record_json)
-            {
-                let converted_record: PerformanceRecord = legacy_record.into();
+    async fn create_session(
+        &self,
+        token_hash: &str,
+        session: ProfileSession,
+    ) -> Result<(), RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
 
-                // Store the key and the new format for updating
-                updates_needed.push((key, converted_record.clone()));
-                performance_records.push(converted_record);
-                continue;
-            }
+        let ttl = (session.expires_at - chrono::Utc::now()).num_seconds();
+        if ttl <= 0 {
+            return Ok(());
+        }
+        let session_json = serde_json::to_string(&session)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        // Redis drops the session once it expires
+        conn.set_ex(
+            format!("{}:{}", PROFILE_SESSION_KEY_PREFIX, token_hash),
+            session_json,
+            ttl as u64,
+        )
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))
+    }
 
-            return Err(RepositoryError::InternalError(
-                "Invalid Performance Record format".to_string(),
-            ));
+    async fn fetch_session(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<ProfileSession>, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let session_json: Option<String> = conn
+            .get(format!("{}:{}", PROFILE_SESSION_KEY_PREFIX, token_hash))
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        session_json
+            .map(|json| {
+                serde_json::from_str(&json)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .transpose()
+    }
+
+    async fn link_wallet(&self, address: &str, profile_id: &str) -> Result<(), RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let linked: bool = conn
+            .hset_nx(WALLET_PROFILES_HSET, address, profile_id)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if !linked {
+            return Err(RepositoryError::AlreadyExists(address.to_string()));
         }
+        Ok(())
+    }
 
-        // Update any legacy records in the storage
-        if !updates_needed.is_empty() {
-            for (key, record) in updates_needed {
-                let record_json = serde_json::to_string(&record)
-                    .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
+    async fn fetch_wallet_profile(&self, address: &str) -> Result<Option<String>, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
 
-                redis::cmd("HSET")
-                    .arg(&hset)
-                    .arg(key)
-                    .arg(record_json)
-                    .query_async(&mut connection)
-                    .await
-                    .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
-            }
+        conn.hget(WALLET_PROFILES_HSET, address)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))
+    }
+
+    async fn consume_payload(
+        &self,
+        payload_hash: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        // Redis drops the key once the payload expired anyway
+        let ttl = (expires_at - chrono::Utc::now()).num_seconds().max(1);
+        let stored: Option<String> = redis::cmd("SET")
+            .arg(format!("{}:{}", CONSUMED_PAYLOAD_KEY_PREFIX, payload_hash))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if stored.is_none() {
+            return Err(RepositoryError::AlreadyExists(payload_hash.to_string()));
         }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LeaderboardRepository for RedisStorage {
+    async fn fetch_performance_records(
+        &self,
+        namespace: &str,
+    ) -> Result<Vec<PerformanceRecord>, RepositoryError> {
+        let zset = format!("{}:{}", PERFORMANCE_RECORDS_ZSET, namespace);
+        let mut connection = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
+
+        let members: Vec<String> = redis::cmd("ZRANGE")
+            .arg(&zset)
+            .arg(0)
+            .arg(-1)
+            .arg("REV")
+            .query_async(&mut connection)
+            .await
+            .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
 
-        Ok(performance_records)
+        members
+            .iter()
+            .map(|member| parse_performance_member(member))
+            .collect()
     }
 
     async fn add_performance_record(
-        &mut self,
+        &self,
         namespace: &str,
+        player: &str,
         performance_record: PerformanceRecord,
+        capacity: usize,
     ) -> Result<PerformanceRecord, RepositoryError> {
-        let hset = format!("{}:{}", PERFORMANCE_RECORDS_HSET, namespace);
+        let zset = format!("{}:{}", PERFORMANCE_RECORDS_ZSET, namespace);
+        let players = format!("{}:{}", PERFORMANCE_PLAYERS_HSET, namespace);
         let mut connection = self
             .client
             .get_multiplexed_async_connection()
             .await
             .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
-        let performance_record_json = serde_json::to_string(&performance_record)
-            .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
-        let performance_records_count: usize = redis::cmd("HLEN")
-            .arg(&hset)
-            .query_async(&mut connection)
+
+        let result: u8 = redis::Script::new(ADD_PERFORMANCE_RECORD_SCRIPT)
+            .key(&zset)
+            .key(&players)
+            .arg(performance_score(&performance_record))
+            .arg(performance_member(&performance_record)?)
+            .arg(capacity)
+            .arg(player)
+            .invoke_async(&mut connection)
             .await
             .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
-        if performance_records_count < PERFORMANCE_RECORDS_LIMIT {
-            let mut hasher = DefaultHasher::default();
-            performance_record.hash(&mut hasher);
-            let id = hasher.finish().to_string();
-
-            redis::cmd("hset")
-                .arg(&hset)
-                .arg(id)
-                .arg(performance_record_json)
-                .query_async(&mut connection)
-                .await
-                .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
-            Ok(performance_record)
-        } else {
-            Err(RepositoryError::LimitReached(PERFORMANCE_RECORDS_LIMIT))
+
+        match result {
+            1 => Ok(performance_record),
+            2 => Err(RepositoryError::AlreadyExists(player.to_string())),
+            _ => Err(RepositoryError::LimitReached(capacity)),
         }
     }
 
     async fn remove_performance_record(
-        &mut self,
+        &self,
         namespace: &str,
         performance_record: PerformanceRecord,
     ) -> Result<PerformanceRecord, RepositoryError> {
-        let hset = format!("{}:{}", PERFORMANCE_RECORDS_HSET, namespace);
+        let zset = format!("{}:{}", PERFORMANCE_RECORDS_ZSET, namespace);
         let mut connection = self
             .client
             .get_multiplexed_async_connection()
             .await
             .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
 
-        // Get all records with their keys
-        let records: Vec<(String, String)> = redis::cmd("HGETALL")
-            .arg(&hset)
-            .query_async(&mut connection)
+        let removed: usize = connection
+            .zrem(&zset, performance_member(&performance_record)?)
             .await
             .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
 
-        // Find the matching record and its key
-        for (key, record_json) in records {
-            let record = if let Ok(record) = serde_json::from_str::<PerformanceRecord>(&record_json)
-            {
-                record
-            } else if let Ok(legacy_record) =
-                serde_json::from_str::<LegacyPerformanceRecord>(&record_json)
-            {
-                legacy_record.into()
-            } else {
-                continue;
-            };
+        if removed == 0 {
+            return Err(RepositoryError::NotFound(
+                "No matching record found".to_string(),
+            ));
+        }
+        Ok(performance_record)
+    }
 
-            // Compare relevant fields to find a match
-            if record.profile_name == performance_record.profile_name
-                && record.performance_percentage == performance_record.performance_percentage
-                && record.date == performance_record.date
-                && record.challenges_performance == performance_record.challenges_performance
-            {
-                // Remove the record using its key
-                redis::cmd("HDEL")
-                    .arg(&hset)
-                    .arg(key)
-                    .query_async(&mut connection)
-                    .await
-                    .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
-
-                return Ok(performance_record);
-            }
+    async fn record_submission(
+        &self,
+        namespace: &str,
+        player: &str,
+        profile_id: Option<&str>,
+        performance_record: PerformanceRecord,
+    ) -> Result<(), RepositoryError> {
+        let list = format!("{}:{}", PERFORMANCE_SUBMISSIONS_LIST, namespace);
+        let scores = format!("{}:{}", PERFORMANCE_RANK_SCORES_ZSET, namespace);
+        let best_records = format!("{}:{}", PERFORMANCE_RANK_RECORDS_HSET, namespace);
+        let mut connection = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
+        let performance_record_json = serde_json::to_string(&performance_record)
+            .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .rpush(&list, &performance_record_json)
+            .ignore();
+        if let Some(profile_id) = profile_id {
+            let profile_list = format!("{}:{}", PROFILE_SUBMISSIONS_LIST, profile_id);
+            pipe.rpush(&profile_list, &performance_record_json).ignore();
         }
+        pipe.cmd("EVAL")
+            .arg(KEEP_BEST_RECORD_SCRIPT)
+            .arg(2)
+            .arg(&scores)
+            .arg(&best_records)
+            .arg(performance_score(&performance_record))
+            .arg(player)
+            .arg(&performance_record_json)
+            .ignore();
+        pipe.query_async::<_, ()>(&mut connection)
+            .await
+            .map_err(|err| RepositoryError::InternalError(err.to_string()))
+    }
+
+    async fn fetch_submissions(
+        &self,
+        namespace: &str,
+    ) -> Result<Vec<PerformanceRecord>, RepositoryError> {
+        let list = format!("{}:{}", PERFORMANCE_SUBMISSIONS_LIST, namespace);
+        self.fetch_submission_list(&list).await
+    }
+
+    async fn fetch_ranked_record(
+        &self,
+        namespace: &str,
+        player: &str,
+    ) -> Result<Option<RankedRecord>, RepositoryError> {
+        let mut connection = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
+        let ranked: Option<(String, usize, usize)> = redis::Script::new(RANK_BEST_RECORD_SCRIPT)
+            .key(format!("{}:{}", PERFORMANCE_RANK_SCORES_ZSET, namespace))
+            .key(format!("{}:{}", PERFORMANCE_RANK_RECORDS_HSET, namespace))
+            .arg(player)
+            .invoke_async(&mut connection)
+            .await
+            .map_err(|err| RepositoryError::InternalError(err.to_string()))?;
+        ranked
+            .map(|(json, higher, total)| {
+                serde_json::from_str(&json)
+                    .map(|record| RankedRecord {
+                        record,
+                        rank: higher + 1,
+                        total,
+                    })
+                    .map_err(|err| RepositoryError::InternalError(err.to_string()))
+            })
+            .transpose()
+    }
 
-        Err(RepositoryError::NotFound(
-            "No matching record found".to_string(),
-        ))
+    async fn fetch_profile_submissions(
+        &self,
+        profile_id: &str,
+    ) -> Result<Vec<PerformanceRecord>, RepositoryError> {
+        let list = format!("{}:{}", PROFILE_SUBMISSIONS_LIST, profile_id);
+        self.fetch_submission_list(&list).await
     }
 }
 
 #[async_trait]
 impl ReviewRepository for RedisStorage {
-    async fn store_review(&mut self, review: Review) -> Result<(), RepositoryError> {
+    async fn store_review(&self, review: Review) -> Result<(), RepositoryError> {
         let ns_key = format!("{}:{}", REVIEWS_HSET, review.challenge_id);
         let review_json = serde_json::to_string(&review)
             .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
@@ -372,7 +1009,7 @@ impl CouponRepository for RedisStorage {
         Ok(coupons)
     }
 
-    async fn save(&mut self, coupon: Coupon) -> Result<Coupon, RepositoryError> {
+    async fn save(&self, coupon: Coupon) -> Result<Coupon, RepositoryError> {
         let mut conn = self
             .client
             .get_multiplexed_async_connection()
@@ -388,25 +1025,556 @@ impl CouponRepository for RedisStorage {
 
         Ok(coupon)
     }
-}
 
-#[cfg(feature = "chat")]
-#[async_trait]
-impl MessageReceiver for RedisStorage {
-    async fn receive_messages(&self, channel: &str) -> Result<Vec<Message>, ReceiveError> {
-        let hset = format!("{}:{}", CHAT_MESSAGES_HSET, channel);
-        let mut connection = self
+    async fn create(&self, coupon: Coupon) -> Result<Coupon, RepositoryError> {
+        let mut conn = self
             .client
             .get_multiplexed_async_connection()
             .await
-            .map_err(|err| ReceiveError::InternalError(err.to_string()))?;
-        let messages_data: Vec<String> = redis::cmd("HVALS")
-            .arg(hset)
-            .query_async(&mut connection)
-            .await
-            .map_err(|err| ReceiveError::InternalError(err.to_string()))?;
-        let mut messages = Vec::new();
-        for message_json in messages_data {
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let coupon_json = serde_json::to_string(&coupon)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let created: bool = conn
+            .hset_nx(COUPONS_HSET, &coupon.code, &coupon_json)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if !created {
+            return Err(RepositoryError::AlreadyExists(coupon.code));
+        }
+        Ok(coupon)
+    }
+
+    async fn update(
+        &self,
+        coupon_code: &str,
+        update: &CouponUpdate,
+    ) -> Result<Coupon, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        let script = redis::Script::new(UPDATE_COUPON_SCRIPT);
+        let active = match update.active {
+            Some(true) => "1",
+            Some(false) => "0",
+            None => "",
+        };
+
+        loop {
+            let coupon_json: Option<String> = conn
+                .hget(COUPONS_HSET, coupon_code)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            let coupon_json =
+                coupon_json.ok_or(RepositoryError::NotFound(coupon_code.to_string()))?;
+            let coupon = serde_json::from_str(&coupon_json)
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+            let coupon = update.apply(coupon);
+            let updated_json = serde_json::to_string(&coupon)
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+            let swapped: bool = script
+                .key(COUPONS_HSET)
+                .key(INACTIVE_COUPONS_SET)
+                .arg(coupon_code)
+                .arg(&coupon_json)
+                .arg(&updated_json)
+                .arg(active)
+                .invoke_async(&mut conn)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            if swapped {
+                return Ok(coupon);
+            }
+        }
+    }
+
+    async fn delete(&self, coupon_code: &str) -> Result<(), RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let deleted: bool = redis::Script::new(DELETE_COUPON_SCRIPT)
+            .key(COUPONS_HSET)
+            .key(format!("{}:{}", COUPON_REDEMPTIONS_LIST, coupon_code))
+            .key(INACTIVE_COUPONS_SET)
+            .arg(coupon_code)
+            .arg(USER_REDEMPTIONS_LIST)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if !deleted {
+            return Err(RepositoryError::NotFound(coupon_code.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn is_active(&self, coupon_code: &str) -> Result<bool, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let exists: bool = conn
+            .hexists(COUPONS_HSET, coupon_code)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        if !exists {
+            return Err(RepositoryError::NotFound(coupon_code.to_string()));
+        }
+
+        let inactive: bool = conn
+            .sismember(INACTIVE_COUPONS_SET, coupon_code)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        Ok(!inactive)
+    }
+
+    async fn redeem(
+        &self,
+        coupon_code: &str,
+        challenge_id: &str,
+        redeemed_by: &str,
+    ) -> Result<Redemption, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        let script = redis::Script::new(REDEEM_COUPON_SCRIPT);
+        let redemptions_key = format!("{}:{}", COUPON_REDEMPTIONS_LIST, coupon_code);
+        let user_redemptions_key = format!("{}:{}", USER_REDEMPTIONS_LIST, redeemed_by);
+
+        loop {
+            let coupon_json: Option<String> = conn
+                .hget(COUPONS_HSET, coupon_code)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            let Some(coupon_json) = coupon_json else {
+                return Ok(Redemption::Rejected);
+            };
+            let coupon = serde_json::from_str(&coupon_json)
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+            let redemption = redeem_coupon(coupon, challenge_id, redeemed_by);
+            let Redemption::Redeemed(coupon, coupon_redemption) = &redemption else {
+                return Ok(redemption);
+            };
+            let updated_json = serde_json::to_string(coupon)
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            let redemption_json = serde_json::to_string(coupon_redemption)
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+            let result: i64 = script
+                .key(COUPONS_HSET)
+                .key(&redemptions_key)
+                .key(INACTIVE_COUPONS_SET)
+                .key(&user_redemptions_key)
+                .arg(coupon_code)
+                .arg(&coupon_json)
+                .arg(&updated_json)
+                .arg(&redemption_json)
+                .invoke_async(&mut conn)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            match result {
+                1 => return Ok(redemption),
+                -1 => return Ok(Redemption::Rejected),
+                _ => continue,
+            }
+        }
+    }
+
+    async fn fetch_redemptions(
+        &self,
+        coupon_code: &str,
+    ) -> Result<Vec<CouponRedemption>, RepositoryError> {
+        let key = format!("{}:{}", COUPON_REDEMPTIONS_LIST, coupon_code);
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let redemption_jsons: Vec<String> = conn
+            .lrange(&key, 0, -1)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        redemption_jsons
+            .iter()
+            .map(|json| {
+                serde_json::from_str::<CouponRedemption>(json)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .collect()
+    }
+
+    async fn fetch_user_redemptions(
+        &self,
+        redeemed_by: &str,
+    ) -> Result<Vec<CouponRedemption>, RepositoryError> {
+        let key = format!("{}:{}", USER_REDEMPTIONS_LIST, redeemed_by);
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let redemption_jsons: Vec<String> = conn
+            .lrange(&key, 0, -1)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        redemption_jsons
+            .iter()
+            .map(|json| {
+                serde_json::from_str::<CouponRedemption>(json)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .collect()
+    }
+
+    async fn fetch_campaign(&self, name: &str) -> Result<Option<CouponCampaign>, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let campaign_json: Option<String> = conn
+            .hget(COUPON_CAMPAIGNS_HSET, name)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        match campaign_json {
+            Some(json) => {
+                let campaign = serde_json::from_str(&json)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+                Ok(Some(campaign))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn create_campaign(
+        &self,
+        campaign: CouponCampaign,
+    ) -> Result<CouponCampaign, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let campaign_json = serde_json::to_string(&campaign)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let created: bool = conn
+            .hset_nx(COUPON_CAMPAIGNS_HSET, &campaign.name, &campaign_json)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if !created {
+            return Err(RepositoryError::AlreadyExists(campaign.name));
+        }
+        Ok(campaign)
+    }
+
+    async fn save_campaign(
+        &self,
+        campaign: CouponCampaign,
+    ) -> Result<CouponCampaign, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let campaign_json = serde_json::to_string(&campaign)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        conn.hset(COUPON_CAMPAIGNS_HSET, &campaign.name, &campaign_json)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        Ok(campaign)
+    }
+
+    async fn delete_campaign(&self, name: &str) -> Result<(), RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let deleted: bool = conn
+            .hdel(COUPON_CAMPAIGNS_HSET, name)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if !deleted {
+            return Err(RepositoryError::NotFound(name.to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ClaimRepository for RedisStorage {
+    async fn create_claim(&self, claim: ClaimRecord) -> Result<ClaimRecord, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let claim_json = serde_json::to_string(&claim)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        let created: bool = redis::Script::new(CREATE_CLAIM_SCRIPT)
+            .key(CLAIMS_HSET)
+            .key(format!("{}:{}", USER_CLAIMS_SET, claim.user))
+            .key(QUEUED_CLAIMS_SET)
+            .arg(claim.id)
+            .arg(&claim_json)
+            .arg(claim.is_queued() as u8)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if !created {
+            return Err(RepositoryError::AlreadyExists(claim.id.to_string()));
+        }
+        Ok(claim)
+    }
+
+    async fn fetch_claim(&self, id: i64) -> Result<Option<ClaimRecord>, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let claim_json: Option<String> = conn
+            .hget(CLAIMS_HSET, id)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        claim_json
+            .map(|json| {
+                serde_json::from_str(&json)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .transpose()
+    }
+
+    async fn fetch_user_claims(&self, user: &str) -> Result<Vec<ClaimRecord>, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let ids: Vec<i64> = conn
+            .smembers(format!("{}:{}", USER_CLAIMS_SET, user))
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let claim_jsons: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(CLAIMS_HSET)
+            .arg(&ids)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        claim_jsons
+            .into_iter()
+            .flatten()
+            .map(|json| {
+                serde_json::from_str(&json)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .collect()
+    }
+
+    async fn fetch_queued_claims(&self) -> Result<Vec<ClaimRecord>, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let ids: Vec<i64> = conn
+            .smembers(QUEUED_CLAIMS_SET)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let claim_jsons: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(CLAIMS_HSET)
+            .arg(&ids)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        claim_jsons
+            .into_iter()
+            .flatten()
+            .map(|json| {
+                serde_json::from_str(&json)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .collect()
+    }
+
+    async fn update_claim(&self, claim: ClaimRecord) -> Result<ClaimRecord, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let claim_json = serde_json::to_string(&claim)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        let updated: i32 = redis::Script::new(UPDATE_CLAIM_SCRIPT)
+            .key(CLAIMS_HSET)
+            .key(QUEUED_CLAIMS_SET)
+            .arg(claim.id)
+            .arg(&claim_json)
+            .arg(claim.is_queued() as u8)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if updated == 0 {
+            return Err(RepositoryError::NotFound(claim.id.to_string()));
+        }
+        Ok(claim)
+    }
+}
+
+#[async_trait]
+impl CertificateRepository for RedisStorage {
+    async fn create_certificate(
+        &self,
+        certificate: CertificateRecord,
+    ) -> Result<CertificateRecord, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let certificate_json = serde_json::to_string(&certificate)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        let created: bool = conn
+            .hset_nx(CERTIFICATES_HSET, &certificate.id, &certificate_json)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if !created {
+            return Err(RepositoryError::AlreadyExists(certificate.id));
+        }
+        Ok(certificate)
+    }
+
+    async fn fetch_certificate(
+        &self,
+        id: &str,
+    ) -> Result<Option<CertificateRecord>, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let certificate_json: Option<String> = conn
+            .hget(CERTIFICATES_HSET, id)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        certificate_json
+            .map(|json| {
+                serde_json::from_str(&json)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .transpose()
+    }
+
+    async fn update_certificate(
+        &self,
+        certificate: CertificateRecord,
+    ) -> Result<CertificateRecord, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let certificate_json = serde_json::to_string(&certificate)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        let updated: i32 = redis::Script::new(UPDATE_CERTIFICATE_SCRIPT)
+            .key(CERTIFICATES_HSET)
+            .arg(&certificate.id)
+            .arg(&certificate_json)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if updated == 0 {
+            return Err(RepositoryError::NotFound(certificate.id));
+        }
+        Ok(certificate)
+    }
+
+    async fn reserve_item_index(
+        &self,
+        collection: &str,
+        next_index: u64,
+    ) -> Result<u64, RepositoryError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        redis::Script::new(RESERVE_ITEM_INDEX_SCRIPT)
+            .key(NFT_ITEM_INDEXES_HSET)
+            .arg(collection)
+            .arg(next_index)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))
+    }
+}
+
+#[cfg(feature = "chat")]
+#[async_trait]
+impl MessageReceiver for RedisStorage {
+    async fn receive_messages(&self, channel: &str) -> Result<Vec<Message>, ReceiveError> {
+        let hset = format!("{}:{}", CHAT_MESSAGES_HSET, channel);
+        let mut connection = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|err| ReceiveError::InternalError(err.to_string()))?;
+        let messages_data: Vec<String> = redis::cmd("HVALS")
+            .arg(hset)
+            .query_async(&mut connection)
+            .await
+            .map_err(|err| ReceiveError::InternalError(err.to_string()))?;
+        let mut messages = Vec::new();
+        for message_json in messages_data {
             let message: Message = serde_json::from_str(&message_json)
                 .map_err(|err| ReceiveError::InternalError(err.to_string()))?;
             messages.push(message);
@@ -452,15 +1620,16 @@ impl WindowedCounterRepository for RedisStorage {
             .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
 
         let key = format!("{}:{}", USER_COUNTER_KEY, namespace);
+        let min_timestamp = chrono::Utc::now().timestamp() - WINDOW_SECONDS;
         let count: u32 = conn
-            .zcount(&key, "-inf", "+inf")
+            .zcount(&key, format!("({}", min_timestamp), "+inf")
             .await
             .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
 
         Ok(count)
     }
 
-    async fn record_presence(&mut self, namespace: &str) -> Result<u32, RepositoryError> {
+    async fn record_presence(&self, namespace: &str) -> Result<u32, RepositoryError> {
         let mut conn = self
             .client
             .get_multiplexed_async_connection()
@@ -470,16 +1639,18 @@ impl WindowedCounterRepository for RedisStorage {
         let key = format!("{}:{}", USER_COUNTER_KEY, namespace);
         let timestamp = chrono::Utc::now().timestamp() as f64;
 
-        // Add presence with current timestamp
+        // Add presence with current timestamp, the member has to be unique
+        // so that several visits within the same second are all counted
+        let member = format!("{}:{}", timestamp, uuid::Uuid::new_v4().simple());
         let _: () = conn
-            .zadd(&key, timestamp.to_string(), timestamp)
+            .zadd(&key, member, timestamp)
             .await
             .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
 
         // Remove old entries (older than 24 hours)
         let min_timestamp = timestamp - WINDOW_SECONDS as f64;
         let _: () = conn
-            .zrevrangebyscore(&key, "-inf", min_timestamp)
+            .zrembyscore(&key, "-inf", min_timestamp)
             .await
             .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
 
@@ -493,3 +1664,255 @@ impl WindowedCounterRepository for RedisStorage {
         self.get_active_count(namespace).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::conformance::{storage_conformance_tests, PERFORMANCE_RECORDS_LIMIT};
+
+    /// Runs against the server in `REDIS_TEST_URL`, skipped when it is not set.
+    async fn create_storage() -> Option<RedisStorage> {
+        let url = std::env::var("REDIS_TEST_URL").ok()?;
+        Some(RedisStorage::new(&url).await.unwrap())
+    }
+
+    storage_conformance_tests!(create_storage);
+
+    fn record(percentage: u8, time: u64, date: &str) -> PerformanceRecord {
+        PerformanceRecord {
+            profile_name: format!("{}-{}-{}", percentage, time, date),
+            challenges_performance: vec![("challenge".to_string(), percentage, time)],
+            performance_percentage: percentage,
+            date: chrono::DateTime::parse_from_rfc3339(date).unwrap().into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_scores_follow_the_record_order() {
+        let mut records = vec![
+            record(90, 100, "2021-08-01T00:00:00Z"),
+            record(100, 300, "2021-08-01T00:00:00Z"),
+            record(100, 200, "2021-08-01T00:00:00Z"),
+            record(100, 200, "2021-08-02T00:00:00Z"),
+        ];
+        records.sort();
+
+        // Best first, like ZRANGE REV orders the sorted set
+        let mut ranked = records.clone();
+        ranked.sort_by_key(|record| {
+            std::cmp::Reverse((
+                performance_score(record),
+                performance_member(record).unwrap(),
+            ))
+        });
+        assert_eq!(ranked, records);
+
+        let member = performance_member(&records[0]).unwrap();
+        assert_eq!(parse_performance_member(&member).unwrap(), records[0]);
+    }
+
+    #[tokio::test]
+    async fn test_hash_leaderboards_are_migrated() {
+        let Some(storage) = create_storage().await else {
+            return;
+        };
+        let namespace = format!("migration_{}", uuid::Uuid::new_v4().simple());
+        let hset = format!("{}:{}", PERFORMANCE_RECORDS_HSET, namespace);
+        let current = record(100, 200, "2021-08-01T00:00:00Z");
+        // An earlier, worse record of the same player
+        let worse = PerformanceRecord {
+            performance_percentage: 60,
+            date: chrono::DateTime::parse_from_rfc3339("2021-07-01T00:00:00Z")
+                .unwrap()
+                .into(),
+            ..current.clone()
+        };
+        let legacy = LegacyPerformanceRecord {
+            game_path_id: "game_path".to_string(),
+            profile_name: "legacy".to_string(),
+            challenges_performance: vec![("challenge".to_string(), 80)],
+            total_challenges: 1,
+            performance_percentage: 80,
+            date: chrono::Utc::now(),
+        };
+        let mut conn = storage
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .unwrap();
+        let _: () = conn
+            .hset_multiple(
+                &hset,
+                &[
+                    ("1", serde_json::to_string(&current).unwrap()),
+                    ("2", serde_json::to_string(&legacy).unwrap()),
+                    ("3", serde_json::to_string(&worse).unwrap()),
+                ],
+            )
+            .await
+            .unwrap();
+
+        storage.migrate_performance_records().await.unwrap();
+        let records = storage.fetch_performance_records(&namespace).await.unwrap();
+        let legacy: PerformanceRecord = legacy.into();
+        assert_eq!(records, vec![current.clone(), legacy.clone()]);
+        let exists: bool = conn.exists(&hset).await.unwrap();
+        assert!(!exists);
+
+        // Migrated records belong to the players of their names
+        let result = storage
+            .add_performance_record(&namespace, "legacy", legacy, PERFORMANCE_RECORDS_LIMIT)
+            .await;
+        assert_eq!(
+            result,
+            Err(RepositoryError::AlreadyExists("legacy".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sorted_set_leaderboards_keep_the_best_record_of_a_player() {
+        let Some(storage) = create_storage().await else {
+            return;
+        };
+        let namespace = format!("deduplication_{}", uuid::Uuid::new_v4().simple());
+        let zset = format!("{}:{}", PERFORMANCE_RECORDS_ZSET, namespace);
+        let named = |name: &str, percentage: u8, date: &str| PerformanceRecord {
+            profile_name: name.to_string(),
+            ..record(percentage, 100, date)
+        };
+        let best = named("alice", 90, "2021-08-01T00:00:00Z");
+        let worse = named("alice", 70, "2021-08-02T00:00:00Z");
+        let bob = named("bob", 80, "2021-08-01T00:00:00Z");
+        let mut conn = storage
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .unwrap();
+        // Filled before the players of the members were tracked
+        for record in [&best, &worse, &bob] {
+            let _: () = conn
+                .zadd(
+                    &zset,
+                    performance_member(record).unwrap(),
+                    performance_score(record),
+                )
+                .await
+                .unwrap();
+        }
+
+        storage.deduplicate_performance_records().await.unwrap();
+        let records = storage.fetch_performance_records(&namespace).await.unwrap();
+        assert_eq!(records, vec![best.clone(), bob.clone()]);
+
+        // The kept records belong to their players
+        let result = storage
+            .add_performance_record(&namespace, "alice", worse, PERFORMANCE_RECORDS_LIMIT)
+            .await;
+        assert_eq!(
+            result,
+            Err(RepositoryError::AlreadyExists("alice".to_string()))
+        );
+        let better = named("alice", 95, "2021-08-03T00:00:00Z");
+        storage
+            .add_performance_record(
+                &namespace,
+                "alice",
+                better.clone(),
+                PERFORMANCE_RECORDS_LIMIT,
+            )
+            .await
+            .unwrap();
+        let records = storage.fetch_performance_records(&namespace).await.unwrap();
+        assert_eq!(records, vec![better, bob]);
+    }
+
+    #[tokio::test]
+    async fn test_best_records_are_taken_from_earlier_submissions_and_boards() {
+        let Some(storage) = create_storage().await else {
+            return;
+        };
+        let namespace = format!("ranks_{}", uuid::Uuid::new_v4().simple());
+        let list = format!("{}:{}", PERFORMANCE_SUBMISSIONS_LIST, namespace);
+        let named = |name: &str, percentage: u8| PerformanceRecord {
+            profile_name: name.to_string(),
+            ..record(percentage, 100, "2021-08-01T00:00:00Z")
+        };
+        let best = named("alice", 90);
+        let worse = named("alice", 70);
+        let bob = named("bob", 80);
+        let mut conn = storage
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .unwrap();
+        // Submitted before best records were kept
+        for record in [&worse, &best] {
+            let _: () = conn
+                .rpush(&list, serde_json::to_string(record).unwrap())
+                .await
+                .unwrap();
+        }
+        // On the board from before submissions were recorded
+        storage
+            .add_performance_record(&namespace, "bob", bob.clone(), PERFORMANCE_RECORDS_LIMIT)
+            .await
+            .unwrap();
+
+        storage.backfill_performance_ranks().await.unwrap();
+        let ranked = storage
+            .fetch_ranked_record(&namespace, "alice")
+            .await
+            .unwrap();
+        assert_eq!(
+            ranked,
+            Some(RankedRecord {
+                record: best,
+                rank: 1,
+                total: 2
+            })
+        );
+        let ranked = storage
+            .fetch_ranked_record(&namespace, "bob")
+            .await
+            .unwrap();
+        assert_eq!(
+            ranked.map(|ranked| (ranked.record, ranked.rank)),
+            Some((bob, 2))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_players_of_dropped_records_are_removed() {
+        let Some(storage) = create_storage().await else {
+            return;
+        };
+        let namespace = format!("eviction_{}", uuid::Uuid::new_v4().simple());
+        let players = format!("{}:{}", PERFORMANCE_PLAYERS_HSET, namespace);
+        let named = |name: &str, percentage: u8| PerformanceRecord {
+            profile_name: name.to_string(),
+            ..record(percentage, 100, "2021-08-01T00:00:00Z")
+        };
+
+        for (player, percentage) in [("alice", 70), ("bob", 80), ("carol", 90)] {
+            storage
+                .add_performance_record(&namespace, player, named(player, percentage), 2)
+                .await
+                .unwrap();
+        }
+        // Too low for the full board, the player is not tracked either
+        let result = storage
+            .add_performance_record(&namespace, "dave", named("dave", 60), 2)
+            .await;
+        assert_eq!(result, Err(RepositoryError::LimitReached(2)));
+
+        let mut conn = storage
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .unwrap();
+        let mut tracked: Vec<String> = conn.hkeys(&players).await.unwrap();
+        tracked.sort();
+        assert_eq!(tracked, vec!["bob".to_string(), "carol".to_string()]);
+    }
+}
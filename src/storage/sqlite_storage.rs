@@ -0,0 +1,1311 @@
+use crate::storage::coupon_repository::redeem_coupon;
+use crate::storage::{
+    evict_performance_records, performance_score, CertificateRecord, CertificateRepository,
+    ClaimRecord, ClaimRepository, CouponCampaign, CouponRedemption, CouponRepository, CouponUpdate,
+    LeaderboardRepository, ProfileCredentialRepository, ProfileRepository, ProfileSession,
+    RankedRecord, Redemption, RepositoryError, ReviewRepository, Storage,
+    WindowedCounterRepository,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use konnektoren_core::challenges::{PerformanceRecord, Review};
+use konnektoren_core::prelude::{Coupon, PlayerProfile};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::{Row, Sqlite, Transaction};
+use std::str::FromStr;
+use std::time::Duration;
+use yew_chat::prelude::{Message, MessageReceiver, MessageSender, ReceiveError, SendError};
+use yew_chat::server::MessageStorage;
+
+const WINDOW_SECONDS: i64 = 24 * 60 * 60;
+/// How long a connection waits for the write lock of another one.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct SqliteStorage {
+    pool: SqlitePool,
+}
+
+impl SqliteStorage {
+    /// Opens (or creates) the database at `url` and applies pending migrations.
+    pub async fn new(url: &str) -> Result<Self, RepositoryError> {
+        let options = SqliteConnectOptions::from_str(url)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?
+            .create_if_missing(true)
+            .busy_timeout(BUSY_TIMEOUT);
+
+        // Every connection to an in-memory database gets its own database,
+        // so keep a single connection to share one store.
+        let max_connections = if url.contains(":memory:") { 1 } else { 5 };
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(max_connections)
+            .connect_with(options)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        sqlx::migrate!("./migrations/sqlite")
+            .run(&pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        Ok(Self { pool })
+    }
+
+    /// Begins a transaction that takes the write lock right away. A deferred
+    /// transaction that reads before it writes fails with `SQLITE_BUSY` when
+    /// another connection wrote in between, without waiting for the lock.
+    async fn begin(&self) -> Result<Transaction<'static, Sqlite>, RepositoryError> {
+        self.pool
+            .begin_with("BEGIN IMMEDIATE")
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))
+    }
+}
+
+impl Storage for SqliteStorage {}
+
+#[async_trait]
+impl ProfileRepository for SqliteStorage {
+    async fn fetch(&self, profile_id: String) -> Result<PlayerProfile, RepositoryError> {
+        let profile_json: Option<String> =
+            sqlx::query_scalar("SELECT data FROM profiles WHERE id = ?")
+                .bind(&profile_id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        match profile_json {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| RepositoryError::InternalError(e.to_string())),
+            None => Err(RepositoryError::NotFound(profile_id)),
+        }
+    }
+
+    async fn fetch_all(&self) -> Result<Vec<PlayerProfile>, RepositoryError> {
+        let profiles_data: Vec<String> = sqlx::query_scalar("SELECT data FROM profiles")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        profiles_data
+            .into_iter()
+            .map(|data| {
+                serde_json::from_str(&data)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .collect()
+    }
+
+    async fn save(&self, profile: PlayerProfile) -> Result<PlayerProfile, RepositoryError> {
+        let profile_json = serde_json::to_string(&profile)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        sqlx::query(
+            "INSERT INTO profiles (id, data) VALUES (?, ?)
+             ON CONFLICT (id) DO UPDATE SET data = excluded.data",
+        )
+        .bind(&profile.id)
+        .bind(&profile_json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        Ok(profile)
+    }
+}
+
+#[async_trait]
+impl ProfileCredentialRepository for SqliteStorage {
+    async fn create_credential(
+        &self,
+        profile_id: &str,
+        secret_hash: &str,
+    ) -> Result<(), RepositoryError> {
+        let result = sqlx::query(
+            "INSERT INTO profile_credentials (profile_id, secret_hash) VALUES (?, ?)
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(profile_id)
+        .bind(secret_hash)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::AlreadyExists(profile_id.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn fetch_credential(&self, profile_id: &str) -> Result<Option<String>, RepositoryError> {
+        sqlx::query_scalar("SELECT secret_hash FROM profile_credentials WHERE profile_id = ?")
+            .bind(profile_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))
+    }
+
+    async fn create_session(
+        &self,
+        token_hash: &str,
+        session: ProfileSession,
+    ) -> Result<(), RepositoryError> {
+        // Expired sessions are dropped whenever a new one is created
+        sqlx::query("DELETE FROM profile_sessions WHERE expires_at <= ?")
+            .bind(Utc::now().timestamp())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        sqlx::query(
+            "INSERT INTO profile_sessions (token_hash, profile_id, expires_at) VALUES (?, ?, ?)",
+        )
+        .bind(token_hash)
+        .bind(&session.profile_id)
+        .bind(session.expires_at.timestamp())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn fetch_session(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<ProfileSession>, RepositoryError> {
+        let row: Option<(String, i64)> = sqlx::query_as(
+            "SELECT profile_id, expires_at FROM profile_sessions WHERE token_hash = ?",
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        row.map(|(profile_id, expires_at)| {
+            let expires_at = DateTime::from_timestamp(expires_at, 0).ok_or_else(|| {
+                RepositoryError::InternalError(format!("Invalid session expiry {}", expires_at))
+            })?;
+            Ok(ProfileSession {
+                profile_id,
+                expires_at,
+            })
+        })
+        .transpose()
+    }
+
+    async fn link_wallet(&self, address: &str, profile_id: &str) -> Result<(), RepositoryError> {
+        let result = sqlx::query(
+            "INSERT INTO wallet_profiles (address, profile_id) VALUES (?, ?)
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(address)
+        .bind(profile_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::AlreadyExists(address.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn fetch_wallet_profile(&self, address: &str) -> Result<Option<String>, RepositoryError> {
+        sqlx::query_scalar("SELECT profile_id FROM wallet_profiles WHERE address = ?")
+            .bind(address)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))
+    }
+
+    async fn consume_payload(
+        &self,
+        payload_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        // Expired payloads are rejected before they get here, so drop them
+        sqlx::query("DELETE FROM consumed_payloads WHERE expires_at <= ?")
+            .bind(Utc::now().timestamp())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let result = sqlx::query(
+            "INSERT INTO consumed_payloads (payload_hash, expires_at) VALUES (?, ?)
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(payload_hash)
+        .bind(expires_at.timestamp())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::AlreadyExists(payload_hash.to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LeaderboardRepository for SqliteStorage {
+    async fn fetch_performance_records(
+        &self,
+        namespace: &str,
+    ) -> Result<Vec<PerformanceRecord>, RepositoryError> {
+        let records_data: Vec<String> = sqlx::query_scalar(
+            "SELECT data FROM performance_records WHERE namespace = ? ORDER BY id",
+        )
+        .bind(namespace)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        records_data
+            .into_iter()
+            .map(|data| {
+                serde_json::from_str(&data)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .collect()
+    }
+
+    async fn add_performance_record(
+        &self,
+        namespace: &str,
+        player: &str,
+        performance_record: PerformanceRecord,
+        capacity: usize,
+    ) -> Result<PerformanceRecord, RepositoryError> {
+        let performance_record_json = serde_json::to_string(&performance_record)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let mut tx = self.begin().await?;
+
+        let previous: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT id, data FROM performance_records WHERE namespace = ? AND player = ?",
+        )
+        .bind(namespace)
+        .bind(player)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        for (id, data) in previous {
+            let previous: PerformanceRecord = serde_json::from_str(&data)
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            if previous <= performance_record {
+                return Err(RepositoryError::AlreadyExists(player.to_string()));
+            }
+            sqlx::query("DELETE FROM performance_records WHERE id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        }
+
+        let count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM performance_records WHERE namespace = ?")
+                .bind(namespace)
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if count as usize >= capacity {
+            let rows = sqlx::query("SELECT id, data FROM performance_records WHERE namespace = ?")
+                .bind(namespace)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            let records = rows
+                .iter()
+                .map(|row| {
+                    let data: String = row.get("data");
+                    serde_json::from_str(&data)
+                        .map(|record| (row.get::<i64, _>("id"), record))
+                        .map_err(|e| RepositoryError::InternalError(e.to_string()))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let (dropped, kept) = evict_performance_records(records, &performance_record, capacity);
+            for id in dropped {
+                sqlx::query("DELETE FROM performance_records WHERE id = ?")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            }
+            if !kept {
+                // Boards above a lowered capacity are still trimmed
+                tx.commit()
+                    .await
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+                return Err(RepositoryError::LimitReached(capacity));
+            }
+        }
+
+        sqlx::query("INSERT INTO performance_records (namespace, player, data) VALUES (?, ?, ?)")
+            .bind(namespace)
+            .bind(player)
+            .bind(&performance_record_json)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        Ok(performance_record)
+    }
+
+    async fn remove_performance_record(
+        &self,
+        namespace: &str,
+        performance_record: PerformanceRecord,
+    ) -> Result<PerformanceRecord, RepositoryError> {
+        let rows = sqlx::query("SELECT id, data FROM performance_records WHERE namespace = ?")
+            .bind(namespace)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        for row in rows {
+            let id: i64 = row.get("id");
+            let data: String = row.get("data");
+            let record: PerformanceRecord = match serde_json::from_str(&data) {
+                Ok(record) => record,
+                Err(_) => continue,
+            };
+
+            if record == performance_record {
+                sqlx::query("DELETE FROM performance_records WHERE id = ?")
+                    .bind(id)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+                return Ok(performance_record);
+            }
+        }
+
+        Err(RepositoryError::NotFound(
+            "No matching record found".to_string(),
+        ))
+    }
+
+    async fn record_submission(
+        &self,
+        namespace: &str,
+        player: &str,
+        profile_id: Option<&str>,
+        performance_record: PerformanceRecord,
+    ) -> Result<(), RepositoryError> {
+        let performance_record_json = serde_json::to_string(&performance_record)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        let mut tx = self.begin().await?;
+        sqlx::query(
+            "INSERT INTO performance_submissions (namespace, profile_id, data) VALUES (?, ?, ?)",
+        )
+        .bind(namespace)
+        .bind(profile_id)
+        .bind(&performance_record_json)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        sqlx::query(
+            "INSERT INTO performance_ranks (namespace, player, score, data) VALUES (?, ?, ?, ?)
+             ON CONFLICT (namespace, player) DO UPDATE SET score = excluded.score, data = excluded.data
+             WHERE excluded.score > performance_ranks.score",
+        )
+        .bind(namespace)
+        .bind(player)
+        .bind(performance_score(&performance_record) as i64)
+        .bind(&performance_record_json)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        tx.commit()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))
+    }
+
+    async fn fetch_ranked_record(
+        &self,
+        namespace: &str,
+        player: &str,
+    ) -> Result<Option<RankedRecord>, RepositoryError> {
+        let best: Option<(i64, String)> = sqlx::query_as(
+            "SELECT score, data FROM performance_ranks WHERE namespace = ? AND player = ?",
+        )
+        .bind(namespace)
+        .bind(player)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        let Some((score, data)) = best else {
+            return Ok(None);
+        };
+        let record = serde_json::from_str(&data)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let (higher, total): (i64, i64) = sqlx::query_as(
+            "SELECT COALESCE(SUM(score > ?), 0), COUNT(*) FROM performance_ranks WHERE namespace = ?",
+        )
+        .bind(score)
+        .bind(namespace)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        Ok(Some(RankedRecord {
+            record,
+            rank: higher as usize + 1,
+            total: total as usize,
+        }))
+    }
+
+    async fn fetch_submissions(
+        &self,
+        namespace: &str,
+    ) -> Result<Vec<PerformanceRecord>, RepositoryError> {
+        let records_data: Vec<String> = sqlx::query_scalar(
+            "SELECT data FROM performance_submissions WHERE namespace = ? ORDER BY id",
+        )
+        .bind(namespace)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        records_data
+            .into_iter()
+            .map(|data| {
+                serde_json::from_str(&data)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .collect()
+    }
+
+    async fn fetch_profile_submissions(
+        &self,
+        profile_id: &str,
+    ) -> Result<Vec<PerformanceRecord>, RepositoryError> {
+        let records_data: Vec<String> = sqlx::query_scalar(
+            "SELECT data FROM performance_submissions WHERE profile_id = ? ORDER BY id",
+        )
+        .bind(profile_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        records_data
+            .into_iter()
+            .map(|data| {
+                serde_json::from_str(&data)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl ReviewRepository for SqliteStorage {
+    async fn store_review(&self, review: Review) -> Result<(), RepositoryError> {
+        let review_json = serde_json::to_string(&review)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        sqlx::query("INSERT INTO reviews (challenge_id, rating, data) VALUES (?, ?, ?)")
+            .bind(&review.challenge_id)
+            .bind(review.rating as i64)
+            .bind(&review_json)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn fetch_reviews(&self, namespace: &str) -> Result<Vec<Review>, RepositoryError> {
+        let review_jsons: Vec<String> =
+            sqlx::query_scalar("SELECT data FROM reviews WHERE challenge_id = ? ORDER BY id")
+                .bind(namespace)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        review_jsons
+            .iter()
+            .map(|json| {
+                serde_json::from_str::<Review>(json)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .collect()
+    }
+
+    async fn fetch_all_reviews(&self) -> Result<Vec<Review>, RepositoryError> {
+        let review_jsons: Vec<String> = sqlx::query_scalar("SELECT data FROM reviews ORDER BY id")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        review_jsons
+            .iter()
+            .map(|json| {
+                serde_json::from_str::<Review>(json)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .collect()
+    }
+
+    async fn fetch_average_rating(&self, namespace: &str) -> Result<f64, RepositoryError> {
+        let average: Option<f64> =
+            sqlx::query_scalar("SELECT AVG(rating) FROM reviews WHERE challenge_id = ?")
+                .bind(namespace)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        Ok(average.unwrap_or(0.0))
+    }
+}
+
+#[async_trait]
+impl CouponRepository for SqliteStorage {
+    async fn fetch(&self, coupon_code: &str) -> Result<Option<Coupon>, RepositoryError> {
+        let coupon_json: Option<String> =
+            sqlx::query_scalar("SELECT data FROM coupons WHERE code = ?")
+                .bind(coupon_code)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        match coupon_json {
+            Some(json) => {
+                let coupon = serde_json::from_str(&json)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+                Ok(Some(coupon))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn fetch_all(&self) -> Result<Vec<Coupon>, RepositoryError> {
+        let coupons_data: Vec<String> = sqlx::query_scalar("SELECT data FROM coupons")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        coupons_data
+            .into_iter()
+            .map(|data| {
+                serde_json::from_str(&data)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .collect()
+    }
+
+    async fn save(&self, coupon: Coupon) -> Result<Coupon, RepositoryError> {
+        let coupon_json = serde_json::to_string(&coupon)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        sqlx::query(
+            "INSERT INTO coupons (code, data) VALUES (?, ?)
+             ON CONFLICT (code) DO UPDATE SET data = excluded.data",
+        )
+        .bind(&coupon.code)
+        .bind(&coupon_json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        Ok(coupon)
+    }
+
+    async fn create(&self, coupon: Coupon) -> Result<Coupon, RepositoryError> {
+        let coupon_json = serde_json::to_string(&coupon)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let result =
+            sqlx::query("INSERT INTO coupons (code, data) VALUES (?, ?) ON CONFLICT DO NOTHING")
+                .bind(&coupon.code)
+                .bind(&coupon_json)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::AlreadyExists(coupon.code));
+        }
+        Ok(coupon)
+    }
+
+    async fn update(
+        &self,
+        coupon_code: &str,
+        update: &CouponUpdate,
+    ) -> Result<Coupon, RepositoryError> {
+        loop {
+            let coupon_json: Option<String> =
+                sqlx::query_scalar("SELECT data FROM coupons WHERE code = ?")
+                    .bind(coupon_code)
+                    .fetch_optional(&self.pool)
+                    .await
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            let coupon_json =
+                coupon_json.ok_or(RepositoryError::NotFound(coupon_code.to_string()))?;
+            let coupon = serde_json::from_str(&coupon_json)
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+            let coupon = update.apply(coupon);
+            let updated_json = serde_json::to_string(&coupon)
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+            // Compare-and-swap, see redeem
+            let result = sqlx::query(
+                "UPDATE coupons SET data = ?, active = COALESCE(?, active)
+                 WHERE code = ? AND data = ?",
+            )
+            .bind(&updated_json)
+            .bind(update.active)
+            .bind(coupon_code)
+            .bind(&coupon_json)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            if result.rows_affected() == 1 {
+                return Ok(coupon);
+            }
+        }
+    }
+
+    async fn delete(&self, coupon_code: &str) -> Result<(), RepositoryError> {
+        let mut tx = self.begin().await?;
+
+        let result = sqlx::query("DELETE FROM coupons WHERE code = ?")
+            .bind(coupon_code)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound(coupon_code.to_string()));
+        }
+
+        sqlx::query("DELETE FROM coupon_redemptions WHERE code = ?")
+            .bind(coupon_code)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))
+    }
+
+    async fn is_active(&self, coupon_code: &str) -> Result<bool, RepositoryError> {
+        let active: Option<bool> = sqlx::query_scalar("SELECT active FROM coupons WHERE code = ?")
+            .bind(coupon_code)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        active.ok_or(RepositoryError::NotFound(coupon_code.to_string()))
+    }
+
+    async fn redeem(
+        &self,
+        coupon_code: &str,
+        challenge_id: &str,
+        redeemed_by: &str,
+    ) -> Result<Redemption, RepositoryError> {
+        loop {
+            let coupon_json: Option<String> =
+                sqlx::query_scalar("SELECT data FROM coupons WHERE code = ? AND active = 1")
+                    .bind(coupon_code)
+                    .fetch_optional(&self.pool)
+                    .await
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            let Some(coupon_json) = coupon_json else {
+                return Ok(Redemption::Rejected);
+            };
+            let coupon = serde_json::from_str(&coupon_json)
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+            let redemption = redeem_coupon(coupon, challenge_id, redeemed_by);
+            let Redemption::Redeemed(coupon, coupon_redemption) = &redemption else {
+                return Ok(redemption);
+            };
+            let updated_json = serde_json::to_string(coupon)
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            let redemption_json = serde_json::to_string(coupon_redemption)
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+            let mut tx = self.begin().await?;
+
+            // Compare-and-swap: only write if nobody changed the coupon since it was read
+            let result = sqlx::query(
+                "UPDATE coupons SET data = ? WHERE code = ? AND data = ? AND active = 1",
+            )
+            .bind(&updated_json)
+            .bind(coupon_code)
+            .bind(&coupon_json)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            if result.rows_affected() != 1 {
+                continue;
+            }
+
+            sqlx::query("INSERT INTO coupon_redemptions (code, data) VALUES (?, ?)")
+                .bind(coupon_code)
+                .bind(&redemption_json)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+            tx.commit()
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+            return Ok(redemption);
+        }
+    }
+
+    async fn fetch_redemptions(
+        &self,
+        coupon_code: &str,
+    ) -> Result<Vec<CouponRedemption>, RepositoryError> {
+        let redemptions_data: Vec<String> =
+            sqlx::query_scalar("SELECT data FROM coupon_redemptions WHERE code = ? ORDER BY id")
+                .bind(coupon_code)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        redemptions_data
+            .into_iter()
+            .map(|data| {
+                serde_json::from_str(&data)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .collect()
+    }
+
+    async fn fetch_user_redemptions(
+        &self,
+        redeemed_by: &str,
+    ) -> Result<Vec<CouponRedemption>, RepositoryError> {
+        let redemptions_data: Vec<String> = sqlx::query_scalar(
+            "SELECT data FROM coupon_redemptions WHERE json_extract(data, '$.redeemed_by') = ? ORDER BY id",
+        )
+        .bind(redeemed_by)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        redemptions_data
+            .into_iter()
+            .map(|data| {
+                serde_json::from_str(&data)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .collect()
+    }
+
+    async fn fetch_campaign(&self, name: &str) -> Result<Option<CouponCampaign>, RepositoryError> {
+        let campaign_json: Option<String> =
+            sqlx::query_scalar("SELECT data FROM coupon_campaigns WHERE name = ?")
+                .bind(name)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        match campaign_json {
+            Some(json) => {
+                let campaign = serde_json::from_str(&json)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+                Ok(Some(campaign))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn create_campaign(
+        &self,
+        campaign: CouponCampaign,
+    ) -> Result<CouponCampaign, RepositoryError> {
+        let campaign_json = serde_json::to_string(&campaign)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let result = sqlx::query(
+            "INSERT INTO coupon_campaigns (name, data) VALUES (?, ?) ON CONFLICT DO NOTHING",
+        )
+        .bind(&campaign.name)
+        .bind(&campaign_json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::AlreadyExists(campaign.name));
+        }
+        Ok(campaign)
+    }
+
+    async fn save_campaign(
+        &self,
+        campaign: CouponCampaign,
+    ) -> Result<CouponCampaign, RepositoryError> {
+        let campaign_json = serde_json::to_string(&campaign)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        sqlx::query(
+            "INSERT INTO coupon_campaigns (name, data) VALUES (?, ?)
+             ON CONFLICT (name) DO UPDATE SET data = excluded.data",
+        )
+        .bind(&campaign.name)
+        .bind(&campaign_json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        Ok(campaign)
+    }
+
+    async fn delete_campaign(&self, name: &str) -> Result<(), RepositoryError> {
+        let result = sqlx::query("DELETE FROM coupon_campaigns WHERE name = ?")
+            .bind(name)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound(name.to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ClaimRepository for SqliteStorage {
+    async fn create_claim(&self, claim: ClaimRecord) -> Result<ClaimRecord, RepositoryError> {
+        let claim_json = serde_json::to_string(&claim)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let result =
+            sqlx::query("INSERT INTO claims (id, data) VALUES (?, ?) ON CONFLICT DO NOTHING")
+                .bind(claim.id)
+                .bind(&claim_json)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::AlreadyExists(claim.id.to_string()));
+        }
+        Ok(claim)
+    }
+
+    async fn fetch_claim(&self, id: i64) -> Result<Option<ClaimRecord>, RepositoryError> {
+        let claim_json: Option<String> = sqlx::query_scalar("SELECT data FROM claims WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        claim_json
+            .map(|json| {
+                serde_json::from_str(&json)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .transpose()
+    }
+
+    async fn fetch_user_claims(&self, user: &str) -> Result<Vec<ClaimRecord>, RepositoryError> {
+        let claims_data: Vec<String> =
+            sqlx::query_scalar("SELECT data FROM claims WHERE json_extract(data, '$.user') = ?")
+                .bind(user)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        claims_data
+            .into_iter()
+            .map(|data| {
+                serde_json::from_str(&data)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .collect()
+    }
+
+    async fn fetch_queued_claims(&self) -> Result<Vec<ClaimRecord>, RepositoryError> {
+        let claims_data: Vec<String> = sqlx::query_scalar(
+            "SELECT data FROM claims WHERE json_extract(data, '$.status') IN ('queued', 'submitted')",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        claims_data
+            .into_iter()
+            .map(|data| {
+                serde_json::from_str(&data)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .collect()
+    }
+
+    async fn update_claim(&self, claim: ClaimRecord) -> Result<ClaimRecord, RepositoryError> {
+        let claim_json = serde_json::to_string(&claim)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let result = sqlx::query("UPDATE claims SET data = ? WHERE id = ?")
+            .bind(&claim_json)
+            .bind(claim.id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound(claim.id.to_string()));
+        }
+        Ok(claim)
+    }
+}
+
+#[async_trait]
+impl CertificateRepository for SqliteStorage {
+    async fn create_certificate(
+        &self,
+        certificate: CertificateRecord,
+    ) -> Result<CertificateRecord, RepositoryError> {
+        let certificate_json = serde_json::to_string(&certificate)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let result =
+            sqlx::query("INSERT INTO certificates (id, data) VALUES (?, ?) ON CONFLICT DO NOTHING")
+                .bind(&certificate.id)
+                .bind(&certificate_json)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::AlreadyExists(certificate.id));
+        }
+        Ok(certificate)
+    }
+
+    async fn fetch_certificate(
+        &self,
+        id: &str,
+    ) -> Result<Option<CertificateRecord>, RepositoryError> {
+        let certificate_json: Option<String> =
+            sqlx::query_scalar("SELECT data FROM certificates WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        certificate_json
+            .map(|json| {
+                serde_json::from_str(&json)
+                    .map_err(|e| RepositoryError::InternalError(e.to_string()))
+            })
+            .transpose()
+    }
+
+    async fn update_certificate(
+        &self,
+        certificate: CertificateRecord,
+    ) -> Result<CertificateRecord, RepositoryError> {
+        let certificate_json = serde_json::to_string(&certificate)
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        let result = sqlx::query("UPDATE certificates SET data = ? WHERE id = ?")
+            .bind(&certificate_json)
+            .bind(&certificate.id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound(certificate.id));
+        }
+        Ok(certificate)
+    }
+
+    async fn reserve_item_index(
+        &self,
+        collection: &str,
+        next_index: u64,
+    ) -> Result<u64, RepositoryError> {
+        let next_index =
+            i64::try_from(next_index).map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+        let index: i64 = sqlx::query_scalar(
+            "INSERT INTO nft_item_indexes (collection, next_index) VALUES (?, ? + 1)
+             ON CONFLICT (collection) DO UPDATE SET next_index = MAX(next_index, ?) + 1
+             RETURNING next_index - 1",
+        )
+        .bind(collection)
+        .bind(next_index)
+        .bind(next_index)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        Ok(index as u64)
+    }
+}
+
+#[cfg(feature = "chat")]
+#[async_trait]
+impl MessageReceiver for SqliteStorage {
+    async fn receive_messages(&self, channel: &str) -> Result<Vec<Message>, ReceiveError> {
+        let messages_data: Vec<String> =
+            sqlx::query_scalar("SELECT data FROM chat_messages WHERE channel = ? ORDER BY id")
+                .bind(channel)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|err| ReceiveError::InternalError(err.to_string()))?;
+
+        let mut messages = Vec::new();
+        for message_json in messages_data {
+            let message: Message = serde_json::from_str(&message_json)
+                .map_err(|err| ReceiveError::InternalError(err.to_string()))?;
+            messages.push(message);
+        }
+        Ok(messages)
+    }
+}
+
+#[cfg(feature = "chat")]
+#[async_trait]
+impl MessageSender for SqliteStorage {
+    async fn send_message(&self, channel: &str, message: Message) -> Result<(), SendError> {
+        let message_json = serde_json::to_string(&message)
+            .map_err(|err| SendError::InternalError(err.to_string()))?;
+
+        sqlx::query("INSERT INTO chat_messages (channel, data) VALUES (?, ?)")
+            .bind(channel)
+            .bind(&message_json)
+            .execute(&self.pool)
+            .await
+            .map_err(|err| SendError::InternalError(err.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "chat")]
+impl MessageStorage for SqliteStorage {}
+
+#[async_trait]
+impl WindowedCounterRepository for SqliteStorage {
+    async fn get_active_count(&self, namespace: &str) -> Result<u32, RepositoryError> {
+        let min_timestamp = chrono::Utc::now().timestamp() - WINDOW_SECONDS;
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM presence WHERE namespace = ? AND timestamp > ?",
+        )
+        .bind(namespace)
+        .bind(min_timestamp)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        Ok(count as u32)
+    }
+
+    async fn record_presence(&self, namespace: &str) -> Result<u32, RepositoryError> {
+        let timestamp = chrono::Utc::now().timestamp();
+
+        // Remove old entries (older than 24 hours)
+        sqlx::query("DELETE FROM presence WHERE namespace = ? AND timestamp <= ?")
+            .bind(namespace)
+            .bind(timestamp - WINDOW_SECONDS)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        sqlx::query("INSERT INTO presence (namespace, timestamp) VALUES (?, ?)")
+            .bind(namespace)
+            .bind(timestamp)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::InternalError(e.to_string()))?;
+
+        self.get_active_count(namespace).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::conformance::{storage_conformance_tests, PERFORMANCE_RECORDS_LIMIT};
+    use chrono::{Duration, Utc};
+
+    async fn create_storage() -> SqliteStorage {
+        SqliteStorage::new("sqlite::memory:").await.unwrap()
+    }
+
+    async fn conformance_storage() -> Option<SqliteStorage> {
+        Some(create_storage().await)
+    }
+
+    storage_conformance_tests!(conformance_storage);
+
+    #[tokio::test]
+    async fn test_fetch_profile() {
+        let repo = create_storage().await;
+        let profile = PlayerProfile::new("example_user_id".to_string());
+        ProfileRepository::save(&repo, profile.clone())
+            .await
+            .unwrap();
+        let fetched_profile = ProfileRepository::fetch(&repo, "example_user_id".to_string())
+            .await
+            .unwrap();
+        assert_eq!(profile, fetched_profile);
+
+        let missing = ProfileRepository::fetch(&repo, "missing".to_string()).await;
+        assert!(matches!(missing, Err(RepositoryError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_performance_records_limit_and_remove() {
+        let repo = create_storage().await;
+        let namespace = "test";
+
+        for i in 0..PERFORMANCE_RECORDS_LIMIT {
+            let record = PerformanceRecord {
+                profile_name: i.to_string(),
+                performance_percentage: 100,
+                ..Default::default()
+            };
+            repo.add_performance_record(
+                namespace,
+                &i.to_string(),
+                record,
+                PERFORMANCE_RECORDS_LIMIT,
+            )
+            .await
+            .unwrap();
+        }
+
+        let record = PerformanceRecord {
+            profile_name: "overflow".to_string(),
+            ..Default::default()
+        };
+        let result = repo
+            .add_performance_record(
+                namespace,
+                "overflow",
+                record.clone(),
+                PERFORMANCE_RECORDS_LIMIT,
+            )
+            .await;
+        assert_eq!(
+            result,
+            Err(RepositoryError::LimitReached(PERFORMANCE_RECORDS_LIMIT))
+        );
+
+        // Other namespaces are not affected by a full board
+        repo.add_performance_record("other", "overflow", record, PERFORMANCE_RECORDS_LIMIT)
+            .await
+            .unwrap();
+
+        let records = repo.fetch_performance_records(namespace).await.unwrap();
+        assert_eq!(records.len(), PERFORMANCE_RECORDS_LIMIT);
+
+        repo.remove_performance_record(namespace, records[0].clone())
+            .await
+            .unwrap();
+        let records = repo.fetch_performance_records(namespace).await.unwrap();
+        assert_eq!(records.len(), PERFORMANCE_RECORDS_LIMIT - 1);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_average_rating() {
+        let repo = create_storage().await;
+        let review1 = Review {
+            challenge_id: "example_challenge_id".to_string(),
+            rating: 5,
+            comment: None,
+        };
+        let review2 = Review {
+            challenge_id: "example_challenge_id".to_string(),
+            rating: 3,
+            comment: None,
+        };
+        repo.store_review(review1.clone()).await.unwrap();
+        repo.store_review(review2.clone()).await.unwrap();
+
+        let reviews = ReviewRepository::fetch_reviews(&repo, "example_challenge_id")
+            .await
+            .unwrap();
+        assert_eq!(reviews, vec![review1, review2]);
+
+        let average_rating = ReviewRepository::fetch_average_rating(&repo, "example_challenge_id")
+            .await
+            .unwrap();
+        assert_eq!(average_rating, 4.0);
+    }
+
+    #[tokio::test]
+    async fn test_save_and_fetch_coupon() {
+        let repo = create_storage().await;
+        let coupon = Coupon::new(
+            "TESTCOUPON".to_string(),
+            vec!["challenge1".to_string()],
+            1,
+            Utc::now() + Duration::days(7),
+        );
+        CouponRepository::save(&repo, coupon.clone()).await.unwrap();
+        let fetched_coupon = CouponRepository::fetch(&repo, "TESTCOUPON")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetched_coupon, coupon);
+        assert_eq!(CouponRepository::fetch_all(&repo).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_windowed_counter() {
+        let repo = create_storage().await;
+        let namespace = "test_challenge";
+
+        assert_eq!(repo.get_active_count(namespace).await.unwrap(), 0);
+        assert_eq!(repo.record_presence(namespace).await.unwrap(), 1);
+        repo.record_presence(namespace).await.unwrap();
+        assert_eq!(repo.record_presence(namespace).await.unwrap(), 3);
+        assert_eq!(repo.get_active_count("other_namespace").await.unwrap(), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_submissions_to_a_file_database() {
+        // In-memory databases share one connection, a file uses the whole pool
+        let path = std::env::temp_dir().join(format!("konnektoren-{}.db", uuid::Uuid::new_v4()));
+        let url = format!("sqlite://{}", path.display());
+        let repo = std::sync::Arc::new(SqliteStorage::new(&url).await.unwrap());
+        let capacity = 5;
+
+        let handles: Vec<_> = (0..20)
+            .map(|i| {
+                let repo = repo.clone();
+                tokio::spawn(async move {
+                    let record = PerformanceRecord {
+                        profile_name: i.to_string(),
+                        performance_percentage: i as u8,
+                        ..Default::default()
+                    };
+                    repo.add_performance_record("test", &i.to_string(), record, capacity)
+                        .await
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            match handle.await.unwrap() {
+                Ok(_) | Err(RepositoryError::LimitReached(_)) => {}
+                Err(err) => panic!("submission failed: {:?}", err),
+            }
+        }
+        let records = repo.fetch_performance_records("test").await.unwrap();
+        assert_eq!(records.len(), capacity);
+
+        drop(repo);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,22 +1,35 @@
-use anyhow::Result;
-use tonlib::client::{ConnectionCheck, TonClient, TonClientBuilder, TonConnectionParams};
-//use tonlib::config::TESTNET_CONFIG;
+use crate::ton::config::{TonConfig, TonNetwork};
+use anyhow::{Context, Result};
+use tonlib::client::{TonClient, TonClientBuilder, TonConnectionParams};
+use tonlib::config::MAINNET_CONFIG;
 pub const TESTNET_CONFIG: &str = include_str!("./testnet-global.config.json");
 
-pub async fn create_testnet_client() -> Result<TonClient> {
+/// Creates a client for the network of `config`, shared by all requests.
+pub async fn create_client(config: &TonConfig) -> Result<TonClient> {
+    let global_config = match &config.config_path {
+        Some(path) => std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read TON config {}", path.display()))?,
+        None => match config.network {
+            TonNetwork::Mainnet => MAINNET_CONFIG.to_string(),
+            TonNetwork::Testnet => TESTNET_CONFIG.to_string(),
+        },
+    };
     let params = TonConnectionParams {
-        config: TESTNET_CONFIG.to_string(),
+        config: global_config,
         ..Default::default()
     };
     TonClient::set_log_verbosity_level(1);
     let client = TonClientBuilder::new()
         .with_connection_params(&params)
-        .with_pool_size(2)
+        .with_pool_size(config.pool_size as usize)
         .with_logging_callback()
-        .with_keystore_dir("./var/ton/testnet".to_string())
-        //.with_connection_check(ConnectionCheck::Archive)
+        .with_keystore_dir(config.keystore_dir.clone())
         .build()
         .await?;
-    log::debug!("Client created");
+    log::info!(
+        "TON client for {} created with {} connections",
+        config.network,
+        config.pool_size
+    );
     Ok(client)
 }
@@ -0,0 +1,154 @@
+use crate::config::{setting, ConfigError};
+use std::fmt;
+use std::path::PathBuf;
+use thiserror::Error;
+use tonlib::wallet::WalletVersion;
+
+pub const TON_NETWORK_VAR: &str = "TON_NETWORK";
+pub const TON_CONFIG_PATH_VAR: &str = "TON_CONFIG_PATH";
+pub const TON_POOL_SIZE_VAR: &str = "TON_POOL_SIZE";
+pub const TON_KEYSTORE_DIR_VAR: &str = "TON_KEYSTORE_DIR";
+pub const TON_WALLET_VERSION_VAR: &str = "TON_WALLET_VERSION";
+
+const DEFAULT_POOL_SIZE: u32 = 2;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum TonConfigError {
+    #[error("Unknown TON network '{0}', expected mainnet or testnet")]
+    UnknownNetwork(String),
+    #[error("Unsupported wallet version '{0}', expected one of: v3r1, v3r2, v4r1, v4r2")]
+    UnknownWalletVersion(String),
+    #[error(transparent)]
+    Setting(#[from] ConfigError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TonNetwork {
+    Mainnet,
+    Testnet,
+}
+
+impl fmt::Display for TonNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TonNetwork::Mainnet => write!(f, "mainnet"),
+            TonNetwork::Testnet => write!(f, "testnet"),
+        }
+    }
+}
+
+/// Connection settings of the TON client, selected with the `TON_*` variables.
+#[derive(Debug, Clone)]
+pub struct TonConfig {
+    pub network: TonNetwork,
+    /// Global config file to use instead of the one bundled for `network`.
+    pub config_path: Option<PathBuf>,
+    pub pool_size: u32,
+    pub keystore_dir: String,
+    /// Version of the faucet wallet contract.
+    pub wallet_version: WalletVersion,
+}
+
+impl TonConfig {
+    pub fn from_env() -> Result<Self, TonConfigError> {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    /// Builds the config from a variable lookup, defaults to a testnet V4R2 wallet.
+    pub fn from_vars<F>(var: F) -> Result<Self, TonConfigError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let var = |key: &str| var(key).filter(|value| !value.trim().is_empty());
+
+        let network = match var(TON_NETWORK_VAR).map(|value| value.trim().to_lowercase()) {
+            None => TonNetwork::Testnet,
+            Some(network) => match network.as_str() {
+                "mainnet" => TonNetwork::Mainnet,
+                "testnet" => TonNetwork::Testnet,
+                _ => return Err(TonConfigError::UnknownNetwork(network)),
+            },
+        };
+
+        let pool_size = setting(&var, TON_POOL_SIZE_VAR, DEFAULT_POOL_SIZE)?;
+        if pool_size == 0 {
+            return Err(
+                ConfigError::InvalidSetting(TON_POOL_SIZE_VAR, pool_size.to_string()).into(),
+            );
+        }
+
+        let wallet_version = match var(TON_WALLET_VERSION_VAR) {
+            None => WalletVersion::V4R2,
+            Some(version) => match version.trim().to_lowercase().as_str() {
+                "v3r1" => WalletVersion::V3R1,
+                "v3r2" => WalletVersion::V3R2,
+                "v4r1" => WalletVersion::V4R1,
+                "v4r2" => WalletVersion::V4R2,
+                _ => return Err(TonConfigError::UnknownWalletVersion(version)),
+            },
+        };
+
+        Ok(Self {
+            network,
+            config_path: var(TON_CONFIG_PATH_VAR).map(PathBuf::from),
+            pool_size,
+            keystore_dir: var(TON_KEYSTORE_DIR_VAR)
+                .unwrap_or_else(|| format!("./var/ton/{}", network)),
+            wallet_version,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_from_vars() {
+        let config = TonConfig::from_vars(|_| None).unwrap();
+        assert_eq!(config.network, TonNetwork::Testnet);
+        assert_eq!(config.pool_size, DEFAULT_POOL_SIZE);
+        assert_eq!(config.keystore_dir, "./var/ton/testnet");
+        assert_eq!(config.config_path, None);
+
+        let config = TonConfig::from_vars(|key| match key {
+            TON_NETWORK_VAR => Some("Mainnet".to_string()),
+            TON_POOL_SIZE_VAR => Some("4".to_string()),
+            TON_CONFIG_PATH_VAR => Some("/etc/ton/global.config.json".to_string()),
+            TON_WALLET_VERSION_VAR => Some("v3r2".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(config.network, TonNetwork::Mainnet);
+        assert_eq!(config.pool_size, 4);
+        assert_eq!(config.keystore_dir, "./var/ton/mainnet");
+        assert_eq!(
+            config.config_path,
+            Some(PathBuf::from("/etc/ton/global.config.json"))
+        );
+        assert!(matches!(config.wallet_version, WalletVersion::V3R2));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let result = TonConfig::from_vars(|key| (key == TON_NETWORK_VAR).then(|| "devnet".into()));
+        assert_eq!(
+            result.unwrap_err(),
+            TonConfigError::UnknownNetwork("devnet".to_string())
+        );
+        let result = TonConfig::from_vars(|key| (key == TON_POOL_SIZE_VAR).then(|| "0".into()));
+        assert!(matches!(
+            result,
+            Err(TonConfigError::Setting(ConfigError::InvalidSetting(
+                TON_POOL_SIZE_VAR,
+                _
+            )))
+        ));
+        let result =
+            TonConfig::from_vars(|key| (key == TON_WALLET_VERSION_VAR).then(|| "v9".into()));
+        assert!(matches!(
+            result,
+            Err(TonConfigError::UnknownWalletVersion(_))
+        ));
+    }
+}
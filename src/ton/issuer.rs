@@ -0,0 +1,402 @@
+use crate::issuer::{JettonInfo, TokenIssuer, TokenIssuerError};
+use crate::ton::{create_client, create_key_pair, TonConfig};
+use anyhow::Context;
+use async_trait::async_trait;
+use base64::prelude::*;
+use std::env;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tonlib::address::TonAddress;
+use tonlib::cell::{ArcCell, BagOfCells, Cell, CellBuilder};
+use tonlib::client::{TonClient, TonClientInterface};
+use tonlib::contract::{
+    JettonMasterContract, JettonWalletContract, NftCollectionContract, TonContractFactory,
+    TonWalletContract,
+};
+use tonlib::message::TransferMessage;
+use tonlib::meta::{JettonMetaData, JettonMetaLoader, LoadMeta};
+use tonlib::mnemonic::KeyPair;
+use tonlib::wallet::{TonWallet, WalletVersion};
+
+/// TON attached to every transfer to pay the jetton wallet fees, in nanotons.
+const TRANSFER_TON_AMOUNT: u32 = 1_000_000_000;
+/// Base units per jetton, the jetton has 9 decimals.
+const JETTON_UNIT: u128 = 1_000_000_000;
+/// Seconds a signed message stays valid.
+const MESSAGE_TTL_SECONDS: u32 = 60;
+/// TON attached to a mint to pay the collection fees, in nanotons.
+const MINT_TON_AMOUNT: u32 = 50_000_000;
+/// TON the collection forwards to a new item for its storage, in nanotons.
+const NFT_ITEM_TON_AMOUNT: u32 = 20_000_000;
+/// Op of the standard NFT collection to deploy a single item.
+const DEPLOY_NFT_ITEM_OP: u32 = 1;
+/// Transactions of the faucet wallet read at once when looking for a message.
+const TRANSACTIONS_PER_PAGE: usize = 16;
+/// Pages of recent transactions looked through for a message.
+const TRANSACTION_PAGES: usize = 4;
+
+/// Issues jettons of `jetton_master` from the wallet of a mnemonic through tonlib.
+pub struct TonTokenIssuer {
+    client: TonClient,
+    factory: TonContractFactory,
+    wallet: TonWallet,
+    jetton_master: TonAddress,
+    nft_collection: Option<TonAddress>,
+}
+
+impl TonTokenIssuer {
+    pub async fn new(
+        client: TonClient,
+        key_pair: &KeyPair,
+        wallet_version: WalletVersion,
+        jetton_master: &str,
+    ) -> anyhow::Result<Self> {
+        let wallet = TonWallet::derive_default(wallet_version, key_pair)?;
+        let jetton_master = TonAddress::from_base64_url(jetton_master)?;
+        let factory = TonContractFactory::builder(&client).build().await?;
+        Ok(Self {
+            client,
+            factory,
+            wallet,
+            jetton_master,
+            nft_collection: None,
+        })
+    }
+
+    /// Mints certificates in `collection`, which must be owned by the faucet wallet.
+    pub fn with_nft_collection(mut self, collection: &str) -> anyhow::Result<Self> {
+        self.nft_collection = Some(TonAddress::from_base64_url(collection)?);
+        Ok(self)
+    }
+
+    /// Connects to the network of `config` with the wallet of `MNEMONIC` and
+    /// the jetton `CONTRACT_ADDRESS`.
+    ///
+    /// If `FAUCET_ADDRESS` is set, the wallet derived from the mnemonic must have
+    /// that address, which catches a wrong mnemonic or wallet version at startup.
+    /// Certificates are minted in the collection `NFT_COLLECTION_ADDRESS`, if set.
+    pub async fn from_env(config: &TonConfig) -> anyhow::Result<Self> {
+        let mnemonic = env::var("MNEMONIC").context("MNEMONIC is not set")?;
+        let contract_address =
+            env::var("CONTRACT_ADDRESS").context("CONTRACT_ADDRESS is not set")?;
+        let faucet_address = env::var("FAUCET_ADDRESS")
+            .ok()
+            .filter(|address| !address.trim().is_empty())
+            .map(|address| {
+                TonAddress::from_base64_url(address.trim())
+                    .with_context(|| format!("FAUCET_ADDRESS {} is not an address", address))
+            })
+            .transpose()?;
+        TonAddress::from_base64_url(&contract_address)
+            .with_context(|| format!("CONTRACT_ADDRESS {} is not an address", contract_address))?;
+        let nft_collection = env::var("NFT_COLLECTION_ADDRESS")
+            .ok()
+            .filter(|address| !address.trim().is_empty());
+        let key_pair = create_key_pair(&mnemonic)
+            .await
+            .context("MNEMONIC is not a valid mnemonic")?;
+
+        let client = create_client(config).await?;
+        let mut issuer =
+            Self::new(client, &key_pair, config.wallet_version, &contract_address).await?;
+        if let Some(collection) = nft_collection {
+            issuer = issuer
+                .with_nft_collection(collection.trim())
+                .with_context(|| {
+                    format!("NFT_COLLECTION_ADDRESS {} is not an address", collection)
+                })?;
+        }
+        if let Some(faucet_address) = faucet_address {
+            anyhow::ensure!(
+                faucet_address == issuer.wallet.address,
+                "MNEMONIC derives the {:?} wallet {}, not FAUCET_ADDRESS {}",
+                config.wallet_version,
+                issuer.wallet_address(),
+                faucet_address.to_base64_url()
+            );
+        }
+        Ok(issuer)
+    }
+
+    fn parse_address(address: &str) -> Result<TonAddress, TokenIssuerError> {
+        TonAddress::from_base64_url(address)
+            .map_err(|_| TokenIssuerError::InvalidAddress(address.to_string()))
+    }
+
+    /// Body of a jetton transfer from the faucet wallet to `receiver`.
+    fn transfer_cell(&self, receiver: &TonAddress, amount: u128) -> anyhow::Result<Cell> {
+        let forward_payload: ArcCell = CellBuilder::new()
+            .store_uint(32, &0u32.into())?
+            .store_string("Claim")?
+            .build()?
+            .to_arc();
+
+        let transfer_cell = CellBuilder::new()
+            .store_uint(32, &0xf8a7ea5u32.into())?
+            .store_uint(64, &0u32.into())?
+            .store_coins(&(amount * JETTON_UNIT).into())?
+            .store_address(receiver)?
+            .store_address(&self.wallet.address)?
+            .store_bit(false)?
+            .store_coins(&1u32.into())?
+            .store_bit(true)?
+            .store_reference(&forward_payload)?
+            .build()?;
+        Ok(transfer_cell)
+    }
+
+    /// Body of a mint of item `item_index` for `owner` in the standard NFT collection.
+    ///
+    /// The content of the item is `content_url`, the collection must have an empty
+    /// common content prefix for it to resolve.
+    fn mint_cell(owner: &TonAddress, item_index: u64, content_url: &str) -> anyhow::Result<Cell> {
+        let content: ArcCell = CellBuilder::new()
+            .store_string(content_url)?
+            .build()?
+            .to_arc();
+        let item: ArcCell = CellBuilder::new()
+            .store_address(owner)?
+            .store_reference(&content)?
+            .build()?
+            .to_arc();
+
+        let mint_cell = CellBuilder::new()
+            .store_uint(32, &DEPLOY_NFT_ITEM_OP.into())?
+            .store_uint(64, &0u32.into())?
+            .store_uint(64, &item_index.into())?
+            .store_coins(&NFT_ITEM_TON_AMOUNT.into())?
+            .store_reference(&item)?
+            .build()?;
+        Ok(mint_cell)
+    }
+
+    /// Signs a message of the faucet wallet sending `ton_amount` nanotons and `body` to `destination`.
+    fn sign(
+        &self,
+        destination: &TonAddress,
+        ton_amount: u32,
+        body: Cell,
+        seqno: u32,
+    ) -> anyhow::Result<Vec<u8>> {
+        let transfer = TransferMessage::new(destination, &ton_amount.into())
+            .with_data(body)
+            .build()?;
+        let transfer_cells: Vec<Arc<Cell>> = vec![Arc::new(transfer)];
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as u32;
+        let body =
+            self.wallet
+                .create_external_body(now + MESSAGE_TTL_SECONDS, seqno, transfer_cells)?;
+        let signed = self.wallet.sign_external_body(&body)?;
+        let wrapped = self.wallet.wrap_signed_body(signed, false)?;
+        Ok(BagOfCells::from_root(wrapped).serialize(true)?)
+    }
+}
+
+#[async_trait]
+impl TokenIssuer for TonTokenIssuer {
+    fn wallet_address(&self) -> String {
+        self.wallet.address.to_base64_url()
+    }
+
+    fn validate_address(&self, address: &str) -> Result<(), TokenIssuerError> {
+        Self::parse_address(address).map(|_| ())
+    }
+
+    async fn ton_balance(&self) -> Result<u128, TokenIssuerError> {
+        let account_state = self
+            .client
+            .get_account_state(&self.wallet.address)
+            .await
+            .map_err(|e| TokenIssuerError::Network(e.to_string()))?;
+        Ok(account_state.balance.max(0) as u128)
+    }
+
+    async fn jetton_balance_of(&self, owner: &str) -> Result<u128, TokenIssuerError> {
+        let jetton_wallet = self.jetton_wallet(owner).await?;
+        let balance = self
+            .factory
+            .get_contract(&Self::parse_address(&jetton_wallet)?)
+            .get_wallet_data()
+            .await
+            .map_err(|e| TokenIssuerError::Network(e.to_string()))?
+            .balance;
+        Ok(u128::try_from(balance / JETTON_UNIT).unwrap_or(u128::MAX))
+    }
+
+    async fn jetton_info(&self) -> Result<JettonInfo, TokenIssuerError> {
+        let jetton_data = self
+            .factory
+            .get_contract(&self.jetton_master)
+            .get_jetton_data()
+            .await
+            .map_err(|e| TokenIssuerError::Network(e.to_string()))?;
+
+        // Metadata is optional, the jetton data is still useful without it
+        let metadata = match JettonMetaLoader::default() {
+            Ok(loader) => loader.load(&jetton_data.content).await.ok(),
+            Err(_) => None,
+        };
+        if metadata.is_none() {
+            log::warn!(
+                "Failed to load the metadata of jetton {}",
+                self.jetton_master
+            );
+        }
+        let field = |field: fn(&JettonMetaData) -> &Option<String>| {
+            metadata
+                .as_ref()
+                .and_then(|metadata| field(metadata).clone())
+        };
+
+        Ok(JettonInfo {
+            address: self.jetton_master.to_base64_url(),
+            name: field(|metadata| &metadata.name),
+            symbol: field(|metadata| &metadata.symbol),
+            description: field(|metadata| &metadata.description),
+            image: field(|metadata| &metadata.image),
+            decimals: field(|metadata| &metadata.decimals)
+                .and_then(|decimals| decimals.parse().ok()),
+            total_supply: u128::try_from(jetton_data.total_supply / JETTON_UNIT)
+                .unwrap_or(u128::MAX),
+            mintable: jetton_data.mintable,
+            admin_address: Some(jetton_data.admin_address.to_base64_url()),
+        })
+    }
+
+    async fn seqno(&self) -> Result<u32, TokenIssuerError> {
+        self.factory
+            .get_contract(&self.wallet.address)
+            .seqno()
+            .await
+            .map_err(|e| TokenIssuerError::Network(e.to_string()))
+    }
+
+    async fn jetton_wallet(&self, owner: &str) -> Result<String, TokenIssuerError> {
+        let owner = Self::parse_address(owner)?;
+        let jetton_wallet = self
+            .factory
+            .get_contract(&self.jetton_master)
+            .get_wallet_address(&owner)
+            .await
+            .map_err(|e| TokenIssuerError::Network(e.to_string()))?;
+        Ok(jetton_wallet.to_base64_url())
+    }
+
+    async fn sign_transfer(
+        &self,
+        receiver: &str,
+        amount: u128,
+        seqno: u32,
+    ) -> Result<Vec<u8>, TokenIssuerError> {
+        let receiver_address = Self::parse_address(receiver)?;
+        let jetton_wallet = self.jetton_wallet(&self.wallet_address()).await?;
+        let jetton_wallet = Self::parse_address(&jetton_wallet)?;
+        log::info!(
+            "Signing transfer of {} jettons to {} with seqno {}",
+            amount,
+            receiver,
+            seqno
+        );
+
+        self.transfer_cell(&receiver_address, amount)
+            .and_then(|transfer_cell| {
+                self.sign(&jetton_wallet, TRANSFER_TON_AMOUNT, transfer_cell, seqno)
+            })
+            .map_err(|e| TokenIssuerError::Signing(e.to_string()))
+    }
+
+    async fn send_message(&self, message: &[u8]) -> Result<String, TokenIssuerError> {
+        log::info!("Sending raw message: {:?}", BASE64_STANDARD.encode(message));
+        match self.client.send_raw_message_return_hash(message).await {
+            Ok(hash) => {
+                let tx_hash = hex::encode(hash);
+                log::info!("Transaction hash: {}", tx_hash);
+                Ok(tx_hash)
+            }
+            Err(err) => {
+                log::error!("Failed to send raw message: {:?}", err);
+                Err(TokenIssuerError::Network(format!(
+                    "Failed to send raw message: {:?}",
+                    err
+                )))
+            }
+        }
+    }
+
+    async fn message_processed(&self, message: &[u8]) -> Result<bool, TokenIssuerError> {
+        let hash = BagOfCells::parse(message)
+            .and_then(|boc| boc.single_root()?.cell_hash())
+            .map_err(|e| TokenIssuerError::Signing(e.to_string()))?;
+        let account_state = self
+            .client
+            .get_account_state(&self.wallet.address)
+            .await
+            .map_err(|e| TokenIssuerError::Network(e.to_string()))?;
+
+        let mut from = account_state.last_transaction_id;
+        for _ in 0..TRANSACTION_PAGES {
+            let page = self
+                .client
+                .get_raw_transactions_v2(&self.wallet.address, &from, TRANSACTIONS_PER_PAGE, false)
+                .await
+                .map_err(|e| TokenIssuerError::Network(e.to_string()))?;
+            let found = page.transactions.iter().any(|transaction| {
+                transaction
+                    .in_msg
+                    .as_ref()
+                    .is_some_and(|in_msg| in_msg.hash.as_slice() == hash.as_slice())
+            });
+            if found {
+                return Ok(true);
+            }
+            if page.transactions.len() < TRANSACTIONS_PER_PAGE {
+                break;
+            }
+            from = page.previous_transaction_id;
+        }
+        Ok(false)
+    }
+
+    fn nft_collection(&self) -> Option<String> {
+        self.nft_collection
+            .as_ref()
+            .map(|collection| collection.to_base64_url())
+    }
+
+    async fn next_nft_index(&self) -> Result<u64, TokenIssuerError> {
+        let collection = self.nft_collection.as_ref().ok_or_else(no_nft_collection)?;
+        let collection_data = self
+            .factory
+            .get_contract(collection)
+            .get_collection_data()
+            .await
+            .map_err(|e| TokenIssuerError::Network(e.to_string()))?;
+        Ok(collection_data.next_item_index.max(0) as u64)
+    }
+
+    async fn sign_nft_mint(
+        &self,
+        owner: &str,
+        item_index: u64,
+        content_url: &str,
+        seqno: u32,
+    ) -> Result<Vec<u8>, TokenIssuerError> {
+        let collection = self.nft_collection.as_ref().ok_or_else(no_nft_collection)?;
+        let owner_address = Self::parse_address(owner)?;
+        log::info!(
+            "Signing mint of NFT {} for {} with seqno {}",
+            item_index,
+            owner,
+            seqno
+        );
+
+        Self::mint_cell(&owner_address, item_index, content_url)
+            .and_then(|mint_cell| self.sign(collection, MINT_TON_AMOUNT, mint_cell, seqno))
+            .map_err(|e| TokenIssuerError::Signing(e.to_string()))
+    }
+}
+
+fn no_nft_collection() -> TokenIssuerError {
+    TokenIssuerError::Signing("NFT_COLLECTION_ADDRESS is not set".to_string())
+}
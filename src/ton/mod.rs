@@ -5,12 +5,14 @@ use tonlib::contract::JettonMasterContract;
 use tonlib::contract::TonContractFactory;
 use tonlib::mnemonic::{KeyPair, Mnemonic};
 
-mod claim;
 mod client;
+mod config;
+mod issuer;
 mod transfer;
 
-pub use claim::{generate_signed_message, send_jetton};
-pub use client::create_testnet_client;
+pub use client::create_client;
+pub use config::{TonConfig, TonConfigError, TonNetwork};
+pub use issuer::TonTokenIssuer;
 pub use transfer::transfer_jetton_token;
 
 pub async fn create_key_pair(mnemonic_str: &str) -> anyhow::Result<KeyPair> {